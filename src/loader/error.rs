@@ -0,0 +1,65 @@
+use std::{error::Error, fmt::Display, io};
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    Parse {
+        path: String,
+        line: usize,
+        message: String,
+    },
+    Invalid {
+        path: String,
+        message: String,
+    },
+}
+
+impl LoadError {
+    pub fn parse(path: &str, line: usize, message: impl Into<String>) -> LoadError {
+        LoadError::Parse {
+            path: path.to_owned(),
+            line,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid(path: &str, message: impl Into<String>) -> LoadError {
+        LoadError::Invalid {
+            path: path.to_owned(),
+            message: message.into(),
+        }
+    }
+
+    pub fn io(path: &str, source: io::Error) -> LoadError {
+        LoadError::Io {
+            path: path.to_owned(),
+            source,
+        }
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io { path, source } => write!(f, "{}: {}", path, source),
+            LoadError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path, line, message),
+            LoadError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io { source, .. } => Some(source),
+            LoadError::Parse { .. } | LoadError::Invalid { .. } => None,
+        }
+    }
+}
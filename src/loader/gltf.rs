@@ -0,0 +1,1043 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde_json::Value;
+
+use crate::{
+    material::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian,
+        material::Material, metal::Metal, principled::Principled,
+    },
+    model::{
+        camera::Camera, hit::HittableList, matrix::Mat4, quaternion::Quaternion, sphere::Sphere,
+        triangle_mesh::TriangleMesh, vec3::Vec3,
+    },
+    texture::{image::ImageTexture, solid_color::SolidColor, texture::Texture},
+    util::rtweekend::PI,
+};
+
+use super::error::LoadError;
+
+use Vec3 as Point3;
+
+const GLB_MAGIC: u32 = 0x4654_6c67;
+const GLB_CHUNK_JSON: u32 = 0x4e4f_534a;
+const GLB_CHUNK_BIN: u32 = 0x004e_4942;
+
+// Point lights become small emissive spheres of this radius (in scene units).
+const POINT_LIGHT_RADIUS: f64 = 0.05;
+
+pub struct GltfScene {
+    pub world: HittableList,
+    pub cameras: Vec<Camera>,
+    // What could not be loaded, such as unsupported cameras and lights
+    pub warnings: Vec<String>,
+}
+
+struct Document<'a> {
+    json: Value,
+    buffers: Vec<Vec<u8>>,
+    path: &'a str,
+    base_dir: PathBuf,
+    materials: HashMap<usize, Arc<dyn Material + Sync + Send>>,
+    default_material: Arc<dyn Material + Sync + Send>,
+    aspect_ratio: f64,
+}
+
+// Loads the default scene of a .gltf or .glb file. Node transforms are baked into
+// the mesh vertices. Cameras without an aspect ratio use `aspect_ratio`.
+pub fn load_gltf(path: &str, aspect_ratio: f64) -> Result<GltfScene, LoadError> {
+    let data = fs::read(path).map_err(|e| LoadError::io(path, e))?;
+    parse_gltf(&data, path, aspect_ratio)
+}
+
+// External buffers and images are looked up next to `path`.
+pub fn parse_gltf(data: &[u8], path: &str, aspect_ratio: f64) -> Result<GltfScene, LoadError> {
+    let base_dir = Path::new(path)
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .to_path_buf();
+
+    let (json, embedded) = if data.len() >= 4 && read_u32(data, 0) == GLB_MAGIC {
+        split_glb(data, path)?
+    } else {
+        (data, None)
+    };
+
+    let json: Value = serde_json::from_slice(json)
+        .map_err(|e| LoadError::parse(path, e.line(), e.to_string()))?;
+
+    let version = json["asset"]["version"].as_str().unwrap_or("");
+    if !version.starts_with("2.") {
+        return Err(LoadError::invalid(
+            path,
+            format!("unsupported glTF version '{}'", version),
+        ));
+    }
+
+    let mut doc = Document {
+        json,
+        buffers: Vec::new(),
+        path,
+        base_dir,
+        materials: HashMap::new(),
+        default_material: Arc::new(Lambertian::new(&Vec3::new(0.8, 0.8, 0.8))),
+        aspect_ratio,
+    };
+    doc.buffers = doc.load_buffers(embedded)?;
+
+    let roots: Vec<usize> = match doc.json["scene"].as_u64() {
+        Some(scene) => doc.indices(&doc.json["scenes"][scene as usize]["nodes"]),
+        None if doc.json["scenes"].get(0).is_some() => doc.indices(&doc.json["scenes"][0]["nodes"]),
+        // No scenes at all: every node that is nobody's child is a root.
+        None => {
+            let node_count = doc.array("nodes").len();
+            let children: Vec<usize> = doc
+                .array("nodes")
+                .iter()
+                .flat_map(|n| doc.indices(&n["children"]))
+                .collect();
+            (0..node_count).filter(|i| !children.contains(i)).collect()
+        }
+    };
+
+    let mut scene = GltfScene {
+        world: HittableList::new(),
+        cameras: Vec::new(),
+        warnings: Vec::new(),
+    };
+    for root in roots {
+        doc.visit_node(root, &Mat4::identity(), &mut scene, 0)?;
+    }
+
+    Ok(scene)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+// Returns the JSON chunk and the optional BIN chunk of a binary glTF.
+fn split_glb<'a>(data: &'a [u8], path: &str) -> Result<(&'a [u8], Option<&'a [u8]>), LoadError> {
+    if data.len() < 20 || read_u32(data, 4) != 2 {
+        return Err(LoadError::invalid(path, "unsupported GLB header"));
+    }
+    let length = (read_u32(data, 8) as usize).min(data.len());
+
+    let mut json = None;
+    let mut bin = None;
+    let mut offset = 12;
+    while offset + 8 <= length {
+        let chunk_length = read_u32(data, offset) as usize;
+        let chunk_type = read_u32(data, offset + 4);
+        let start = offset + 8;
+        if start + chunk_length > length {
+            return Err(LoadError::invalid(
+                path,
+                format!("GLB chunk at byte {} runs past the end of the file", offset),
+            ));
+        }
+
+        match chunk_type {
+            GLB_CHUNK_JSON => json = Some(&data[start..start + chunk_length]),
+            GLB_CHUNK_BIN => bin = Some(&data[start..start + chunk_length]),
+            _ => {}
+        }
+        offset = start + chunk_length;
+    }
+
+    match json {
+        Some(json) => Ok((json, bin)),
+        None => Err(LoadError::invalid(path, "GLB file has no JSON chunk")),
+    }
+}
+
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    };
+
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+    for c in input
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b'=')
+    {
+        bits = (bits << 6) | value(c)? as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            out.push((bits >> bit_count) as u8);
+        }
+    }
+
+    Some(out)
+}
+
+fn vec3_or(value: &Value, default: Vec3) -> Vec3 {
+    match value.as_array() {
+        Some(a) if a.len() >= 3 => Vec3::new(
+            a[0].as_f64().unwrap_or(0.0),
+            a[1].as_f64().unwrap_or(0.0),
+            a[2].as_f64().unwrap_or(0.0),
+        ),
+        _ => default,
+    }
+}
+
+impl<'a> Document<'a> {
+    fn array(&self, name: &str) -> &[Value] {
+        self.json[name]
+            .as_array()
+            .map(|a| a.as_slice())
+            .unwrap_or(&[])
+    }
+
+    fn indices(&self, value: &Value) -> Vec<usize> {
+        value
+            .as_array()
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_u64())
+                    .map(|v| v as usize)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn get(&self, collection: &str, index: usize) -> Result<&Value, LoadError> {
+        self.array(collection).get(index).ok_or_else(|| {
+            LoadError::invalid(
+                self.path,
+                format!("{}[{}] does not exist", collection, index),
+            )
+        })
+    }
+
+    fn resolve_uri(&self, uri: &str) -> Result<Vec<u8>, LoadError> {
+        if let Some(rest) = uri.strip_prefix("data:") {
+            let encoded = match rest.split_once(";base64,") {
+                Some((_, encoded)) => encoded,
+                None => {
+                    return Err(LoadError::invalid(
+                        self.path,
+                        "only base64 data URIs are supported",
+                    ))
+                }
+            };
+            return decode_base64(encoded)
+                .ok_or_else(|| LoadError::invalid(self.path, "malformed base64 data URI"));
+        }
+
+        let file = self.base_dir.join(uri.replace("%20", " "));
+        let file_name = file.to_string_lossy().into_owned();
+        fs::read(&file).map_err(|e| LoadError::io(&file_name, e))
+    }
+
+    fn load_buffers(&self, embedded: Option<&[u8]>) -> Result<Vec<Vec<u8>>, LoadError> {
+        let mut buffers = Vec::new();
+        for (i, buffer) in self.array("buffers").iter().enumerate() {
+            let bytes = match buffer["uri"].as_str() {
+                Some(uri) => self.resolve_uri(uri)?,
+                // A buffer without uri refers to the GLB binary chunk.
+                None => match embedded {
+                    Some(bin) => bin.to_vec(),
+                    None => {
+                        return Err(LoadError::invalid(
+                            self.path,
+                            format!("buffers[{}] has no uri and there is no GLB chunk", i),
+                        ))
+                    }
+                },
+            };
+
+            let length = buffer["byteLength"].as_u64().unwrap_or(0) as usize;
+            if bytes.len() < length {
+                return Err(LoadError::invalid(
+                    self.path,
+                    format!(
+                        "buffers[{}] is {} bytes, expected {}",
+                        i,
+                        bytes.len(),
+                        length
+                    ),
+                ));
+            }
+            buffers.push(bytes);
+        }
+
+        Ok(buffers)
+    }
+
+    fn buffer_view(&self, index: usize) -> Result<(&[u8], Option<usize>), LoadError> {
+        let view = self.get("bufferViews", index)?;
+        let buffer = view["buffer"].as_u64().unwrap_or(0) as usize;
+        let offset = view["byteOffset"].as_u64().unwrap_or(0) as usize;
+        let length = view["byteLength"].as_u64().unwrap_or(0) as usize;
+        let stride = view["byteStride"].as_u64().map(|s| s as usize);
+
+        match (self.buffers.get(buffer), offset.checked_add(length)) {
+            (Some(data), Some(end)) if end <= data.len() => Ok((&data[offset..end], stride)),
+            _ => Err(LoadError::invalid(
+                self.path,
+                format!("bufferViews[{}] is out of bounds", index),
+            )),
+        }
+    }
+
+    // Reads an accessor as flat f64 values plus the component count per element.
+    fn accessor(&self, index: usize) -> Result<(Vec<f64>, usize), LoadError> {
+        let accessor = self.get("accessors", index)?;
+        let error = |message: &str| {
+            LoadError::invalid(self.path, format!("accessors[{}]: {}", index, message))
+        };
+
+        if accessor.get("sparse").is_some() {
+            return Err(error("sparse accessors are not supported"));
+        }
+
+        let count = accessor["count"].as_u64().unwrap_or(0) as usize;
+        let components = match accessor["type"].as_str() {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            Some("MAT4") => 16,
+            _ => return Err(error("unsupported type")),
+        };
+        let component_type = accessor["componentType"].as_u64().unwrap_or(0);
+        let (size, max) = match component_type {
+            5120 => (1, 127.0),
+            5121 => (1, 255.0),
+            5122 => (2, 32767.0),
+            5123 => (2, 65535.0),
+            5125 => (4, u32::MAX as f64),
+            5126 => (4, 1.0),
+            _ => return Err(error("unsupported componentType")),
+        };
+        let normalized = accessor["normalized"].as_bool().unwrap_or(false);
+
+        // The count is only trusted once the data it covers is known to be
+        // there, so a broken file cannot ask for any amount of memory
+        let length = count
+            .checked_mul(components)
+            .ok_or_else(|| error("count is too large"))?;
+        let view = match accessor["bufferView"].as_u64() {
+            Some(view) => view as usize,
+            // No buffer view means all zeros, which may take no more room
+            // than the buffers would.
+            None => {
+                let available: usize = self.buffers.iter().map(|buffer| buffer.len()).sum();
+                if length.saturating_mul(size) > available {
+                    return Err(error("count is too large"));
+                }
+                return Ok((vec![0.0; length], components));
+            }
+        };
+        let (data, stride) = self.buffer_view(view)?;
+        let offset = accessor["byteOffset"].as_u64().unwrap_or(0) as usize;
+        let stride = stride.unwrap_or(size * components);
+        if stride < size * components {
+            return Err(error("byteStride is smaller than an element"));
+        }
+
+        if count > 0 {
+            let end = (count - 1)
+                .checked_mul(stride)
+                .and_then(|end| end.checked_add(offset))
+                .and_then(|end| end.checked_add(size * components));
+            if end.is_none_or(|end| end > data.len()) {
+                return Err(error("reads past the end of its buffer view"));
+            }
+        }
+
+        let mut values = Vec::with_capacity(length);
+        for i in 0..count {
+            for c in 0..components {
+                let at = offset + i * stride + c * size;
+                let b = &data[at..at + size];
+                let v = match component_type {
+                    5120 => b[0] as i8 as f64,
+                    5121 => b[0] as f64,
+                    5122 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    5123 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                };
+                values.push(if normalized && component_type != 5126 {
+                    (v / max).max(-1.0)
+                } else {
+                    v
+                });
+            }
+        }
+
+        Ok((values, components))
+    }
+
+    fn texture(&self, info: &Value) -> Result<Option<Arc<dyn Texture + Sync + Send>>, LoadError> {
+        let index = match info["index"].as_u64() {
+            Some(index) => index as usize,
+            None => return Ok(None),
+        };
+        let source = match self.get("textures", index)?["source"].as_u64() {
+            Some(source) => source as usize,
+            None => return Ok(None),
+        };
+
+        let image = self.get("images", source)?;
+        let bytes = match (image["uri"].as_str(), image["bufferView"].as_u64()) {
+            (Some(uri), _) => self.resolve_uri(uri)?,
+            (None, Some(view)) => self.buffer_view(view as usize)?.0.to_vec(),
+            _ => {
+                return Err(LoadError::invalid(
+                    self.path,
+                    format!("images[{}] has neither uri nor bufferView", source),
+                ))
+            }
+        };
+
+        let texture = ImageTexture::new_from_memory(&bytes);
+        if texture.width() == 0 {
+            return Err(LoadError::invalid(
+                self.path,
+                format!("images[{}] could not be decoded", source),
+            ));
+        }
+
+        Ok(Some(Arc::new(texture)))
+    }
+
+    // Maps metallic-roughness onto the closest material we have: emissive
+    // surfaces become lights, transmissive ones glass, mostly metallic ones Metal
+    // with roughness as fuzz, or a principled metal when their color is
+    // textured, and everything else Lambertian.
+    fn material(
+        &mut self,
+        index: Option<usize>,
+    ) -> Result<Arc<dyn Material + Sync + Send>, LoadError> {
+        let index = match index {
+            Some(index) => index,
+            None => return Ok(self.default_material.clone()),
+        };
+        if let Some(material) = self.materials.get(&index) {
+            return Ok(material.clone());
+        }
+
+        let m = self.get("materials", index)?;
+        let pbr = &m["pbrMetallicRoughness"];
+        let extensions = &m["extensions"];
+
+        let base = match pbr["baseColorFactor"].as_array() {
+            Some(_) => vec3_or(&pbr["baseColorFactor"], Vec3::new(1.0, 1.0, 1.0)),
+            None => Vec3::new(1.0, 1.0, 1.0),
+        };
+        let metallic = pbr["metallicFactor"].as_f64().unwrap_or(1.0);
+        let roughness = pbr["roughnessFactor"].as_f64().unwrap_or(1.0);
+        let strength = extensions["KHR_materials_emissive_strength"]["emissiveStrength"]
+            .as_f64()
+            .unwrap_or(1.0);
+        let emissive = strength * vec3_or(&m["emissiveFactor"], Vec3::default());
+        let transmission = extensions["KHR_materials_transmission"]["transmissionFactor"]
+            .as_f64()
+            .unwrap_or(0.0);
+        let ior = extensions["KHR_materials_ior"]["ior"]
+            .as_f64()
+            .unwrap_or(1.5);
+
+        let material: Arc<dyn Material + Sync + Send> =
+            if emissive.x().max(emissive.y()).max(emissive.z()) > 0.0 {
+                Arc::new(DiffuseLight::new_with_color(emissive))
+            } else if transmission > 0.5 {
+                Arc::new(Dielectric::new(ior))
+            } else {
+                let texture = self.texture(&pbr["baseColorTexture"])?;
+                match (texture, metallic >= 0.5) {
+                    (Some(texture), true) => {
+                        let mut metal = Principled::new_with_texture(texture);
+                        metal.metallic = Arc::new(SolidColor::new(&Vec3::new(1.0, 1.0, 1.0)));
+                        metal.roughness =
+                            Arc::new(SolidColor::new(&Vec3::new(roughness, roughness, roughness)));
+                        Arc::new(metal)
+                    }
+                    (Some(texture), false) => Arc::new(Lambertian::new_with_texture(texture)),
+                    (None, true) => Arc::new(Metal::new(&base, roughness)),
+                    (None, false) => Arc::new(Lambertian::new(&base)),
+                }
+            };
+
+        self.materials.insert(index, material.clone());
+        Ok(material)
+    }
+
+    fn visit_node(
+        &mut self,
+        index: usize,
+        parent: &Mat4,
+        scene: &mut GltfScene,
+        depth: usize,
+    ) -> Result<(), LoadError> {
+        if depth > self.array("nodes").len() {
+            return Err(LoadError::invalid(self.path, "node hierarchy has a cycle"));
+        }
+
+        let node = self.get("nodes", index)?.clone();
+        let local = match node["matrix"].as_array() {
+            Some(m) if m.len() == 16 => {
+                let mut values = [0.0; 16];
+                for (i, v) in m.iter().enumerate() {
+                    values[i] = v.as_f64().unwrap_or(0.0);
+                }
+                Mat4::from_column_major(&values)
+            }
+            _ => {
+                let r = &node["rotation"];
+                let rotation = match r.as_array() {
+                    Some(q) if q.len() == 4 => Quaternion::new(
+                        q[0].as_f64().unwrap_or(0.0),
+                        q[1].as_f64().unwrap_or(0.0),
+                        q[2].as_f64().unwrap_or(0.0),
+                        q[3].as_f64().unwrap_or(1.0),
+                    ),
+                    _ => Quaternion::identity(),
+                };
+                Mat4::from_trs(
+                    &vec3_or(&node["translation"], Vec3::default()),
+                    &rotation,
+                    &vec3_or(&node["scale"], Vec3::new(1.0, 1.0, 1.0)),
+                )
+            }
+        };
+        let world = *parent * local;
+
+        if let Some(mesh) = node["mesh"].as_u64() {
+            self.add_mesh(mesh as usize, &world, scene)?;
+        }
+        if let Some(camera) = node["camera"].as_u64() {
+            self.add_camera(camera as usize, &world, scene)?;
+        }
+        if let Some(light) = node["extensions"]["KHR_lights_punctual"]["light"].as_u64() {
+            self.add_light(light as usize, &world, scene)?;
+        }
+
+        for child in self.indices(&node["children"]) {
+            self.visit_node(child, &world, scene, depth + 1)?;
+        }
+
+        Ok(())
+    }
+
+    fn add_mesh(
+        &mut self,
+        index: usize,
+        world: &Mat4,
+        scene: &mut GltfScene,
+    ) -> Result<(), LoadError> {
+        let normal_matrix = match world.inverse() {
+            Some(inverse) => inverse.transpose(),
+            None => return Ok(()),
+        };
+        let flips_winding = world.determinant3() < 0.0;
+
+        let primitives = self.get("meshes", index)?["primitives"].clone();
+        for primitive in primitives.as_array().map(|a| a.as_slice()).unwrap_or(&[]) {
+            let attributes = &primitive["attributes"];
+            let position = match attributes["POSITION"].as_u64() {
+                Some(position) => position as usize,
+                None => continue,
+            };
+
+            let (values, components) = self.accessor(position)?;
+            if components != 3 {
+                return Err(LoadError::invalid(self.path, "POSITION must be VEC3"));
+            }
+            let positions: Vec<Point3> = values
+                .chunks(3)
+                .map(|p| world.transform_point(&Point3::new(p[0], p[1], p[2])))
+                .collect();
+
+            let normals: Vec<Vec3> = match attributes["NORMAL"].as_u64() {
+                Some(normal) => self
+                    .accessor(normal as usize)?
+                    .0
+                    .chunks(3)
+                    .map(|n| {
+                        normal_matrix
+                            .transform_vector(&Vec3::new(n[0], n[1], n[2]))
+                            .unit_vector()
+                    })
+                    .collect(),
+                None => Vec::new(),
+            };
+
+            // glTF puts the uv origin at the top left, ImageTexture at the bottom left.
+            let uvs: Vec<(f64, f64)> = match attributes["TEXCOORD_0"].as_u64() {
+                Some(uv) => self
+                    .accessor(uv as usize)?
+                    .0
+                    .chunks(2)
+                    .map(|t| (t[0], 1.0 - t[1]))
+                    .collect(),
+                None => Vec::new(),
+            };
+
+            let order: Vec<usize> = match primitive["indices"].as_u64() {
+                Some(indices) => self
+                    .accessor(indices as usize)?
+                    .0
+                    .iter()
+                    .map(|i| *i as usize)
+                    .collect(),
+                None => (0..positions.len()).collect(),
+            };
+
+            let mut faces: Vec<[usize; 3]> = match primitive["mode"].as_u64().unwrap_or(4) {
+                4 => order.chunks_exact(3).map(|f| [f[0], f[1], f[2]]).collect(),
+                5 => (2..order.len())
+                    .map(|i| {
+                        if i % 2 == 0 {
+                            [order[i - 2], order[i - 1], order[i]]
+                        } else {
+                            [order[i - 1], order[i - 2], order[i]]
+                        }
+                    })
+                    .collect(),
+                6 => (2..order.len())
+                    .map(|i| [order[0], order[i - 1], order[i]])
+                    .collect(),
+                // Points and lines have no surface.
+                _ => continue,
+            };
+
+            if faces.iter().flatten().any(|i| *i >= positions.len())
+                || (!normals.is_empty() && normals.len() != positions.len())
+                || (!uvs.is_empty() && uvs.len() != positions.len())
+            {
+                return Err(LoadError::invalid(
+                    self.path,
+                    format!("meshes[{}] has inconsistent attribute counts", index),
+                ));
+            }
+            if flips_winding {
+                for f in faces.iter_mut() {
+                    f.swap(1, 2);
+                }
+            }
+
+            let material = self.material(primitive["material"].as_u64().map(|m| m as usize))?;
+            scene.world.add(Arc::new(if normals.is_empty() {
+                TriangleMesh::new_smooth(positions, uvs, faces, material)
+            } else {
+                TriangleMesh::new(positions, normals, uvs, faces, material)
+            }));
+        }
+
+        Ok(())
+    }
+
+    // glTF cameras look down -Z with +Y up in their node's frame.
+    fn add_camera(
+        &self,
+        index: usize,
+        world: &Mat4,
+        scene: &mut GltfScene,
+    ) -> Result<(), LoadError> {
+        let camera = self.get("cameras", index)?;
+        let perspective = &camera["perspective"];
+        if camera["type"].as_str() != Some("perspective") {
+            scene.warnings.push(format!(
+                "{}: skipping cameras[{}], only perspective cameras are supported",
+                self.path, index
+            ));
+            return Ok(());
+        }
+
+        let yfov = perspective["yfov"].as_f64().unwrap_or(PI / 4.0);
+        let aspect_ratio = perspective["aspectRatio"]
+            .as_f64()
+            .unwrap_or(self.aspect_ratio);
+        let lookfrom = world.transform_point(&Point3::default());
+        let forward = world.transform_vector(&Vec3::new(0.0, 0.0, -1.0));
+        let vup = world.transform_vector(&Vec3::new(0.0, 1.0, 0.0));
+
+        scene.cameras.push(Camera::new(
+            &lookfrom,
+            &(lookfrom + forward),
+            &vup,
+            yfov * 180.0 / PI,
+            aspect_ratio,
+            0.0,
+            1.0,
+            0.0,
+            1.0,
+        ));
+
+        Ok(())
+    }
+
+    // Only point lights map onto something we can render: a small emissive sphere
+    // whose radiance gives the light's intensity, I = L * pi * r^2.
+    fn add_light(
+        &self,
+        index: usize,
+        world: &Mat4,
+        scene: &mut GltfScene,
+    ) -> Result<(), LoadError> {
+        let light = match self.json["extensions"]["KHR_lights_punctual"]["lights"].get(index) {
+            Some(light) => light,
+            None => {
+                return Err(LoadError::invalid(
+                    self.path,
+                    format!("KHR_lights_punctual light {} does not exist", index),
+                ))
+            }
+        };
+
+        if light["type"].as_str() != Some("point") {
+            scene.warnings.push(format!(
+                "{}: skipping {} light {}, only point lights are supported",
+                self.path,
+                light["type"].as_str().unwrap_or("unknown"),
+                index
+            ));
+            return Ok(());
+        }
+
+        let color = vec3_or(&light["color"], Vec3::new(1.0, 1.0, 1.0));
+        let intensity = light["intensity"].as_f64().unwrap_or(1.0);
+        let radiance = color * intensity / (PI * POINT_LIGHT_RADIUS * POINT_LIGHT_RADIUS);
+
+        scene.world.add(Arc::new(Sphere::new(
+            world.transform_point(&Point3::default()),
+            POINT_LIGHT_RADIUS,
+            Arc::new(DiffuseLight::new_with_color(radiance)),
+        )));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::model::{
+        hit::{HitRecord, Hittable},
+        ray::Ray,
+    };
+
+    use super::*;
+
+    // A unit quad in the xy plane, with v running down the quad like glTF's
+    // image rows, followed by the indices of each primitive as u32s
+    fn quad_buffer(index_lists: &[&[u32]]) -> Vec<u8> {
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [1.0, 1.0, 0.0],
+        ];
+        let uvs = [[0.0, 1.0], [1.0, 1.0], [0.0, 0.0], [1.0, 0.0]];
+
+        let mut bin = Vec::new();
+        for value in positions.iter().flatten().chain(uvs.iter().flatten()) {
+            bin.extend_from_slice(&(*value as f32).to_le_bytes());
+        }
+        for index in index_lists.iter().copied().flatten() {
+            bin.extend_from_slice(&index.to_le_bytes());
+        }
+
+        bin
+    }
+
+    // A document drawing the quad buffer once per primitive mode, under one
+    // node with the given scale
+    fn quad_document(modes: &[(u64, &[u32])], scale: [f64; 3], uri: Option<String>) -> Value {
+        let mut buffer_views = vec![
+            json!({"buffer": 0, "byteOffset": 0, "byteLength": 48}),
+            json!({"buffer": 0, "byteOffset": 48, "byteLength": 32}),
+        ];
+        let mut accessors = vec![
+            json!({"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3"}),
+            json!({"bufferView": 1, "componentType": 5126, "count": 4, "type": "VEC2"}),
+        ];
+        let mut primitives = Vec::new();
+        let mut offset = 80;
+        for (mode, indices) in modes {
+            buffer_views.push(json!({
+                "buffer": 0, "byteOffset": offset, "byteLength": 4 * indices.len()
+            }));
+            accessors.push(json!({
+                "bufferView": buffer_views.len() - 1, "componentType": 5125,
+                "count": indices.len(), "type": "SCALAR"
+            }));
+            primitives.push(json!({
+                "attributes": {"POSITION": 0, "TEXCOORD_0": 1},
+                "indices": accessors.len() - 1,
+                "mode": mode
+            }));
+            offset += 4 * indices.len();
+        }
+
+        let mut buffer = json!({"byteLength": offset});
+        if let Some(uri) = uri {
+            buffer["uri"] = json!(uri);
+        }
+
+        json!({
+            "asset": {"version": "2.0"},
+            "scene": 0,
+            "scenes": [{"nodes": [0]}],
+            "nodes": [{"mesh": 0, "scale": scale}],
+            "meshes": [{"primitives": primitives}],
+            "accessors": accessors,
+            "bufferViews": buffer_views,
+            "buffers": [buffer]
+        })
+    }
+
+    fn glb(json: &Value, bin: &[u8]) -> Vec<u8> {
+        let mut json = serde_json::to_vec(json).unwrap();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let mut bin = bin.to_vec();
+        while !bin.len().is_multiple_of(4) {
+            bin.push(0);
+        }
+
+        let mut data = Vec::new();
+        for word in [GLB_MAGIC, 2, (28 + json.len() + bin.len()) as u32] {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+        data.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        data.extend_from_slice(&GLB_CHUNK_JSON.to_le_bytes());
+        data.extend_from_slice(&json);
+        data.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        data.extend_from_slice(&GLB_CHUNK_BIN.to_le_bytes());
+        data.extend_from_slice(&bin);
+
+        data
+    }
+
+    fn encode_base64(data: &[u8]) -> String {
+        let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = String::new();
+        for chunk in data.chunks(3) {
+            let bits = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |bits, (i, b)| bits | (*b as u32) << (16 - 8 * i));
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(alphabet[(bits >> (18 - 6 * i) & 63) as usize] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+
+        out
+    }
+
+    // A 1x1 24-bit BMP of one color
+    fn bmp(color: [u8; 3]) -> Vec<u8> {
+        let mut data = b"BM".to_vec();
+        for word in [58u32, 0, 54, 40, 1, 1] {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&24u16.to_le_bytes());
+        for word in [0u32, 4, 2835, 2835, 0, 0] {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+        data.extend_from_slice(&[color[2], color[1], color[0], 0]);
+
+        data
+    }
+
+    // Looks straight down -z at (x, y) from in front of the quad
+    fn hit(scene: &GltfScene, x: f64, y: f64) -> HitRecord {
+        let r = Ray::new(&Point3::new(x, y, 1.0), &Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let mut rec = HitRecord::default();
+        assert!(scene.world.hit(&r, 0.001, f64::INFINITY, &mut rec));
+
+        rec
+    }
+
+    #[test]
+    fn test_strips_and_fans() {
+        let modes: [(u64, &[u32]); 3] = [
+            (4, &[0, 1, 2, 2, 1, 3]),
+            (5, &[0, 1, 2, 3]),
+            (6, &[0, 1, 3, 2]),
+        ];
+        for (mode, indices) in modes {
+            let document = quad_document(&[(mode, indices)], [1.0, 1.0, 1.0], None);
+            let scene =
+                parse_gltf(&glb(&document, &quad_buffer(&[indices])), "quad.glb", 1.0).unwrap();
+
+            // Both triangles of the quad are there and wound to face +z
+            for (x, y) in [(0.25, 0.25), (0.75, 0.75)] {
+                assert!(
+                    hit(&scene, x, y).front_face,
+                    "mode {} at ({}, {})",
+                    mode,
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_uvs_flip_to_bottom_left_origin() {
+        let indices: &[u32] = &[0, 1, 2, 2, 1, 3];
+        let bin = quad_buffer(&[indices]);
+        let uri = format!(
+            "data:application/octet-stream;base64,{}",
+            encode_base64(&bin)
+        );
+        let document = quad_document(&[(4, indices)], [1.0, 1.0, 1.0], Some(uri));
+        let scene = parse_gltf(document.to_string().as_bytes(), "quad.gltf", 1.0).unwrap();
+
+        let rec = hit(&scene, 0.25, 0.75);
+        assert!((rec.u - 0.25).abs() < 1e-6 && (rec.v - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_mirroring_keeps_winding() {
+        let indices: &[u32] = &[0, 1, 2, 2, 1, 3];
+        let document = quad_document(&[(4, indices)], [-1.0, 1.0, 1.0], None);
+        let scene = parse_gltf(&glb(&document, &quad_buffer(&[indices])), "quad.glb", 1.0).unwrap();
+
+        let rec = hit(&scene, -0.25, 0.25);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-9);
+    }
+
+    fn textured_quad(image: &[u8], metallic: f64) -> Result<GltfScene, LoadError> {
+        let indices: &[u32] = &[0, 1, 2, 2, 1, 3];
+        let mut bin = quad_buffer(&[indices]);
+        let mut document = quad_document(&[(4, indices)], [1.0, 1.0, 1.0], None);
+        document["bufferViews"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({"buffer": 0, "byteOffset": bin.len(), "byteLength": image.len()}));
+        document["buffers"][0]["byteLength"] = json!(bin.len() + image.len());
+        document["images"] = json!([{"bufferView": 3, "mimeType": "image/bmp"}]);
+        document["textures"] = json!([{"source": 0}]);
+        document["materials"] = json!([{"pbrMetallicRoughness": {
+            "baseColorTexture": {"index": 0},
+            "metallicFactor": metallic,
+            "roughnessFactor": 0.5
+        }}]);
+        document["meshes"][0]["primitives"][0]["material"] = json!(0);
+        bin.extend_from_slice(image);
+
+        parse_gltf(&glb(&document, &bin), "quad.glb", 1.0)
+    }
+
+    #[test]
+    fn test_metals_use_base_color_texture() {
+        let scene = textured_quad(&bmp([255, 0, 0]), 1.0).unwrap();
+        let rec = hit(&scene, 0.5, 0.5);
+
+        // Light glancing off the rough metal takes on the red of the texture
+        let r = Ray::new(
+            &Point3::new(-0.5, 0.5, 1.0),
+            &Vec3::new(1.0, 0.0, -1.0),
+            0.0,
+        );
+        let f = rec
+            .material
+            .eval(&r, &rec, &Vec3::new(0.8, 0.0, 1.0).unit_vector());
+        assert!(f.x() > 100.0 * f.y().max(f.z()));
+    }
+
+    #[test]
+    fn test_undecodable_texture_is_an_error() {
+        for metallic in [0.0, 1.0] {
+            match textured_quad(b"not an image", metallic) {
+                Err(LoadError::Invalid { message, .. }) => {
+                    assert!(message.contains("could not be decoded"))
+                }
+                _ => panic!("a broken image should fail the load"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_oversized_count_is_an_error() {
+        let indices: &[u32] = &[0, 1, 2, 2, 1, 3];
+        let bin = quad_buffer(&[indices]);
+        // Overflowing, past the end of the buffer view, one too many, and
+        // all zeros with no buffer view to check against
+        for (count, has_view) in [
+            (u64::MAX, true),
+            (1 << 40, true),
+            (5, true),
+            (u64::MAX, false),
+        ] {
+            let mut document = quad_document(&[(4, indices)], [1.0, 1.0, 1.0], None);
+            document["accessors"][0]["count"] = json!(count);
+            if !has_view {
+                document["accessors"][0]
+                    .as_object_mut()
+                    .unwrap()
+                    .remove("bufferView");
+            }
+
+            match parse_gltf(&glb(&document, &bin), "quad.glb", 1.0) {
+                Err(LoadError::Invalid { message, .. }) => {
+                    assert!(message.contains("accessors[0]"), "{}", message)
+                }
+                _ => panic!("a count of {} should fail the load", count),
+            }
+        }
+    }
+
+    #[test]
+    fn test_skipped_items_are_reported() {
+        let document = json!({
+            "asset": {"version": "2.0"},
+            "extensionsUsed": ["KHR_lights_punctual"],
+            "extensions": {"KHR_lights_punctual": {"lights": [
+                {"type": "point", "intensity": 2.0},
+                {"type": "spot", "intensity": 2.0}
+            ]}},
+            "nodes": [
+                {"camera": 0},
+                {"camera": 1},
+                {"extensions": {"KHR_lights_punctual": {"light": 0}}},
+                {"extensions": {"KHR_lights_punctual": {"light": 1}}}
+            ],
+            "cameras": [
+                {"type": "perspective", "perspective": {"yfov": 0.8, "znear": 0.1}},
+                {"type": "orthographic", "orthographic": {"xmag": 1, "ymag": 1, "znear": 0.1, "zfar": 10}}
+            ]
+        });
+        let scene = parse_gltf(document.to_string().as_bytes(), "scene.gltf", 1.0).unwrap();
+
+        assert_eq!(scene.cameras.len(), 1);
+        assert_eq!(scene.world.objects.len(), 1);
+        assert_eq!(scene.warnings.len(), 2);
+        assert!(scene.warnings[0].contains("cameras[1]"));
+        assert!(scene.warnings[1].contains("spot"));
+    }
+}
@@ -0,0 +1,58 @@
+use std::fs;
+
+use crate::model::grid_medium::DensityGrid;
+
+use super::error::LoadError;
+
+// Density grids are stored as raw little-endian binary:
+//
+//   u32 nx, u32 ny, u32 nz
+//   nx * ny * nz f32 densities, x varying fastest, then y, then z
+//
+// with nothing before, between or after.
+pub fn load_grid(path: &str) -> Result<DensityGrid, LoadError> {
+    let data = fs::read(path).map_err(|e| LoadError::io(path, e))?;
+    parse_grid(&data, path)
+}
+
+pub fn parse_grid(data: &[u8], path: &str) -> Result<DensityGrid, LoadError> {
+    let read_u32 = |offset: usize| -> Result<usize, LoadError> {
+        let bytes = data
+            .get(offset..offset + 4)
+            .ok_or_else(|| LoadError::invalid(path, "file is too short for the grid header"))?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+    let (nx, ny, nz) = (read_u32(0)?, read_u32(4)?, read_u32(8)?);
+    if nx == 0 || ny == 0 || nz == 0 {
+        return Err(LoadError::invalid(
+            path,
+            format!("grid dimensions {}x{}x{} are empty", nx, ny, nz),
+        ));
+    }
+
+    let count = nx
+        .checked_mul(ny)
+        .and_then(|n| n.checked_mul(nz))
+        .ok_or_else(|| LoadError::invalid(path, "grid dimensions overflow"))?;
+    let body = &data[12..];
+    if body.len() != count * 4 {
+        return Err(LoadError::invalid(
+            path,
+            format!(
+                "expected {} bytes of densities for a {}x{}x{} grid, found {}",
+                count * 4,
+                nx,
+                ny,
+                nz,
+                body.len()
+            ),
+        ));
+    }
+
+    let values = body
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64)
+        .collect();
+
+    Ok(DensityGrid::new(nx, ny, nz, values))
+}
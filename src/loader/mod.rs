@@ -0,0 +1,6 @@
+pub mod error;
+pub mod gltf;
+pub mod grid;
+pub mod mtl;
+pub mod obj;
+pub mod ply;
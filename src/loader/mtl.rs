@@ -0,0 +1,176 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use crate::{
+    material::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian,
+        material::Material, metal::Metal,
+    },
+    model::vec3::Vec3,
+    texture::image::ImageTexture,
+};
+
+use super::error::LoadError;
+
+struct MtlEntry {
+    kd: Vec3,
+    ks: Vec3,
+    ke: Vec3,
+    ns: f64,
+    ni: f64,
+    dissolve: f64,
+    illum: i32,
+    map_kd: Option<String>,
+}
+
+impl Default for MtlEntry {
+    fn default() -> Self {
+        Self {
+            kd: Vec3::new(0.8, 0.8, 0.8),
+            ks: Vec3::default(),
+            ke: Vec3::default(),
+            ns: 0.0,
+            ni: 1.5,
+            dissolve: 1.0,
+            illum: 2,
+            map_kd: None,
+        }
+    }
+}
+
+impl MtlEntry {
+    // Picks the closest of our materials: emitters first, then glass (illum 4/6/7
+    // or a dissolve below one), then mirrors (illum 3, or only a specular color),
+    // falling back to a diffuse surface.
+    fn to_material(&self) -> Arc<dyn Material + Sync + Send> {
+        let max_component = |c: &Vec3| c.x().max(c.y()).max(c.z());
+
+        if max_component(&self.ke) > 0.0 {
+            return Arc::new(DiffuseLight::new_with_color(self.ke));
+        }
+
+        if matches!(self.illum, 4 | 6 | 7) || self.dissolve < 1.0 {
+            return Arc::new(Dielectric::new(self.ni));
+        }
+
+        if self.illum == 3 || (max_component(&self.kd) == 0.0 && max_component(&self.ks) > 0.0) {
+            // Map the Phong exponent onto the fuzz radius, 0 for a perfect mirror.
+            let fuzz = (2.0 / (self.ns + 2.0)).sqrt();
+            return Arc::new(Metal::new(&self.ks, fuzz));
+        }
+
+        match &self.map_kd {
+            Some(file) => Arc::new(Lambertian::new_with_texture(Arc::new(ImageTexture::new(
+                file.clone(),
+            )))),
+            None => Arc::new(Lambertian::new(&self.kd)),
+        }
+    }
+}
+
+pub fn load_mtl(path: &str) -> Result<HashMap<String, Arc<dyn Material + Sync + Send>>, LoadError> {
+    let source = fs::read_to_string(path).map_err(|e| LoadError::io(path, e))?;
+    let base_dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+
+    parse_mtl(&source, path, base_dir)
+}
+
+pub fn parse_mtl(
+    source: &str,
+    path: &str,
+    base_dir: &Path,
+) -> Result<HashMap<String, Arc<dyn Material + Sync + Send>>, LoadError> {
+    let mut entries: Vec<(String, MtlEntry)> = Vec::new();
+
+    for (number, raw) in source.lines().enumerate() {
+        let line = number + 1;
+        let content = raw.split('#').next().unwrap_or("").trim();
+        let mut tokens = content.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(k) => k,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if args.is_empty() {
+                return Err(LoadError::parse(path, line, "newmtl without a name"));
+            }
+            entries.push((args.join(" "), MtlEntry::default()));
+            continue;
+        }
+
+        let entry = match entries.last_mut() {
+            Some((_, entry)) => entry,
+            None => {
+                return Err(LoadError::parse(
+                    path,
+                    line,
+                    format!("'{}' before any newmtl", keyword),
+                ))
+            }
+        };
+
+        match keyword {
+            "Kd" => entry.kd = parse_color(&args, path, line)?,
+            "Ks" => entry.ks = parse_color(&args, path, line)?,
+            "Ke" => entry.ke = parse_color(&args, path, line)?,
+            "Ns" => entry.ns = parse_scalar(&args, path, line)?,
+            "Ni" => entry.ni = parse_scalar(&args, path, line)?,
+            "d" => entry.dissolve = parse_scalar(&args, path, line)?,
+            "Tr" => entry.dissolve = 1.0 - parse_scalar(&args, path, line)?,
+            "illum" => entry.illum = parse_scalar(&args, path, line)? as i32,
+            "map_Kd" => {
+                // Texture options come first, the file name is the last token.
+                let file = match args.last() {
+                    Some(f) => base_dir.join(f),
+                    None => return Err(LoadError::parse(path, line, "map_Kd without a file")),
+                };
+                if !file.exists() {
+                    return Err(LoadError::parse(
+                        path,
+                        line,
+                        format!("texture '{}' not found", file.display()),
+                    ));
+                }
+                entry.map_kd = Some(file.to_string_lossy().into_owned());
+            }
+            _ => {}
+        }
+    }
+
+    Ok(entries
+        .into_iter()
+        .map(|(name, entry)| (name, entry.to_material()))
+        .collect())
+}
+
+fn parse_scalar(args: &[&str], path: &str, line: usize) -> Result<f64, LoadError> {
+    match args.first() {
+        Some(token) => token
+            .parse::<f64>()
+            .map_err(|_| LoadError::parse(path, line, format!("invalid number '{}'", token))),
+        None => Err(LoadError::parse(path, line, "missing value")),
+    }
+}
+
+fn parse_color(args: &[&str], path: &str, line: usize) -> Result<Vec3, LoadError> {
+    let values = args
+        .iter()
+        .map(|token| {
+            token
+                .parse::<f64>()
+                .map_err(|_| LoadError::parse(path, line, format!("invalid number '{}'", token)))
+        })
+        .collect::<Result<Vec<f64>, LoadError>>()?;
+
+    // A single value means a gray color.
+    match values.len() {
+        1 => Ok(Vec3::new(values[0], values[0], values[0])),
+        3 => Ok(Vec3::new(values[0], values[1], values[2])),
+        n => Err(LoadError::parse(
+            path,
+            line,
+            format!("expected 1 or 3 color components, found {}", n),
+        )),
+    }
+}
@@ -0,0 +1,350 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use crate::{
+    material::material::Material,
+    model::{triangle_mesh::TriangleMesh, vec3::Vec3},
+};
+
+use super::{error::LoadError, mtl::load_mtl};
+
+use Vec3 as Point3;
+
+// Indices of a face corner into the position, uv and normal arrays.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+pub struct ObjObject {
+    pub name: String,
+    pub material_name: Option<String>,
+    pub mesh: Arc<TriangleMesh>,
+}
+
+struct FaceGroup {
+    name: String,
+    material_name: Option<String>,
+    faces: Vec<[Corner; 3]>,
+}
+
+pub fn load_obj(
+    path: &str,
+    default_material: Arc<dyn Material + Sync + Send>,
+) -> Result<Vec<ObjObject>, LoadError> {
+    let source = fs::read_to_string(path).map_err(|e| LoadError::io(path, e))?;
+    let base_dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+
+    parse_obj(&source, path, base_dir, default_material)
+}
+
+// Parses OBJ text into one mesh per group and material. `base_dir` resolves
+// mtllib references; `path` is only used in error messages.
+pub fn parse_obj(
+    source: &str,
+    path: &str,
+    base_dir: &Path,
+    default_material: Arc<dyn Material + Sync + Send>,
+) -> Result<Vec<ObjObject>, LoadError> {
+    let mut positions: Vec<Point3> = Vec::new();
+    let mut uvs: Vec<(f64, f64)> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut materials: HashMap<String, Arc<dyn Material + Sync + Send>> = HashMap::new();
+
+    let mut groups = vec![FaceGroup {
+        name: "default".to_owned(),
+        material_name: None,
+        faces: Vec::new(),
+    }];
+    let mut current = 0;
+
+    for (number, raw) in source.lines().enumerate() {
+        let line = number + 1;
+        let content = raw.split('#').next().unwrap_or("").trim();
+        let mut tokens = content.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(k) => k,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let v = parse_floats(&args, 3, path, line)?;
+                positions.push(Point3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let v = parse_floats(&args, 1, path, line)?;
+                uvs.push((v[0], v.get(1).copied().unwrap_or(0.0)));
+            }
+            "vn" => {
+                let v = parse_floats(&args, 3, path, line)?;
+                normals.push(Vec3::new(v[0], v[1], v[2]));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(LoadError::parse(
+                        path,
+                        line,
+                        format!("face needs at least 3 vertices, found {}", args.len()),
+                    ));
+                }
+                let corners = args
+                    .iter()
+                    .map(|token| {
+                        parse_corner(token, positions.len(), uvs.len(), normals.len(), path, line)
+                    })
+                    .collect::<Result<Vec<Corner>, LoadError>>()?;
+
+                // Fan triangulation; fine for the convex polygons exporters write.
+                for i in 1..corners.len() - 1 {
+                    groups[current]
+                        .faces
+                        .push([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            "g" | "o" => {
+                let name = if args.is_empty() {
+                    "default".to_owned()
+                } else {
+                    args.join(" ")
+                };
+                let material_name = groups[current].material_name.clone();
+                current = find_or_add_group(&mut groups, name, material_name);
+            }
+            "usemtl" => {
+                let name = args.join(" ");
+                if !materials.contains_key(&name) {
+                    return Err(LoadError::parse(
+                        path,
+                        line,
+                        format!("unknown material '{}'", name),
+                    ));
+                }
+                let group_name = groups[current].name.clone();
+                current = find_or_add_group(&mut groups, group_name, Some(name));
+            }
+            "mtllib" => {
+                for file in args {
+                    let mtl_path = base_dir.join(file);
+                    materials.extend(load_mtl(&mtl_path.to_string_lossy())?);
+                }
+            }
+            // Smoothing groups, lines, points and free-form geometry are ignored.
+            _ => {}
+        }
+    }
+
+    Ok(groups
+        .into_iter()
+        .filter(|g| !g.faces.is_empty())
+        .map(|g| {
+            let material = match &g.material_name {
+                Some(name) => materials[name].clone(),
+                None => default_material.clone(),
+            };
+            let mesh = build_mesh(&g.faces, &positions, &uvs, &normals, material);
+
+            ObjObject {
+                name: g.name,
+                material_name: g.material_name,
+                mesh: Arc::new(mesh),
+            }
+        })
+        .collect())
+}
+
+fn find_or_add_group(
+    groups: &mut Vec<FaceGroup>,
+    name: String,
+    material_name: Option<String>,
+) -> usize {
+    if let Some(i) = groups
+        .iter()
+        .position(|g| g.name == name && g.material_name == material_name)
+    {
+        return i;
+    }
+
+    groups.push(FaceGroup {
+        name,
+        material_name,
+        faces: Vec::new(),
+    });
+    groups.len() - 1
+}
+
+// OBJ indexes positions, uvs and normals separately; the mesh needs one index per
+// vertex, so each distinct corner becomes a vertex.
+fn build_mesh(
+    faces: &[[Corner; 3]],
+    positions: &[Point3],
+    uvs: &[(f64, f64)],
+    normals: &[Vec3],
+    material: Arc<dyn Material + Sync + Send>,
+) -> TriangleMesh {
+    let mut remap: HashMap<Corner, usize> = HashMap::new();
+    let mut corners: Vec<Corner> = Vec::new();
+    let mut indices = Vec::with_capacity(faces.len());
+
+    for face in faces {
+        let mut triangle = [0; 3];
+        for (i, corner) in face.iter().enumerate() {
+            triangle[i] = *remap.entry(*corner).or_insert_with(|| {
+                corners.push(*corner);
+                corners.len() - 1
+            });
+        }
+        indices.push(triangle);
+    }
+
+    let mesh_positions: Vec<Point3> = corners.iter().map(|c| positions[c.0]).collect();
+
+    let mesh_uvs = if corners.iter().any(|c| c.1.is_some()) {
+        corners
+            .iter()
+            .map(|c| c.1.map(|i| uvs[i]).unwrap_or((0.0, 0.0)))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    // Without vn the OBJ is faceted; if only some corners have one, fill in
+    // smooth normals for the whole group.
+    if corners.iter().all(|c| c.2.is_some()) {
+        let mesh_normals = corners.iter().map(|c| normals[c.2.unwrap()]).collect();
+        TriangleMesh::new(mesh_positions, mesh_normals, mesh_uvs, indices, material)
+    } else if corners.iter().any(|c| c.2.is_some()) {
+        TriangleMesh::new_smooth(mesh_positions, mesh_uvs, indices, material)
+    } else {
+        TriangleMesh::new(mesh_positions, Vec::new(), mesh_uvs, indices, material)
+    }
+}
+
+fn parse_floats(args: &[&str], min: usize, path: &str, line: usize) -> Result<Vec<f64>, LoadError> {
+    if args.len() < min {
+        return Err(LoadError::parse(
+            path,
+            line,
+            format!("expected {} values, found {}", min, args.len()),
+        ));
+    }
+
+    args.iter()
+        .map(|token| {
+            token
+                .parse::<f64>()
+                .map_err(|_| LoadError::parse(path, line, format!("invalid number '{}'", token)))
+        })
+        .collect()
+}
+
+// Accepts v, v/vt, v//vn and v/vt/vn with 1-based or negative (relative) indices.
+fn parse_corner(
+    token: &str,
+    position_count: usize,
+    uv_count: usize,
+    normal_count: usize,
+    path: &str,
+    line: usize,
+) -> Result<Corner, LoadError> {
+    let mut parts = token.split('/');
+    let position = match parts.next() {
+        Some(p) if !p.is_empty() => resolve_index(p, position_count, "vertex", path, line)?,
+        _ => {
+            return Err(LoadError::parse(
+                path,
+                line,
+                format!("missing vertex index in '{}'", token),
+            ))
+        }
+    };
+
+    let uv = match parts.next() {
+        Some(p) if !p.is_empty() => Some(resolve_index(p, uv_count, "uv", path, line)?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(p) if !p.is_empty() => Some(resolve_index(p, normal_count, "normal", path, line)?),
+        _ => None,
+    };
+
+    Ok((position, uv, normal))
+}
+
+fn resolve_index(
+    token: &str,
+    count: usize,
+    kind: &str,
+    path: &str,
+    line: usize,
+) -> Result<usize, LoadError> {
+    let index = token
+        .parse::<i64>()
+        .map_err(|_| LoadError::parse(path, line, format!("invalid {} index '{}'", kind, token)))?;
+
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(LoadError::parse(
+            path,
+            line,
+            format!("{} index {} out of range (have {})", kind, index, count),
+        ));
+    }
+
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+
+    fn parse(source: &str) -> Result<Vec<ObjObject>, LoadError> {
+        let default = Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5)));
+        parse_obj(source, "test.obj", Path::new(""), default)
+    }
+
+    #[test]
+    fn test_quad_with_negative_indices() {
+        let objects = parse(
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+             f -4/-4 -3/-3 -2/-2 -1/-1\n",
+        )
+        .unwrap();
+
+        assert_eq!(1, objects.len());
+        assert_eq!(2, objects[0].mesh.face_count());
+        assert_eq!(4, objects[0].mesh.positions.len());
+        assert_eq!(4, objects[0].mesh.uvs.len());
+        assert!(objects[0].mesh.normals.is_empty());
+    }
+
+    #[test]
+    fn test_groups_split_meshes() {
+        let objects = parse(
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\n\
+             g first\nf 1 2 3\ng second\nf 1 2 4\nf 2 3 4\ng first\nf 1 3 4\n",
+        )
+        .unwrap();
+
+        assert_eq!(2, objects.len());
+        assert_eq!("first", objects[0].name);
+        assert_eq!(2, objects[0].mesh.face_count());
+        assert_eq!("second", objects[1].name);
+        assert_eq!(2, objects[1].mesh.face_count());
+    }
+
+    #[test]
+    fn test_errors_report_line_numbers() {
+        let err = parse("v 0 0 0\nv 1 0 0\n\nf 1 2 3\n").err().unwrap();
+        match err {
+            LoadError::Parse { line, .. } => assert_eq!(4, line),
+            _ => panic!("expected a parse error"),
+        }
+
+        let err = parse("v 0 0 zero\n").err().unwrap();
+        assert_eq!("test.obj:1: invalid number 'zero'", err.to_string());
+    }
+}
@@ -0,0 +1,623 @@
+use std::{fs, sync::Arc};
+
+use crate::{
+    material::{lambertian::Lambertian, material::Material},
+    model::{triangle_mesh::TriangleMesh, vec3::Vec3},
+    texture::vertex_color::VertexColorTexture,
+};
+
+use super::error::LoadError;
+
+use Vec3 as Point3;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+#[derive(Clone, Copy)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<ScalarType> {
+        match name {
+            "char" | "int8" => Some(ScalarType::Int8),
+            "uchar" | "uint8" => Some(ScalarType::UInt8),
+            "short" | "int16" => Some(ScalarType::Int16),
+            "ushort" | "uint16" => Some(ScalarType::UInt16),
+            "int" | "int32" => Some(ScalarType::Int32),
+            "uint" | "uint32" => Some(ScalarType::UInt32),
+            "float" | "float32" => Some(ScalarType::Float32),
+            "double" | "float64" => Some(ScalarType::Float64),
+            _ => None,
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+}
+
+struct Property {
+    name: String,
+    value_type: ScalarType,
+    // Type of the element count for list properties.
+    count_type: Option<ScalarType>,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+// Walks the body one element instance at a time. ASCII instances are one per
+// line; binary instances are read straight from the bytes.
+struct BodyReader<'a> {
+    format: Format,
+    path: &'a str,
+    data: &'a [u8],
+    pos: usize,
+    line: usize,
+    tokens: Vec<&'a str>,
+    token: usize,
+}
+
+impl<'a> BodyReader<'a> {
+    // ASCII errors point at a line, binary ones at a byte offset.
+    fn error(&self, message: impl Into<String>) -> LoadError {
+        match self.format {
+            Format::Ascii => LoadError::parse(self.path, self.line, message),
+            Format::BinaryLittleEndian => LoadError::invalid(
+                self.path,
+                format!("{} at byte {}", message.into(), self.pos),
+            ),
+        }
+    }
+
+    fn begin_instance(&mut self) -> Result<(), LoadError> {
+        if self.format == Format::Ascii {
+            loop {
+                if self.pos >= self.data.len() {
+                    return Err(LoadError::parse(
+                        self.path,
+                        self.line,
+                        "unexpected end of file",
+                    ));
+                }
+                let end = self.data[self.pos..]
+                    .iter()
+                    .position(|b| *b == b'\n')
+                    .map(|i| self.pos + i)
+                    .unwrap_or(self.data.len());
+                let text = std::str::from_utf8(&self.data[self.pos..end])
+                    .map_err(|_| LoadError::parse(self.path, self.line + 1, "invalid UTF-8"))?;
+                self.pos = end + 1;
+                self.line += 1;
+                self.tokens = text.split_whitespace().collect();
+                self.token = 0;
+                if !self.tokens.is_empty() {
+                    return Ok(());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read(&mut self, ty: ScalarType) -> Result<f64, LoadError> {
+        match self.format {
+            Format::Ascii => {
+                let token = match self.tokens.get(self.token) {
+                    Some(t) => *t,
+                    None => return Err(self.error("too few values for element")),
+                };
+                self.token += 1;
+                token
+                    .parse::<f64>()
+                    .map_err(|_| self.error(format!("invalid number '{}'", token)))
+            }
+            Format::BinaryLittleEndian => {
+                let size = ty.size();
+                if self.pos + size > self.data.len() {
+                    return Err(self.error("unexpected end of binary data"));
+                }
+                let b = &self.data[self.pos..self.pos + size];
+                self.pos += size;
+
+                Ok(match ty {
+                    ScalarType::Int8 => b[0] as i8 as f64,
+                    ScalarType::UInt8 => b[0] as f64,
+                    ScalarType::Int16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    ScalarType::UInt16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    ScalarType::Int32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    ScalarType::UInt32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    ScalarType::Float32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    ScalarType::Float64 => {
+                        f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
+                    }
+                })
+            }
+        }
+    }
+
+    fn read_list(&mut self, count_type: ScalarType, ty: ScalarType) -> Result<Vec<f64>, LoadError> {
+        let count = self.read(count_type)?;
+        if count < 0.0 {
+            return Err(self.error("negative list length"));
+        }
+
+        (0..count as usize).map(|_| self.read(ty)).collect()
+    }
+}
+
+pub struct PlyModel {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<(f64, f64)>,
+    pub colors: Vec<Vec3>,
+    pub indices: Vec<[usize; 3]>,
+}
+
+impl PlyModel {
+    pub fn into_mesh(self, mat: Arc<dyn Material + Sync + Send>) -> TriangleMesh {
+        TriangleMesh::new(self.positions, self.normals, self.uvs, self.indices, mat)
+    }
+
+    // Builds a diffuse mesh colored by the vertex colors, or white without them.
+    // Scans rarely carry normals, so missing ones are smoothed over the faces.
+    pub fn into_vertex_color_mesh(self) -> TriangleMesh {
+        let normals = if self.normals.is_empty() {
+            TriangleMesh::compute_vertex_normals(&self.positions, &self.indices)
+        } else {
+            self.normals
+        };
+
+        let texture = Arc::new(VertexColorTexture::new(&Vec3::new(1.0, 1.0, 1.0)));
+        TriangleMesh::new(
+            self.positions,
+            normals,
+            self.uvs,
+            self.indices,
+            Arc::new(Lambertian::new_with_texture(texture)),
+        )
+        .with_colors(self.colors)
+    }
+}
+
+pub fn load_ply(path: &str) -> Result<PlyModel, LoadError> {
+    let data = fs::read(path).map_err(|e| LoadError::io(path, e))?;
+    parse_ply(&data, path)
+}
+
+pub fn parse_ply(data: &[u8], path: &str) -> Result<PlyModel, LoadError> {
+    let (format, elements, body_start, header_lines) = parse_header(data, path)?;
+
+    let mut model = PlyModel {
+        positions: Vec::new(),
+        normals: Vec::new(),
+        uvs: Vec::new(),
+        colors: Vec::new(),
+        indices: Vec::new(),
+    };
+    let mut reader = BodyReader {
+        format,
+        path,
+        data,
+        pos: body_start,
+        line: header_lines,
+        tokens: Vec::new(),
+        token: 0,
+    };
+
+    for element in elements.iter() {
+        match element.name.as_str() {
+            "vertex" => read_vertices(&mut reader, element, &mut model)?,
+            "face" => read_faces(&mut reader, element, &mut model)?,
+            _ => {
+                for _ in 0..element.count {
+                    reader.begin_instance()?;
+                    for property in element.properties.iter() {
+                        match property.count_type {
+                            Some(count_type) => {
+                                reader.read_list(count_type, property.value_type)?;
+                            }
+                            None => {
+                                reader.read(property.value_type)?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let vertex_count = model.positions.len();
+    if let Some(face) = model
+        .indices
+        .iter()
+        .find(|f| f.iter().any(|i| *i >= vertex_count))
+    {
+        return Err(LoadError::invalid(
+            path,
+            format!(
+                "face {:?} references a vertex past the {} defined",
+                face, vertex_count
+            ),
+        ));
+    }
+
+    Ok(model)
+}
+
+fn parse_header(
+    data: &[u8],
+    path: &str,
+) -> Result<(Format, Vec<Element>, usize, usize), LoadError> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut pos = 0;
+    let mut line = 0;
+
+    loop {
+        let end = match data[pos..].iter().position(|b| *b == b'\n') {
+            Some(i) => pos + i,
+            None => return Err(LoadError::parse(path, line + 1, "missing end_header")),
+        };
+        let text = std::str::from_utf8(&data[pos..end])
+            .map_err(|_| LoadError::parse(path, line + 1, "invalid UTF-8 in header"))?
+            .trim();
+        pos = end + 1;
+        line += 1;
+
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        if line == 1 {
+            if text != "ply" {
+                return Err(LoadError::parse(path, line, "not a PLY file"));
+            }
+            continue;
+        }
+
+        match tokens.first().copied() {
+            Some("format") => {
+                format = match tokens.get(1).copied() {
+                    Some("ascii") => Some(Format::Ascii),
+                    Some("binary_little_endian") => Some(Format::BinaryLittleEndian),
+                    Some(other) => {
+                        return Err(LoadError::parse(
+                            path,
+                            line,
+                            format!("unsupported format '{}'", other),
+                        ))
+                    }
+                    None => return Err(LoadError::parse(path, line, "missing format")),
+                };
+            }
+            Some("element") => {
+                let count = tokens.get(2).and_then(|c| c.parse::<usize>().ok());
+                match (tokens.get(1), count) {
+                    (Some(name), Some(count)) => elements.push(Element {
+                        name: name.to_string(),
+                        count,
+                        properties: Vec::new(),
+                    }),
+                    _ => return Err(LoadError::parse(path, line, "malformed element")),
+                }
+            }
+            Some("property") => {
+                let element = match elements.last_mut() {
+                    Some(e) => e,
+                    None => {
+                        return Err(LoadError::parse(path, line, "property before any element"))
+                    }
+                };
+                let property = if tokens.get(1) == Some(&"list") {
+                    match (
+                        tokens.get(2).and_then(|t| ScalarType::parse(t)),
+                        tokens.get(3).and_then(|t| ScalarType::parse(t)),
+                        tokens.get(4),
+                    ) {
+                        (Some(count_type), Some(value_type), Some(name)) => Property {
+                            name: name.to_string(),
+                            value_type,
+                            count_type: Some(count_type),
+                        },
+                        _ => return Err(LoadError::parse(path, line, "malformed list property")),
+                    }
+                } else {
+                    match (
+                        tokens.get(1).and_then(|t| ScalarType::parse(t)),
+                        tokens.get(2),
+                    ) {
+                        (Some(value_type), Some(name)) => Property {
+                            name: name.to_string(),
+                            value_type,
+                            count_type: None,
+                        },
+                        _ => return Err(LoadError::parse(path, line, "malformed property")),
+                    }
+                };
+                element.properties.push(property);
+            }
+            Some("end_header") => break,
+            Some("comment") | Some("obj_info") | None => {}
+            Some(other) => {
+                return Err(LoadError::parse(
+                    path,
+                    line,
+                    format!("unexpected header keyword '{}'", other),
+                ))
+            }
+        }
+    }
+
+    match format {
+        Some(format) => Ok((format, elements, pos, line)),
+        None => Err(LoadError::invalid(path, "header has no format line")),
+    }
+}
+
+fn read_vertices(
+    reader: &mut BodyReader,
+    element: &Element,
+    model: &mut PlyModel,
+) -> Result<(), LoadError> {
+    let find = |names: &[&str]| {
+        element
+            .properties
+            .iter()
+            .position(|p| names.contains(&p.name.as_str()))
+    };
+    let position = [find(&["x"]), find(&["y"]), find(&["z"])];
+    let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+    let uv = [
+        find(&["u", "s", "texture_u", "texture_s"]),
+        find(&["v", "t", "texture_v", "texture_t"]),
+    ];
+    let color = [find(&["red"]), find(&["green"]), find(&["blue"])];
+
+    if position.iter().any(|p| p.is_none()) {
+        return Err(LoadError::invalid(
+            reader.path,
+            "vertex element needs x, y and z",
+        ));
+    }
+    let has_normals = normal.iter().all(|p| p.is_some());
+    let has_uvs = uv.iter().all(|p| p.is_some());
+    let has_colors = color.iter().all(|p| p.is_some());
+
+    // Integer colors are 0-255, float colors are already normalized.
+    let color_scale = match color[0].map(|i| element.properties[i].value_type) {
+        Some(ScalarType::Float32) | Some(ScalarType::Float64) => 1.0,
+        _ => 1.0 / 255.0,
+    };
+
+    let mut values = vec![0.0; element.properties.len()];
+    for _ in 0..element.count {
+        reader.begin_instance()?;
+        for (i, property) in element.properties.iter().enumerate() {
+            values[i] = match property.count_type {
+                Some(count_type) => {
+                    reader.read_list(count_type, property.value_type)?;
+                    0.0
+                }
+                None => reader.read(property.value_type)?,
+            };
+        }
+
+        let get = |i: Option<usize>| values[i.unwrap()];
+        model.positions.push(Point3::new(
+            get(position[0]),
+            get(position[1]),
+            get(position[2]),
+        ));
+        if has_normals {
+            model
+                .normals
+                .push(Vec3::new(get(normal[0]), get(normal[1]), get(normal[2])));
+        }
+        if has_uvs {
+            model.uvs.push((get(uv[0]), get(uv[1])));
+        }
+        if has_colors {
+            model
+                .colors
+                .push(color_scale * Vec3::new(get(color[0]), get(color[1]), get(color[2])));
+        }
+    }
+
+    Ok(())
+}
+
+fn read_faces(
+    reader: &mut BodyReader,
+    element: &Element,
+    model: &mut PlyModel,
+) -> Result<(), LoadError> {
+    let list = element
+        .properties
+        .iter()
+        .position(|p| p.name == "vertex_indices" || p.name == "vertex_index");
+    if list.is_none() {
+        return Err(LoadError::invalid(
+            reader.path,
+            "face element needs a vertex_indices list",
+        ));
+    }
+
+    for _ in 0..element.count {
+        reader.begin_instance()?;
+        for (i, property) in element.properties.iter().enumerate() {
+            let count_type = match property.count_type {
+                Some(count_type) => count_type,
+                None => {
+                    reader.read(property.value_type)?;
+                    continue;
+                }
+            };
+            let values = reader.read_list(count_type, property.value_type)?;
+            if Some(i) != list {
+                continue;
+            }
+
+            if values.len() < 3 || values.iter().any(|v| *v < 0.0) {
+                return Err(reader.error(format!("invalid face with {} indices", values.len())));
+            }
+            for k in 1..values.len() - 1 {
+                model.indices.push([
+                    values[0] as usize,
+                    values[k] as usize,
+                    values[k + 1] as usize,
+                ]);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        model::{
+            hit::{HitRecord, Hittable},
+            ray::Ray,
+        },
+        texture::texture::Texture,
+    };
+
+    use super::*;
+
+    const HEADER: &str = "ply\n\
+        format FORMAT 1.0\n\
+        comment a unit quad\n\
+        element vertex 4\n\
+        property float x\n\
+        property float y\n\
+        property float z\n\
+        property float u\n\
+        property float v\n\
+        property uchar red\n\
+        property uchar green\n\
+        property uchar blue\n\
+        element face 1\n\
+        property list uchar int vertex_indices\n\
+        end_header\n";
+
+    const CORNERS: [[f32; 5]; 4] = [
+        [0.0, 0.0, 0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0, 1.0, 0.0],
+        [1.0, 1.0, 0.0, 1.0, 1.0],
+        [0.0, 1.0, 0.0, 0.0, 1.0],
+    ];
+    const COLORS: [[u8; 3]; 4] = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]];
+
+    fn header(format: &str) -> String {
+        HEADER.replace("FORMAT", format)
+    }
+
+    fn check_quad(model: &PlyModel) {
+        assert_eq!(model.positions.len(), 4);
+        assert_eq!(model.positions[2], Point3::new(1.0, 1.0, 0.0));
+        assert_eq!(model.uvs[1], (1.0, 0.0));
+        assert_eq!(model.colors[1], Vec3::new(0.0, 1.0, 0.0));
+        assert!(model.normals.is_empty());
+        // The quad is fanned into two triangles around its first corner
+        assert_eq!(model.indices, vec![[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn test_ascii() {
+        let mut text = header("ascii");
+        for (c, color) in CORNERS.iter().zip(COLORS.iter()) {
+            text += &format!(
+                "{} {} {} {} {} {} {} {}\n",
+                c[0], c[1], c[2], c[3], c[4], color[0], color[1], color[2]
+            );
+        }
+        text += "4 0 1 2 3\n";
+
+        check_quad(&parse_ply(text.as_bytes(), "quad.ply").unwrap());
+    }
+
+    #[test]
+    fn test_binary_little_endian() {
+        let mut data = header("binary_little_endian").into_bytes();
+        for (c, color) in CORNERS.iter().zip(COLORS.iter()) {
+            for value in c {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+            data.extend_from_slice(color);
+        }
+        data.push(4);
+        for i in 0..4i32 {
+            data.extend_from_slice(&i.to_le_bytes());
+        }
+
+        check_quad(&parse_ply(&data, "quad.ply").unwrap());
+
+        // Cutting the last index off runs out of data
+        data.truncate(data.len() - 1);
+        assert!(matches!(
+            parse_ply(&data, "quad.ply"),
+            Err(LoadError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rejects_big_endian() {
+        let data = header("binary_big_endian");
+        match parse_ply(data.as_bytes(), "quad.ply") {
+            Err(LoadError::Parse { line, message, .. }) => {
+                assert_eq!(line, 2);
+                assert!(message.contains("binary_big_endian"));
+            }
+            _ => panic!("big endian PLY should be rejected"),
+        }
+    }
+
+    #[test]
+    fn test_vertex_color_mesh() {
+        let mut text = header("ascii");
+        for (c, color) in CORNERS.iter().zip(COLORS.iter()) {
+            text += &format!(
+                "{} {} {} {} {} {} {} {}\n",
+                c[0], c[1], c[2], c[3], c[4], color[0], color[1], color[2]
+            );
+        }
+        text += "4 0 1 2 3\n";
+        let mesh = parse_ply(text.as_bytes(), "quad.ply")
+            .unwrap()
+            .into_vertex_color_mesh();
+
+        let r = Ray::new(
+            &Point3::new(0.75, 0.25, 1.0),
+            &Vec3::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        let mut rec = HitRecord::default();
+        assert!(mesh.hit(&r, 0.001, f64::INFINITY, &mut rec));
+
+        // The file's uvs survive, and the color is blended from the corners
+        assert!((rec.u - 0.75).abs() < 1e-9 && (rec.v - 0.25).abs() < 1e-9);
+        let expected = Vec3::new(0.25, 0.5, 0.25);
+        assert!((rec.color.unwrap() - expected).length() < 1e-9);
+        let texture = VertexColorTexture::new(&Vec3::new(1.0, 1.0, 1.0));
+        assert!((texture.value_at(&rec) - expected).length() < 1e-9);
+    }
+}
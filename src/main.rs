@@ -3,16 +3,48 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use material::{diffuse_light::DiffuseLight, material::Material};
+use material::{
+    coated::Coated, conductor::Conductor, diffuse_light::DiffuseLight, material::Material,
+    medium_interface::MediumInterface, phase_material::PhaseMaterial, principled::Principled,
+    rough_dielectric::RoughDielectric,
+};
+use media::{
+    homogeneous::HomogeneousMedium,
+    medium::MediumSample,
+    phase::{DoubleHenyeyGreenstein, HenyeyGreenstein, PhaseFunction, Rayleigh},
+    stack::MediumStack,
+};
 use model::{
+    aabb::Aabb,
+    animated_transform::{AnimatedTransform, Keyframe},
     bvh::BvhNode,
+    cone::Cone,
     constant_medium::ConstantMedium,
+    csg::Csg,
+    cylinder::Cylinder,
+    disk::Disk,
+    grid_medium::{DensityGrid, GridMedium},
+    heightfield::Heightfield,
+    heterogeneous_medium::HeterogeneousMedium,
     hit::{HitRecord, Hittable},
+    hyperboloid::Hyperboloid,
+    instance::Instance,
+    matrix::Mat4,
     moving_sphere::MovingSphere,
+    paraboloid::Paraboloid,
+    quad::Quad,
+    quaternion::Quaternion,
     r#box::Box,
     ray::Ray,
     rotate::RotateY,
+    sdf::{
+        Repeat, Sdf, SdfBox, SdfCapsule, SdfHittable, SdfSphere, SdfTorus, SmoothUnion,
+        Subtraction, Twist,
+    },
+    torus::Torus,
+    transform::Transform,
     translate::Translate,
+    triangle::Triangle,
     vec3::Vec3,
     xy_rect::XyRect,
     xz_rect::XzRect,
@@ -21,19 +53,30 @@ use model::{
 use Vec3 as Point3;
 
 use rayon::prelude::{IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
-use texture::{checker::CheckerTexture, image::ImageTexture, noise::NoiseTexture};
+use spectrum::{
+    cie::{sample_wavelength, wavelength_to_rgb},
+    ior::Ior,
+    smits::rgb_to_spectrum,
+};
+use texture::{
+    checker::CheckerTexture, image::ImageTexture, noise::NoiseTexture, solid_color::SolidColor,
+};
 use util::{
     rtweekend::INFINITY,
     rtweekend::{random_double, random_double_by_range},
 };
 
 use crate::{
+    loader::{error::LoadError, gltf::load_gltf, grid::load_grid, obj::load_obj, ply::load_ply},
     material::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal},
     model::{camera::Camera, color::Color, hit::HittableList, sphere::Sphere},
     util::rtweekend::PI,
 };
+mod loader;
 mod material;
+mod media;
 mod model;
+mod spectrum;
 mod texture;
 mod util;
 
@@ -45,31 +88,171 @@ fn main() {
     const MAX_DEPTH: i32 = 50;
     const IMAGE_HEIGHT: usize = (IMAGE_WIDTH as f64 / ASPECT_RATIO) as usize;
 
-    // World
-    let world = cornell_box();
-    let background = Vec3::new(0.0, 0.0, 0.0);
+    // World, picked by name on the command line, followed by a file for the
+    // scenes that load one. lights are the surfaces worth sending rays
+    // towards directly. --spectral traces one wavelength per camera ray
+    // instead of RGB, for dispersion.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut spectral = args.iter().any(|arg| arg == "--spectral");
+    let positional: Vec<&str> = args
+        .iter()
+        .filter(|arg| !arg.starts_with("--"))
+        .map(String::as_str)
+        .collect();
+    let scene = positional.first().copied();
+    let file = || match positional.get(1) {
+        Some(file) => *file,
+        None => {
+            eprintln!("Scene '{}' needs a file to load", positional[0]);
+            std::process::exit(1);
+        }
+    };
+    let mut background = Vec3::new(0.0, 0.0, 0.0);
+    let mut lookfrom = Point3::new(278.0, 278.0, -800.0);
+    let mut lookat = Point3::new(278.0, 278.0, 0.0);
+    let mut vfov = 40.0;
+    let mut aperture = 0.0;
+    let mut scene_camera = None;
+
+    let (world, lights) = match scene.unwrap_or("cornell_box") {
+        "random" => {
+            background = Vec3::new(0.70, 0.80, 1.00);
+            lookfrom = Point3::new(13.0, 2.0, 3.0);
+            lookat = Point3::new(0.0, 0.0, 0.0);
+            vfov = 20.0;
+            aperture = 0.1;
+            random_scene()
+        }
+        "two_spheres" => {
+            background = Vec3::new(0.70, 0.80, 1.00);
+            lookfrom = Point3::new(13.0, 2.0, 3.0);
+            lookat = Point3::new(0.0, 0.0, 0.0);
+            vfov = 20.0;
+            two_spheres()
+        }
+        "two_perlin_spheres" => {
+            background = Vec3::new(0.70, 0.80, 1.00);
+            lookfrom = Point3::new(13.0, 2.0, 3.0);
+            lookat = Point3::new(0.0, 0.0, 0.0);
+            vfov = 20.0;
+            two_perlin_spheres()
+        }
+        "earth" => {
+            background = Vec3::new(0.70, 0.80, 1.00);
+            lookfrom = Point3::new(13.0, 2.0, 3.0);
+            lookat = Point3::new(0.0, 0.0, 0.0);
+            vfov = 20.0;
+            earth()
+        }
+        "simple_light" => {
+            lookfrom = Point3::new(26.0, 3.0, 6.0);
+            lookat = Point3::new(0.0, 2.0, 0.0);
+            vfov = 20.0;
+            simple_light()
+        }
+        "cornell_box" => cornell_box(),
+        "cornell_smoke" => cornell_smoke(),
+        "final" => {
+            lookfrom = Point3::new(478.0, 278.0, -600.0);
+            final_scene()
+        }
+        "motion" => motion(),
+        "media" => or_exit(media(positional.get(1).copied())),
+        "liquids" => liquids(),
+        "terrain" => {
+            background = Vec3::new(0.50, 0.65, 0.90);
+            lookfrom = Point3::new(5.0, 4.0, 15.0);
+            lookat = Point3::new(5.0, 0.5, 4.0);
+            vfov = 45.0;
+            or_exit(terrain(positional.get(1).copied()))
+        }
+        "solids" => {
+            background = Vec3::new(0.70, 0.80, 1.00);
+            lookfrom = Point3::new(0.0, 3.0, 10.0);
+            lookat = Point3::new(0.0, 0.6, 0.0);
+            vfov = 45.0;
+            solids()
+        }
+        "instances" => {
+            background = Vec3::new(0.70, 0.80, 1.00);
+            lookfrom = Point3::new(0.0, 2.5, 9.0);
+            lookat = Point3::new(0.0, 1.8, 0.0);
+            vfov = 35.0;
+            instances()
+        }
+        "gltf" => {
+            // The file's first camera if it has one, or the mesh scene's view
+            background = Vec3::new(0.70, 0.80, 1.00);
+            lookfrom = Point3::new(0.0, 2.0, 6.0);
+            lookat = Point3::new(0.0, 1.0, 0.0);
+            vfov = 35.0;
+            let scene = or_exit(load_gltf(file(), ASPECT_RATIO));
+            for warning in &scene.warnings {
+                eprintln!("{}", warning);
+            }
+            scene_camera = scene.cameras.into_iter().next();
+
+            let mut world = HittableList::new();
+            if scene_camera.is_some() {
+                world = scene.world;
+            } else {
+                world.add(Arc::new(fit_to_floor(Arc::new(scene.world), 2.0)));
+            }
+            (world, HittableList::new())
+        }
+        "mesh" => {
+            background = Vec3::new(0.70, 0.80, 1.00);
+            lookfrom = Point3::new(0.0, 2.0, 6.0);
+            lookat = Point3::new(0.0, 1.0, 0.0);
+            vfov = 35.0;
+            or_exit(mesh(file()))
+        }
+        "shapes" => {
+            background = Vec3::new(0.70, 0.80, 1.00);
+            lookfrom = Point3::new(0.0, 5.0, 12.0);
+            lookat = Point3::new(0.0, 0.8, 0.0);
+            vfov = 38.0;
+            shapes()
+        }
+        "materials" => {
+            background = Vec3::new(0.70, 0.80, 1.00);
+            lookfrom = Point3::new(0.0, 3.5, 8.0);
+            lookat = Point3::new(0.0, 0.4, -0.5);
+            vfov = 38.0;
+            materials()
+        }
+        "dispersion" => {
+            spectral = true;
+            lookfrom = Point3::new(0.0, 5.0, 9.0);
+            lookat = Point3::new(0.0, 0.5, 0.0);
+            vfov = 40.0;
+            dispersion()
+        }
+        other => {
+            eprintln!("Unknown scene '{}'", other);
+            std::process::exit(1);
+        }
+    };
 
     // Camera
-    let lookfrom = Point3::new(278.0, 278.0, -800.0);
-    let lookat = Point3::new(278.0, 278.0, 0.0);
     let vup = Vec3::new(0.0, 1.0, 0.0);
     let dist_to_focus = 10.0;
-    let aperture = 0.0;
-    let vfov = 40.0;
     let time0 = 0.0;
     let time1 = 1.0;
 
-    let camera = Camera::new(
-        &lookfrom,
-        &lookat,
-        &vup,
-        vfov,
-        ASPECT_RATIO,
-        aperture,
-        dist_to_focus,
-        time0,
-        time1,
-    );
+    let camera = scene_camera.unwrap_or_else(|| {
+        Camera::new(
+            &lookfrom,
+            &lookat,
+            &vup,
+            vfov,
+            ASPECT_RATIO,
+            aperture,
+            dist_to_focus,
+            time0,
+            time1,
+        )
+    });
 
     // Render
     print!("P3\n{} {}\n255\n", IMAGE_WIDTH, IMAGE_HEIGHT);
@@ -91,7 +274,14 @@ fn main() {
                 let u = (x as f64 + random_double()) / (IMAGE_WIDTH as f64 - 1.0);
                 let v = (j as f64 + random_double()) / (IMAGE_HEIGHT as f64 - 1.0);
                 let r = camera.get_ray(u, v);
-                pixel_color += ray_color(&r, &background, &world, MAX_DEPTH);
+                if spectral {
+                    let lambda = sample_wavelength(s, SAMPLES_PER_PIXEL);
+                    let r = r.with_wavelength(Some(lambda));
+                    let radiance = ray_color(&r, &background, &world, &lights, MAX_DEPTH, 1.0);
+                    pixel_color += wavelength_to_rgb(lambda, radiance.x());
+                } else {
+                    pixel_color += ray_color(&r, &background, &world, &lights, MAX_DEPTH, 1.0);
+                }
             }
 
             let s = pixel_color.as_color_repr(SAMPLES_PER_PIXEL);
@@ -111,7 +301,29 @@ fn main() {
     eprintln!("\nDone.");
 }
 
-fn ray_color(r: &Ray, background: &Vec3, world: &dyn Hittable, depth: i32) -> Vec3 {
+// An RGB color as seen by r. Spectral rays see the value of its upsampled
+// spectrum at their wavelength, the same in every channel.
+fn color_at(r: &Ray, color: &Vec3) -> Vec3 {
+    match r.wavelength() {
+        Some(lambda) => {
+            let value = rgb_to_spectrum(color, lambda);
+            Vec3::new(value, value, value)
+        }
+        None => *color,
+    }
+}
+
+// The light arriving along r. emission_weight scales the light emitted by
+// whatever r reaches first, for rays scattered from a surface that also
+// sampled the lights, so that no light is counted twice.
+fn ray_color(
+    r: &Ray,
+    background: &Vec3,
+    world: &dyn Hittable,
+    lights: &HittableList,
+    depth: i32,
+    emission_weight: f64,
+) -> Vec3 {
     let mut rec = HitRecord::default();
 
     // If we've exceeded the ray bounce limit, no more light is gathered.
@@ -119,26 +331,134 @@ fn ray_color(r: &Ray, background: &Vec3, world: &dyn Hittable, depth: i32) -> Ve
         return Vec3::new(0.0, 0.0, 0.0);
     }
 
+    let hit = world.hit(r, 0.001, INFINITY, &mut rec);
+
+    // A ray travelling through a medium may scatter before the next surface
+    let mut transmittance = Vec3::new(1.0, 1.0, 1.0);
+    if let Some(medium) = r.medium() {
+        let t_max = if hit { rec.t } else { INFINITY };
+        match medium.sample(r, t_max) {
+            MediumSample::Scatter { t, weight, phase } => {
+                let scattered = Ray::new_in_media(
+                    &r.at(t),
+                    &phase.sample(r.dir()),
+                    r.time(),
+                    r.media().clone(),
+                )
+                .with_wavelength(r.wavelength());
+                return weight * ray_color(&scattered, background, world, lights, depth - 1, 1.0);
+            }
+            MediumSample::Pass { weight } => transmittance = weight,
+        }
+    }
+
     // If the ray hits nothing, return the background color
-    if !world.hit(r, 0.001, INFINITY, &mut rec) {
-        return background.clone();
+    if !hit {
+        return transmittance * emission_weight * color_at(r, background);
     }
 
     let mut scattered = Ray::new(&Vec3::new(0.0, 0.0, 0.0), &Vec3::new(0.0, 0.0, 0.0), 0.0);
     let mut attenuation = Vec3::new(0.0, 0.0, 0.0);
-    let emitted = rec.material.emitted(rec.u, rec.v, &rec.p);
+    let emitted = emission_weight * color_at(r, &rec.material.emitted(rec.u, rec.v, &rec.p));
+
+    // Materials that can be evaluated also look straight at a light through a
+    // shadow ray. The light found that way and the light found by scattering
+    // are both weighted by the balance heuristic.
+    let sample_lights = !lights.objects.is_empty() && !rec.material.is_specular(r, &rec);
+    let mut direct = Vec3::new(0.0, 0.0, 0.0);
+    if sample_lights {
+        let dir = lights.random(&rec.p);
+        let light_pdf = lights.pdf_value(&rec.p, &dir);
+        let f = rec.material.eval(r, &rec, &dir);
+        if light_pdf > 0.0 && !f.near_zero() {
+            let shadow = Ray::new_in_media(&rec.p, &dir, r.time(), media_beyond(r, &rec, &dir))
+                .with_wavelength(r.wavelength());
+            direct = color_at(r, &f) * shadow_color(&shadow, background, world)
+                / (light_pdf + rec.material.pdf(r, &rec, &dir));
+        }
+    }
 
     if !rec
         .material
         .scatter(r, &rec, &mut attenuation, &mut scattered)
     {
-        return emitted;
+        return transmittance * (emitted + direct);
     }
 
-    return emitted + attenuation * ray_color(&scattered, background, world, depth - 1);
+    // A ray going straight through a boundary is still the one that found the
+    // light it reaches, and keeps its weight
+    let mut scattered_weight = if rec.material.is_pass_through() {
+        emission_weight
+    } else {
+        1.0
+    };
+    if sample_lights {
+        let pdf = rec.material.pdf(r, &rec, scattered.dir());
+        if pdf <= 0.0 {
+            return transmittance * (emitted + direct);
+        }
+        attenuation = rec.material.eval(r, &rec, scattered.dir()) / pdf;
+        scattered_weight = pdf / (pdf + lights.pdf_value(&rec.p, scattered.dir()));
+    }
+
+    let media = media_beyond(r, &rec, scattered.dir());
+    let scattered = Ray::new_in_media(scattered.origin(), scattered.dir(), scattered.time(), media)
+        .with_wavelength(r.wavelength());
+
+    return transmittance
+        * (emitted
+            + direct
+            + color_at(r, &attenuation)
+                * ray_color(
+                    &scattered,
+                    background,
+                    world,
+                    lights,
+                    depth - 1,
+                    scattered_weight,
+                ));
+}
+
+// The objects a ray leaving the hit along dir is inside of. Crossing the
+// surface of a material with an interior enters it through the front face
+// and leaves it through the back. Anything else stays inside the same
+// objects.
+fn media_beyond(r: &Ray, rec: &HitRecord, dir: &Vec3) -> MediumStack {
+    let crossed = dir.dot(&rec.normal) < 0.0;
+    match rec.material.interior() {
+        Some(interior) if crossed => r.media().crossing(&interior, rec.front_face).1,
+        _ => r.media().clone(),
+    }
+}
+
+// The light reaching the origin of r straight from the first surface along
+// it, or from the background, thinned out by the media on the way. Media in
+// the scene are seen through by ratio tracking, the medium the ray is in by
+// its transmittance. Surfaces that only mark the boundary of a medium let the
+// ray carry on into the next one.
+fn shadow_color(r: &Ray, background: &Vec3, world: &dyn Hittable) -> Vec3 {
+    let mut rec = HitRecord::default();
+    let hit = world.hit_surface(r, 0.001, INFINITY, &mut rec);
+    let t_max = if hit { rec.t } else { INFINITY };
+
+    let mut transmittance = world.transmittance(r, 0.001, t_max) * Vec3::new(1.0, 1.0, 1.0);
+    if let Some(medium) = r.medium() {
+        transmittance *= medium.transmittance(r, t_max);
+    }
+
+    if !hit {
+        return transmittance * color_at(r, background);
+    }
+    if rec.material.is_pass_through() {
+        let beyond = Ray::new_in_media(&rec.p, r.dir(), r.time(), media_beyond(r, &rec, r.dir()))
+            .with_wavelength(r.wavelength());
+        return transmittance * shadow_color(&beyond, background, world);
+    }
+
+    transmittance * color_at(r, &rec.material.emitted(rec.u, rec.v, &rec.p))
 }
 
-fn random_scene() -> HittableList {
+fn random_scene() -> (HittableList, HittableList) {
     let mut world = HittableList::new();
 
     let ground_material = Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5)));
@@ -219,10 +539,10 @@ fn random_scene() -> HittableList {
         material3,
     )));
 
-    world
+    (world, HittableList::new())
 }
 
-fn two_spheres() -> HittableList {
+fn two_spheres() -> (HittableList, HittableList) {
     let mut world = HittableList::new();
 
     let checker = Arc::new(CheckerTexture::new_with_color(
@@ -242,10 +562,10 @@ fn two_spheres() -> HittableList {
         Arc::new(Lambertian::new_with_texture(checker)),
     )));
 
-    world
+    (world, HittableList::new())
 }
 
-fn two_perlin_spheres() -> HittableList {
+fn two_perlin_spheres() -> (HittableList, HittableList) {
     let mut world = HittableList::new();
 
     let pertext = Arc::new(NoiseTexture::new(4.0));
@@ -262,10 +582,10 @@ fn two_perlin_spheres() -> HittableList {
         Arc::new(Lambertian::new_with_texture(pertext)),
     )));
 
-    world
+    (world, HittableList::new())
 }
 
-fn earth() -> HittableList {
+fn earth() -> (HittableList, HittableList) {
     let mut world = HittableList::new();
 
     let earth_texture = Arc::new(ImageTexture::new("earthmap.jpg".to_owned()));
@@ -274,10 +594,10 @@ fn earth() -> HittableList {
 
     world.add(globe);
 
-    world
+    (world, HittableList::new())
 }
 
-fn simple_light() -> HittableList {
+fn simple_light() -> (HittableList, HittableList) {
     let mut world = HittableList::new();
 
     let pertext = Arc::new(NoiseTexture::new(4.0));
@@ -293,14 +613,50 @@ fn simple_light() -> HittableList {
     )));
 
     let difflight = Arc::new(DiffuseLight::new_with_color((Vec3::new(4.0, 4.0, 4.0))));
-    world.add(Arc::new(XyRect::new(3.0, 5.0, 1.0, 4.0, -2.0, difflight)));
+    let light: Arc<dyn Hittable + Sync + Send> =
+        Arc::new(XyRect::new(3.0, 5.0, 1.0, 4.0, -2.0, difflight));
+    world.add(light.clone());
 
-    world
+    let mut lights = HittableList::new();
+    lights.add(light);
+
+    (world, lights)
 }
 
-fn cornell_box() -> HittableList {
+// Spheres of water, crown glass, flint glass and diamond under a small
+// light. Their caustics split into colors when rendered spectrally.
+fn dispersion() -> (HittableList, HittableList) {
     let mut world = HittableList::new();
 
+    let white = Arc::new(Lambertian::new(&Vec3::new(0.73, 0.73, 0.73)));
+    world.add(Arc::new(XzRect::new(-20.0, 20.0, -20.0, 20.0, 0.0, white)));
+
+    let water = Ior::Cauchy {
+        a: 1.3199,
+        b: 0.006878,
+    };
+    let glasses = [water, Ior::BK7, Ior::SF11, Ior::DIAMOND];
+    for (i, ior) in glasses.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(2.2 * (i as f64 - 1.5), 1.0, 0.0),
+            1.0,
+            Arc::new(Dielectric::new_with_ior(ior)),
+        )));
+    }
+
+    let light = Arc::new(DiffuseLight::new_with_color(Vec3::new(200.0, 200.0, 200.0)));
+    let light: Arc<dyn Hittable + Sync + Send> =
+        Arc::new(XzRect::new(-0.5, 0.5, -4.5, -3.5, 8.0, light));
+    world.add(light.clone());
+
+    let mut lights = HittableList::new();
+    lights.add(light);
+
+    (world, lights)
+}
+
+// The walls of the Cornell box, returning its ceiling light
+fn cornell_room(world: &mut HittableList) -> Arc<dyn Hittable + Sync + Send> {
     let red = Arc::new(Lambertian::new(&Vec3::new(0.65, 0.05, 0.05)));
     let white = Arc::new(Lambertian::new(&Vec3::new(0.73, 0.73, 0.73)));
     let green = Arc::new(Lambertian::new(&Vec3::new(0.12, 0.45, 0.15)));
@@ -308,9 +664,9 @@ fn cornell_box() -> HittableList {
 
     world.add(Arc::new(YzRect::new(0.0, 555.0, 0.0, 555.0, 555.0, green)));
     world.add(Arc::new(YzRect::new(0.0, 555.0, 0.0, 555.0, 0.0, red)));
-    world.add(Arc::new(XzRect::new(
-        213.0, 343.0, 227.0, 332.0, 554.0, light,
-    )));
+    let ceiling_light: Arc<dyn Hittable + Sync + Send> =
+        Arc::new(XzRect::new(213.0, 343.0, 227.0, 332.0, 554.0, light));
+    world.add(ceiling_light.clone());
     world.add(Arc::new(XzRect::new(
         0.0,
         555.0,
@@ -327,14 +683,16 @@ fn cornell_box() -> HittableList {
         555.0,
         white.clone(),
     )));
-    world.add(Arc::new(XyRect::new(
-        0.0,
-        555.0,
-        0.0,
-        555.0,
-        555.0,
-        white.clone(),
-    )));
+    world.add(Arc::new(XyRect::new(0.0, 555.0, 0.0, 555.0, 555.0, white)));
+
+    ceiling_light
+}
+
+fn cornell_box() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+
+    let white = Arc::new(Lambertian::new(&Vec3::new(0.73, 0.73, 0.73)));
+    let ceiling_light = cornell_room(&mut world);
 
     // world.add(Arc::new(Box::new(
     //     &Point3::new(130.0, 0.0, 65.0),
@@ -365,10 +723,13 @@ fn cornell_box() -> HittableList {
     box2 = Arc::new(Translate::new(box2, &Vec3::new(130.0, 0.0, 65.0)));
     world.add(box2);
 
-    world
+    let mut lights = HittableList::new();
+    lights.add(ceiling_light);
+
+    (world, lights)
 }
 
-fn cornell_smoke() -> HittableList {
+fn cornell_smoke() -> (HittableList, HittableList) {
     let mut world = HittableList::new();
 
     let red = Arc::new(Lambertian::new(&Vec3::new(0.65, 0.05, 0.05)));
@@ -378,9 +739,9 @@ fn cornell_smoke() -> HittableList {
 
     world.add(Arc::new(YzRect::new(0.0, 555.0, 0.0, 555.0, 555.0, green)));
     world.add(Arc::new(YzRect::new(0.0, 555.0, 0.0, 555.0, 0.0, red)));
-    world.add(Arc::new(XzRect::new(
-        113.0, 443.0, 127.0, 432.0, 554.0, light,
-    )));
+    let ceiling_light: Arc<dyn Hittable + Sync + Send> =
+        Arc::new(XzRect::new(113.0, 443.0, 127.0, 432.0, 554.0, light));
+    world.add(ceiling_light.clone());
     world.add(Arc::new(XzRect::new(
         0.0,
         555.0,
@@ -435,12 +796,20 @@ fn cornell_smoke() -> HittableList {
         Vec3::new(1.0, 1.0, 1.0),
     )));
 
-    world
+    let mut lights = HittableList::new();
+    lights.add(ceiling_light);
+
+    (world, lights)
 }
 
-fn final_scene() -> HittableList {
+fn final_scene() -> (HittableList, HittableList) {
     let mut boxes1 = HittableList::new();
     let ground = Arc::new(Lambertian::new(&Vec3::new(0.48, 0.83, 0.53)));
+    let unit_box: Arc<dyn Hittable + Sync + Send> = Arc::new(Box::new(
+        &Vec3::new(0.0, 0.0, 0.0),
+        &Vec3::new(1.0, 1.0, 1.0),
+        ground,
+    ));
 
     let boxes_per_side = 20;
     for i in 0..boxes_per_side {
@@ -449,15 +818,11 @@ fn final_scene() -> HittableList {
             let x0 = -1000.0 + i as f64 * w;
             let z0 = -1000.0 + j as f64 * w;
             let y0 = 0.0;
-            let x1 = x0 + w;
             let y1 = random_double_by_range(1.0, 101.0);
-            let z1 = z0 + w;
 
-            boxes1.add(Arc::new(Box::new(
-                &Vec3::new(x0, y0, z0),
-                &Vec3::new(x1, y1, z1),
-                ground.clone(),
-            )));
+            let m =
+                Mat4::translate(&Vec3::new(x0, y0, z0)) * Mat4::scale(&Vec3::new(w, y1 - y0, w));
+            boxes1.add(Arc::new(Instance::new(unit_box.clone(), &m)));
         }
     }
 
@@ -466,14 +831,15 @@ fn final_scene() -> HittableList {
     world.add(Arc::new(BvhNode::new_with_list(&boxes1, 0.0, 1.0)));
 
     let light = Arc::new(DiffuseLight::new_with_color(Vec3::new(7.0, 7.0, 7.0)));
-    world.add(Arc::new(XzRect::new(
+    let ceiling_light: Arc<dyn Hittable + Sync + Send> = Arc::new(XzRect::new(
         123.0,
         423.0,
         147.0,
         412.0,
         554.0,
         light.clone(),
-    )));
+    ));
+    world.add(ceiling_light.clone());
 
     let center1 = Point3::new(400.0, 400.0, 200.0);
     let center2 = center1 + Point3::new(30.0, 0.0, 0.0);
@@ -498,18 +864,18 @@ fn final_scene() -> HittableList {
         Arc::new(Metal::new(&Vec3::new(0.8, 0.8, 0.9), 1.0)),
     )));
 
-    let mut boundary = Arc::new(Sphere::new(
+    // A glass sphere filled with blue smoke of density 0.2
+    let smoke_albedo = Vec3::new(0.2, 0.4, 0.9);
+    let smoke = Arc::new(HomogeneousMedium::new(
+        &(0.2 * (Vec3::new(1.0, 1.0, 1.0) - smoke_albedo)),
+        &(0.2 * smoke_albedo),
+    ));
+    world.add(Arc::new(Sphere::new(
         Point3::new(360.0, 150.0, 145.0),
         70.0,
-        Arc::new(Dielectric::new(1.5)),
-    ));
-    world.add(boundary.clone());
-    world.add(Arc::new(ConstantMedium::new(
-        boundary.clone(),
-        0.2,
-        Vec3::new(0.2, 0.4, 0.9),
+        Arc::new(Dielectric::new_with_medium(1.5, smoke)),
     )));
-    boundary = Arc::new(Sphere::new(
+    let boundary = Arc::new(Sphere::new(
         Point3::new(0.0, 0.0, 0.0),
         5000.0,
         Arc::new(Dielectric::new(1.5)),
@@ -554,5 +920,752 @@ fn final_scene() -> HittableList {
         &Vec3::new(-100.0, 270.0, 395.0),
     )));
 
-    world
+    let mut lights = HittableList::new();
+    lights.add(ceiling_light);
+
+    (world, lights)
+}
+
+// Motion blur from keyframes: a box sliding and turning over the shutter,
+// and a sphere swelling as it rises
+fn motion() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+    let light = cornell_room(&mut world);
+
+    let white = Arc::new(Lambertian::new(&Vec3::new(0.73, 0.73, 0.73)));
+    let cube: Arc<dyn Hittable + Sync + Send> = Arc::new(Box::new(
+        &Point3::new(-60.0, 0.0, -60.0),
+        &Point3::new(60.0, 120.0, 60.0),
+        white,
+    ));
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let unit = Vec3::new(1.0, 1.0, 1.0);
+    world.add(Arc::new(AnimatedTransform::new(
+        cube,
+        vec![
+            Keyframe::new(
+                0.0,
+                Vec3::new(150.0, 0.0, 200.0),
+                Quaternion::identity(),
+                unit,
+            ),
+            Keyframe::new(
+                0.5,
+                Vec3::new(250.0, 0.0, 220.0),
+                Quaternion::from_axis_angle(&up, 30.0),
+                unit,
+            ),
+            Keyframe::new(
+                1.0,
+                Vec3::new(300.0, 0.0, 300.0),
+                Quaternion::from_axis_angle(&up, 90.0),
+                unit,
+            ),
+        ],
+    )));
+
+    let blue = Arc::new(Lambertian::new(&Vec3::new(0.2, 0.3, 0.7)));
+    let ball: Arc<dyn Hittable + Sync + Send> =
+        Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, blue));
+    world.add(Arc::new(AnimatedTransform::new(
+        ball,
+        vec![
+            Keyframe::new(
+                0.0,
+                Vec3::new(400.0, 60.0, 250.0),
+                Quaternion::identity(),
+                60.0 * unit,
+            ),
+            Keyframe::new(
+                1.0,
+                Vec3::new(400.0, 200.0, 250.0),
+                Quaternion::identity(),
+                Vec3::new(90.0, 60.0, 90.0),
+            ),
+        ],
+    )));
+
+    let mut lights = HittableList::new();
+    lights.add(light);
+
+    (world, lights)
+}
+
+// The analytic primitives on a checkered floor. The quadrics are built
+// around the z axis and stood up along y.
+fn shapes() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::new_with_color(
+        &Vec3::new(0.2, 0.3, 0.1),
+        &Vec3::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(XzRect::new(
+        -50.0,
+        50.0,
+        -50.0,
+        50.0,
+        0.0,
+        Arc::new(Lambertian::new_with_texture(checker)),
+    )));
+
+    let upright = |object: Arc<dyn Hittable + Sync + Send>, x: f64, z: f64| {
+        Arc::new(
+            Transform::new(object)
+                .rotate_x(-90.0)
+                .translate(&Vec3::new(x, 0.0, z)),
+        )
+    };
+    let color = |r: f64, g: f64, b: f64| Arc::new(Lambertian::new(&Vec3::new(r, g, b)));
+
+    world.add(upright(
+        Arc::new(Cylinder::new_capped(
+            0.7,
+            0.0,
+            1.5,
+            360.0,
+            color(0.8, 0.3, 0.2),
+        )),
+        -3.0,
+        -1.5,
+    ));
+    world.add(upright(
+        Arc::new(Cone::new(1.8, 0.8, 360.0, color(0.9, 0.7, 0.2))),
+        -1.0,
+        -1.5,
+    ));
+    world.add(upright(
+        Arc::new(Paraboloid::new(0.8, 0.0, 1.5, 360.0, color(0.2, 0.6, 0.3))),
+        1.0,
+        -1.5,
+    ));
+    world.add(upright(
+        Arc::new(Hyperboloid::new(
+            &Point3::new(0.8, -0.4, 0.0),
+            &Point3::new(0.8, 0.4, 1.6),
+            360.0,
+            color(0.3, 0.4, 0.8),
+        )),
+        3.0,
+        -1.5,
+    ));
+
+    // A cut-away cylinder and cone, showing their insides, an annulus and
+    // a torus standing on its rim
+    world.add(upright(
+        Arc::new(Cylinder::new(0.6, 0.0, 1.0, 270.0, color(0.7, 0.7, 0.7))),
+        -3.0,
+        1.5,
+    ));
+    world.add(upright(
+        Arc::new(Cone::new_clipped(
+            1.6,
+            0.7,
+            0.0,
+            1.0,
+            240.0,
+            color(0.6, 0.3, 0.7),
+        )),
+        -1.0,
+        1.5,
+    ));
+    world.add(upright(
+        Arc::new(Disk::new(0.01, 0.7, 0.35, 360.0, color(0.9, 0.5, 0.1))),
+        1.0,
+        1.5,
+    ));
+    let torus = Arc::new(Torus::new(0.5, 0.2, color(0.8, 0.2, 0.5)));
+    world.add(Arc::new(
+        Transform::new(torus)
+            .rotate_axis(&Vec3::new(0.0, 1.0, 0.0), 30.0)
+            .translate(&Vec3::new(3.0, 0.7, 1.5)),
+    ));
+
+    // Tetrahedra of triangles in front, flat and with the vertex normals of
+    // a sphere through its corners
+    let corners = [
+        Point3::new(0.0, 1.2, 0.0),
+        Point3::new(0.0, 0.0, 0.7),
+        Point3::new(0.6, 0.0, -0.35),
+        Point3::new(-0.6, 0.0, -0.35),
+    ];
+    let center = Point3::new(0.0, 0.3, 0.0);
+    let faces = [[0, 1, 2], [0, 2, 3], [0, 3, 1], [1, 3, 2]];
+    for face in faces {
+        let [a, b, c] = face.map(|i| corners[i]);
+        let flat = Vec3::new(-1.0, 0.0, 3.5);
+        world.add(Arc::new(Triangle::new(
+            &(a + flat),
+            &(b + flat),
+            &(c + flat),
+            color(0.2, 0.7, 0.7),
+        )));
+
+        let smooth = Vec3::new(1.0, 0.0, 3.5);
+        world.add(Arc::new(Triangle::new_with_attributes(
+            [a + smooth, b + smooth, c + smooth],
+            Some([a, b, c].map(|v| (v - center).unit_vector())),
+            None,
+            color(0.2, 0.7, 0.7),
+        )));
+    }
+
+    (world, HittableList::new())
+}
+
+// Loading failures end the program
+fn or_exit<T>(result: Result<T, LoadError>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+// Scales and moves an object to stand on the origin, size units across
+fn fit_to_floor(object: Arc<dyn Hittable + Sync + Send>, size: f64) -> Transform {
+    let mut bbox = Aabb::new(Point3::default(), Point3::default());
+    object.bounding_box(0.0, 1.0, &mut bbox);
+    let extent = bbox.maximum - bbox.minimum;
+    let largest = extent.x().max(extent.y()).max(extent.z());
+    let base = Point3::new(
+        0.5 * (bbox.minimum.x() + bbox.maximum.x()),
+        bbox.minimum.y(),
+        0.5 * (bbox.minimum.z() + bbox.maximum.z()),
+    );
+
+    let scale = size / largest;
+    Transform::new(object)
+        .translate(&-base)
+        .scale(&Vec3::new(scale, scale, scale))
+}
+
+// An OBJ or PLY model on a floor under the sky. PLY vertex colors are
+// shown when there are some.
+fn mesh(path: &str) -> Result<(HittableList, HittableList), LoadError> {
+    let mut world = HittableList::new();
+
+    let white = Arc::new(Lambertian::new(&Vec3::new(0.73, 0.73, 0.73)));
+    world.add(Arc::new(XzRect::new(
+        -50.0,
+        50.0,
+        -50.0,
+        50.0,
+        0.0,
+        white.clone(),
+    )));
+
+    let mut model = HittableList::new();
+    if path.to_lowercase().ends_with(".ply") {
+        let ply = load_ply(path)?;
+        let mesh = if ply.colors.is_empty() {
+            ply.into_mesh(white)
+        } else {
+            ply.into_vertex_color_mesh()
+        };
+        eprintln!("{} faces", mesh.face_count());
+        model.add(Arc::new(mesh));
+    } else {
+        for object in load_obj(path, white)? {
+            eprintln!(
+                "{}: {} faces of {}",
+                object.name,
+                object.mesh.face_count(),
+                object
+                    .material_name
+                    .as_deref()
+                    .unwrap_or("the default material")
+            );
+            model.add(object.mesh);
+        }
+    }
+    world.add(Arc::new(fit_to_floor(Arc::new(model), 2.0)));
+
+    Ok((world, HittableList::new()))
+}
+
+// One cone shared by a ring of instances, each with its own color and all
+// pointing at a torus in the middle, which is turned to face off to the
+// side. The ring is built flat around the origin, then tilted and lifted as
+// a whole.
+fn instances() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+
+    let ground = Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5)));
+    world.add(Arc::new(XzRect::new(-50.0, 50.0, -50.0, 50.0, 0.0, ground)));
+
+    let cone: Arc<dyn Hittable + Sync + Send> = Arc::new(Cone::new(
+        0.6,
+        0.2,
+        360.0,
+        Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5))),
+    ));
+    let mut ring = HittableList::new();
+    let count = 16;
+    for i in 0..count {
+        let angle = 360.0 * i as f64 / count as f64;
+        let from = Mat4::rotate_z(angle).transform_point(&Point3::new(1.8, 0.0, 0.0));
+        let placement = Mat4::look_at(&from, &Point3::default(), &Vec3::new(0.0, 0.0, 1.0));
+
+        let hue = i as f64 / count as f64;
+        let color = Vec3::new(
+            0.5 + 0.4 * (2.0 * PI * hue).cos(),
+            0.5 + 0.4 * (2.0 * PI * (hue - 1.0 / 3.0)).cos(),
+            0.5 + 0.4 * (2.0 * PI * (hue - 2.0 / 3.0)).cos(),
+        );
+        ring.add(Arc::new(Instance::new_with_material(
+            cone.clone(),
+            &placement,
+            Arc::new(Lambertian::new(&color)),
+        )));
+    }
+
+    let gold = Arc::new(Metal::new(&Vec3::new(0.8, 0.6, 0.2), 0.1));
+    ring.add(Arc::new(
+        Transform::new(Arc::new(Torus::new(0.6, 0.2, gold))).look_at(
+            &Point3::default(),
+            &Point3::new(1.0, 0.5, 1.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+        ),
+    ));
+
+    world.add(Arc::new(
+        Transform::new(Arc::new(BvhNode::new_with_list(&ring, 0.0, 1.0)))
+            .rotate_x(-20.0)
+            .rotate_z(15.0)
+            .translate(&Vec3::new(0.0, 2.2, 0.0)),
+    ));
+
+    (world, HittableList::new())
+}
+
+// Solids that are not made of surfaces: distance functions rendered by
+// sphere tracing on the left, each placed by a transform around its own
+// origin, and constructive solid geometry on the right
+fn solids() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+
+    let ground = Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5)));
+    world.add(Arc::new(XzRect::new(-50.0, 50.0, -50.0, 50.0, 0.0, ground)));
+
+    let place = |sdf: Arc<dyn Sdf + Sync + Send>,
+                 half: Vec3,
+                 mat: Arc<dyn Material + Sync + Send>,
+                 at: Vec3| {
+        Arc::new(
+            Transform::new(Arc::new(SdfHittable::new(sdf, Aabb::new(-half, half), mat)))
+                .translate(&at),
+        )
+    };
+
+    // A sphere melting into a bar
+    let blob = SmoothUnion::new(
+        Arc::new(SdfSphere::new(0.5)),
+        Arc::new(SdfCapsule::new(
+            &Point3::new(-0.5, -0.3, 0.0),
+            &Point3::new(0.5, 0.3, 0.0),
+            0.2,
+        )),
+        0.3,
+    );
+    world.add(place(
+        Arc::new(blob),
+        Vec3::new(1.0, 1.0, 1.0),
+        Arc::new(Lambertian::new(&Vec3::new(0.8, 0.3, 0.3))),
+        Vec3::new(-3.6, 0.7, 0.0),
+    ));
+
+    // A rounded pillar twisted about its axis
+    let pillar = Twist::new(
+        Arc::new(SdfBox::new(&Vec3::new(0.35, 0.8, 0.35), 0.05)),
+        1.2,
+    );
+    world.add(place(
+        Arc::new(pillar),
+        Vec3::new(0.6, 0.9, 0.6),
+        Arc::new(Metal::new(&Vec3::new(0.8, 0.8, 0.9), 0.05)),
+        Vec3::new(-2.2, 0.85, 0.0),
+    ));
+
+    // A cube with a sphere scooped out of its top
+    let scooped = Subtraction::new(
+        Arc::new(SdfBox::new(&Vec3::new(0.5, 0.5, 0.5), 0.05)),
+        Arc::new(move |p: &Point3| (p - Point3::new(0.0, 0.6, 0.0)).length() - 0.55),
+        0.05,
+    );
+    world.add(place(
+        Arc::new(scooped),
+        Vec3::new(0.6, 0.6, 0.6),
+        Arc::new(Lambertian::new(&Vec3::new(0.3, 0.6, 0.8))),
+        Vec3::new(-0.8, 0.55, 0.0),
+    ));
+
+    // A tray of little rings in front
+    let rings = Repeat::new(
+        Arc::new(SdfTorus::new(0.15, 0.05)),
+        &Vec3::new(0.5, 0.0, 0.5),
+    );
+    world.add(place(
+        Arc::new(rings),
+        Vec3::new(3.75, 0.06, 0.5),
+        Arc::new(Lambertian::new(&Vec3::new(0.9, 0.7, 0.2))),
+        Vec3::new(0.0, 0.05, 2.5),
+    ));
+
+    // The CSG logo, a box and a sphere meeting with three bars drilled
+    // through, each surface keeping its operand's color
+    let color = |r: f64, g: f64, b: f64| Arc::new(Lambertian::new(&Vec3::new(r, g, b)));
+    let bar = || -> Arc<dyn Hittable + Sync + Send> {
+        Arc::new(Cylinder::new_capped(
+            0.25,
+            -1.0,
+            1.0,
+            360.0,
+            color(0.2, 0.7, 0.3),
+        ))
+    };
+    let bars = Csg::union(
+        bar(),
+        Arc::new(Csg::union(
+            Arc::new(Transform::new(bar()).rotate_x(90.0)),
+            Arc::new(Transform::new(bar()).rotate_y(90.0)),
+        )),
+    );
+    let rounded_box = Csg::intersection(
+        Arc::new(Box::new(
+            &Point3::new(-0.5, -0.5, -0.5),
+            &Point3::new(0.5, 0.5, 0.5),
+            color(0.2, 0.3, 0.8),
+        )),
+        Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            0.68,
+            color(0.8, 0.2, 0.2),
+        )),
+    );
+    world.add(Arc::new(
+        Transform::new(Arc::new(Csg::difference(
+            Arc::new(rounded_box),
+            Arc::new(bars),
+        )))
+        .rotate_y(30.0)
+        .translate(&Vec3::new(1.0, 0.5, 0.0)),
+    ));
+
+    // A bitten apple
+    world.add(Arc::new(Csg::difference(
+        Arc::new(Sphere::new(
+            Point3::new(2.5, 0.55, 0.0),
+            0.55,
+            color(0.7, 0.8, 0.2),
+        )),
+        Arc::new(Sphere::new(
+            Point3::new(2.6, 0.75, 0.55),
+            0.3,
+            color(0.9, 0.9, 0.7),
+        )),
+    )));
+
+    // A pipe stood on end
+    let pipe = Csg::difference(
+        Arc::new(Cylinder::new_capped(
+            0.4,
+            0.0,
+            1.2,
+            360.0,
+            color(0.6, 0.4, 0.3),
+        )),
+        Arc::new(Cylinder::new_capped(
+            0.28,
+            -0.1,
+            1.3,
+            360.0,
+            color(0.3, 0.2, 0.1),
+        )),
+    );
+    world.add(Arc::new(
+        Transform::new(Arc::new(pipe))
+            .rotate_x(-90.0)
+            .translate(&Vec3::new(3.8, 0.0, -0.3)),
+    ));
+
+    (world, HittableList::new())
+}
+
+// A heightfield lit low by the sun. Heights come from the brightness of an
+// image, which also colors the ground, or from Perlin turbulence without one.
+fn terrain(image: Option<&str>) -> Result<(HittableList, HittableList), LoadError> {
+    let mut world = HittableList::new();
+
+    let size = Vec3::new(10.0, 1.5, 10.0);
+    let field = match image {
+        Some(path) => {
+            let texture = Arc::new(ImageTexture::new(path.to_owned()));
+            let ground = Arc::new(Lambertian::new_with_texture(texture));
+            Heightfield::new_from_image(path, &size, ground)?
+        }
+        None => {
+            let ground = Arc::new(Lambertian::new(&Vec3::new(0.4, 0.5, 0.3)));
+            Heightfield::new_from_perlin(200, 200, 0.4, &size, ground)
+        }
+    };
+    world.add(Arc::new(field));
+
+    // Water filling the low ground and reaching the horizon
+    let water = Arc::new(Metal::new(&Vec3::new(0.3, 0.45, 0.6), 0.05));
+    world.add(Arc::new(XzRect::new(
+        -100.0,
+        100.0,
+        -100.0,
+        100.0,
+        0.2 * size.y(),
+        water,
+    )));
+
+    // A far away square facing the terrain
+    let toward = Vec3::new(-1.0, 0.6, -0.8).unit_vector();
+    let u = 20.0 * toward.cross(&Vec3::new(0.0, 1.0, 0.0)).unit_vector();
+    let v = 20.0 * u.cross(&toward).unit_vector();
+    let center = Point3::new(5.0, 0.0, 5.0) + 200.0 * toward;
+    let sun_light = Arc::new(DiffuseLight::new_with_color(Vec3::new(300.0, 280.0, 250.0)));
+    let sun: Arc<dyn Hittable + Sync + Send> =
+        Arc::new(Quad::new(&(center - 0.5 * (u + v)), &u, &v, sun_light));
+    world.add(sun.clone());
+
+    let mut lights = HittableList::new();
+    lights.add(sun);
+
+    Ok((world, lights))
+}
+
+// Participating media in the Cornell box. The cloud's densities are loaded
+// from a grid file, or are Perlin turbulence without one.
+fn media(grid: Option<&str>) -> Result<(HittableList, HittableList), LoadError> {
+    let mut world = HittableList::new();
+    let ceiling_light = cornell_room(&mut world);
+
+    let grid = match grid {
+        Some(path) => load_grid(path)?,
+        None => {
+            // Turbulence fading out towards the sides of the box, into a puff
+            let n = 64;
+            let mut grid = DensityGrid::new_from_perlin(n, n, n, 4.0);
+            for k in 0..n {
+                for j in 0..n {
+                    for i in 0..n {
+                        let p =
+                            Point3::new(i as f64, j as f64, k as f64) + Vec3::new(0.5, 0.5, 0.5);
+                        let r = (p / n as f64 - Vec3::new(0.5, 0.5, 0.5)).length();
+                        grid.values[(k * n + j) * n + i] *= (1.0 - 2.0 * r).max(0.0);
+                    }
+                }
+            }
+            grid
+        }
+    };
+    let mut cloud = GridMedium::new(
+        Aabb::new(
+            Point3::new(128.0, 280.0, 228.0),
+            Point3::new(428.0, 520.0, 528.0),
+        ),
+        grid,
+        0.4,
+        Vec3::new(0.9, 0.9, 0.9),
+    );
+    // Mostly forward scattering, with a little glow back towards the light
+    cloud.phase_function = Arc::new(PhaseMaterial::new_with_color(
+        Arc::new(DoubleHenyeyGreenstein::new(0.8, -0.3, 0.8)),
+        Vec3::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(cloud));
+
+    // Smoke banded by marble noise
+    let white = Arc::new(Lambertian::new(&Vec3::new(0.73, 0.73, 0.73)));
+    let boundary = Arc::new(Sphere::new(
+        Point3::new(160.0, 100.0, 200.0),
+        100.0,
+        white.clone(),
+    ));
+    world.add(Arc::new(HeterogeneousMedium::new(
+        boundary,
+        0.1,
+        Arc::new(NoiseTexture::new(0.05)),
+        Vec3::new(0.8, 0.6, 0.3),
+    )));
+
+    // Fog scattering light on forward, and air scattering as much back as
+    // forward but little to the side
+    let fogs: [(Point3, Arc<dyn PhaseFunction + Sync + Send>, Vec3); 2] = [
+        (
+            Point3::new(400.0, 70.0, 130.0),
+            Arc::new(HenyeyGreenstein::new(0.7)),
+            Vec3::new(0.9, 0.9, 0.9),
+        ),
+        (
+            Point3::new(420.0, 70.0, 330.0),
+            Arc::new(Rayleigh),
+            Vec3::new(0.5, 0.7, 0.95),
+        ),
+    ];
+    for (center, phase, color) in fogs {
+        let boundary = Arc::new(Sphere::new(center, 70.0, white.clone()));
+        world.add(Arc::new(ConstantMedium::new_with_phase(
+            boundary,
+            0.05,
+            phase,
+            Arc::new(SolidColor::new(&color)),
+        )));
+    }
+
+    let mut lights = HittableList::new();
+    lights.add(ceiling_light);
+
+    Ok((world, lights))
+}
+
+// Liquids in the Cornell box: milk, which scatters far more than it absorbs,
+// and wine, which only absorbs, held in place by invisible boundaries, a tank
+// of water with a glass ball sunk halfway into it, and green glass
+fn liquids() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+    let ceiling_light = cornell_room(&mut world);
+
+    // Milk scatters blue a little more than red, mostly forward
+    let milk = Arc::new(HomogeneousMedium::new_with_phase(
+        &Vec3::new(0.0005, 0.001, 0.003),
+        &Vec3::new(0.25, 0.27, 0.3),
+        Arc::new(HenyeyGreenstein::new(0.6)),
+    ));
+    world.add(Arc::new(Box::new(
+        &Point3::new(330.0, 0.0, 330.0),
+        &Point3::new(480.0, 220.0, 400.0),
+        Arc::new(MediumInterface::new(milk)),
+    )));
+
+    let wine = Arc::new(HomogeneousMedium::new(
+        &Vec3::new(0.01, 0.06, 0.05),
+        &Vec3::default(),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(400.0, 70.0, 150.0),
+        70.0,
+        Arc::new(MediumInterface::new(wine)),
+    )));
+
+    // The ball outranks the water, so the water's surface is not seen inside
+    // it and light passing from one to the other bends by 1.5 / 1.33
+    world.add(Arc::new(Box::new(
+        &Point3::new(60.0, 0.0, 100.0),
+        &Point3::new(260.0, 120.0, 300.0),
+        Arc::new(Dielectric::new_with_priority(1.33, 1)),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(160.0, 120.0, 200.0),
+        70.0,
+        Arc::new(Dielectric::new_with_priority(1.5, 2)),
+    )));
+
+    // The same glass, deeper in the large ball than in the small one
+    let green = Vec3::new(0.5, 0.85, 0.6);
+    world.add(Arc::new(Sphere::new(
+        Point3::new(278.0, 90.0, 460.0),
+        90.0,
+        Arc::new(Dielectric::new_with_absorption(1.5, &green, 50.0)),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(290.0, 25.0, 60.0),
+        25.0,
+        Arc::new(Dielectric::new_with_absorption(1.5, &green, 50.0)),
+    )));
+
+    let mut lights = HittableList::new();
+    lights.add(ceiling_light);
+
+    (world, lights)
+}
+
+// Rows of spheres on a checkered floor under the sky: the measured metals at
+// the back, rougher from left to right, frosted glass in front of them
+// between clear coated and varnished paint, and the principled material's
+// lobes at the front
+fn materials() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::new_with_color(
+        &Vec3::new(0.2, 0.3, 0.1),
+        &Vec3::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(XzRect::new(
+        -50.0,
+        50.0,
+        -50.0,
+        50.0,
+        0.0,
+        Arc::new(Lambertian::new_with_texture(checker)),
+    )));
+
+    let ball = |x: f64, z: f64, material: Arc<dyn Material + Sync + Send>| {
+        Arc::new(Sphere::new(Point3::new(x, 0.4, z), 0.4, material))
+    };
+
+    world.add(ball(-2.0, -1.5, Arc::new(Conductor::gold(0.05))));
+    world.add(ball(-1.0, -1.5, Arc::new(Conductor::silver(0.1))));
+    world.add(ball(0.0, -1.5, Arc::new(Conductor::copper(0.2))));
+    world.add(ball(1.0, -1.5, Arc::new(Conductor::aluminium(0.3))));
+    world.add(ball(2.0, -1.5, Arc::new(Conductor::iron(0.5))));
+
+    let ink = Arc::new(HomogeneousMedium::new(
+        &Vec3::new(1.5, 0.8, 0.2),
+        &Vec3::default(),
+    ));
+    world.add(ball(-1.5, -0.3, Arc::new(RoughDielectric::new(1.5, 0.05))));
+    world.add(ball(-0.5, -0.3, Arc::new(RoughDielectric::new(1.5, 0.3))));
+    world.add(ball(
+        0.5,
+        -0.3,
+        Arc::new(RoughDielectric::new_with_ior(Ior::SF11, 0.15)),
+    ));
+    world.add(ball(
+        1.5,
+        -0.3,
+        Arc::new(RoughDielectric::new_with_medium(1.5, 0.15, ink)),
+    ));
+
+    let paint = Arc::new(Lambertian::new(&Vec3::new(0.7, 0.05, 0.05)));
+    world.add(ball(-2.5, -0.3, Arc::new(Coated::new(paint, 1.5))));
+    let wood = Arc::new(Lambertian::new(&Vec3::new(0.6, 0.4, 0.25)));
+    world.add(ball(
+        2.5,
+        -0.3,
+        Arc::new(Coated::new_with_absorption(
+            wood,
+            1.5,
+            0.5,
+            &Vec3::new(0.2, 1.0, 3.0),
+        )),
+    ));
+
+    let constant = |value: f64| Arc::new(SolidColor::new_with_values(value, value, value));
+    let plastic = Principled::new(&Vec3::new(0.8, 0.2, 0.2));
+    let mut metal = Principled::new(&Vec3::new(0.9, 0.6, 0.3));
+    metal.metallic = constant(1.0);
+    metal.roughness = constant(0.3);
+    let mut brushed = Principled::new(&Vec3::new(0.8, 0.8, 0.8));
+    brushed.metallic = constant(1.0);
+    brushed.anisotropy = constant(0.8);
+    let mut varnished = Principled::new(&Vec3::new(0.2, 0.3, 0.7));
+    varnished.clearcoat = constant(1.0);
+    let mut velvet = Principled::new(&Vec3::new(0.5, 0.1, 0.4));
+    velvet.roughness = constant(1.0);
+    velvet.sheen = constant(1.0);
+    let mut glass = Principled::new(&Vec3::new(0.9, 1.0, 0.9));
+    glass.roughness = constant(0.1);
+    glass.transmission = constant(1.0);
+    for (i, material) in [plastic, metal, brushed, varnished, velvet, glass]
+        .into_iter()
+        .enumerate()
+    {
+        world.add(ball(-2.25 + 0.9 * i as f64, 0.9, Arc::new(material)));
+    }
+
+    (world, HittableList::new())
 }
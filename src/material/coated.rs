@@ -0,0 +1,251 @@
+use std::sync::Arc;
+
+use crate::{
+    media::stack::Interior,
+    model::{hit::HitRecord, ray::Ray, vec3::Vec3},
+    spectrum::ior::Ior,
+    util::rtweekend::random_double,
+};
+
+use super::{
+    material::Material,
+    microfacet::{fresnel_dielectric, reflect, refract},
+};
+
+// How many times light may bounce between the coat and the base before it is
+// given up on
+const MAX_BOUNCES: usize = 16;
+
+// A base material under a smooth, thin dielectric coat, like car paint or
+// varnished wood. Light entering the coat is followed on a random walk:
+// through the coat to the base, scattered by it, and back up to the top,
+// where Fresnel decides whether it leaves or is reflected down again. Each
+// crossing of the coat absorbs by Beer-Lambert's law over its slanted path.
+// The coat has no extent, so the whole walk happens at the hit point.
+pub struct Coated {
+    pub base: Arc<dyn Material + Sync + Send>,
+    pub ior: Ior,
+    pub thickness: f64,
+    // The absorption coefficient per unit of length inside the coat
+    pub absorption: Vec3,
+}
+
+impl Coated {
+    pub fn new(base: Arc<dyn Material + Sync + Send>, index_of_refraction: f64) -> Self {
+        Coated::new_with_absorption(base, index_of_refraction, 0.0, &Vec3::default())
+    }
+
+    pub fn new_with_absorption(
+        base: Arc<dyn Material + Sync + Send>,
+        index_of_refraction: f64,
+        thickness: f64,
+        absorption: &Vec3,
+    ) -> Self {
+        Self {
+            base,
+            ior: Ior::Constant(index_of_refraction),
+            thickness,
+            absorption: *absorption,
+        }
+    }
+
+    // What is left of light crossing the coat at cos_theta to the normal
+    fn transmittance(&self, cos_theta: f64) -> Vec3 {
+        let distance = self.thickness / cos_theta.abs().max(1e-6);
+        let mut transmittance = Vec3::default();
+        for c in 0..3 {
+            transmittance[c] = (-self.absorption[c] * distance).exp();
+        }
+
+        transmittance
+    }
+}
+
+impl Material for Coated {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Vec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        let n = rec.normal;
+        let wo = -r_in.dir().unit_vector();
+        let eta = self.ior.at(r_in.wavelength()) / r_in.media().ior(r_in.wavelength());
+
+        if random_double() < fresnel_dielectric(wo.dot(&n), eta) {
+            *attenuation = Vec3::new(1.0, 1.0, 1.0);
+            *scattered = Ray::new(&rec.p, &reflect(&wo, &n), r_in.time());
+            return true;
+        }
+
+        // The base sees the coat as what it sits in
+        let mut media = r_in.media().clone();
+        media.push(&Interior {
+            id: self as *const Self as usize,
+            priority: 0,
+            ior: Some(self.ior),
+            medium: None,
+        });
+
+        let mut weight = Vec3::new(1.0, 1.0, 1.0);
+        let mut down = match refract(&wo, &n, eta) {
+            Some(down) => down,
+            None => return false,
+        };
+        for _ in 0..MAX_BOUNCES {
+            weight *= self.transmittance(down.dot(&n));
+
+            let into_base = Ray::new_in_media(&rec.p, &down, r_in.time(), media.clone())
+                .with_wavelength(r_in.wavelength());
+            let mut base_attenuation = Vec3::default();
+            let mut from_base = Ray::new(&rec.p, &n, r_in.time());
+            if !self
+                .base
+                .scatter(&into_base, rec, &mut base_attenuation, &mut from_base)
+            {
+                return false;
+            }
+
+            // Light the base lets through is lost below the coat
+            let up = from_base.dir().unit_vector();
+            if up.dot(&n) <= 0.0 {
+                return false;
+            }
+            weight *= base_attenuation * self.transmittance(up.dot(&n));
+
+            // Leave through the top, or be reflected back down to the base.
+            // Seen from inside the coat, the top faces down.
+            if random_double() >= fresnel_dielectric(up.dot(&n), 1.0 / eta) {
+                if let Some(out) = refract(&-up, &-n, 1.0 / eta) {
+                    *attenuation = weight;
+                    *scattered = Ray::new(&rec.p, &out, r_in.time());
+                    return true;
+                }
+            }
+            down = reflect(&-up, &-n);
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::{lambertian::Lambertian, metal::Metal};
+
+    use super::*;
+
+    fn hit_from(dir: &Vec3) -> (Ray, HitRecord) {
+        let r = Ray::new(&(-dir), dir, 0.0);
+        let mut rec = HitRecord::default();
+        rec.set_face_normal(&r, &Vec3::new(0.0, 0.0, 1.0));
+        (r, rec)
+    }
+
+    // The mean weight of scattering r, counting failures as zero
+    fn albedo(material: &Coated, r: &Ray, rec: &HitRecord, n: usize) -> f64 {
+        let mut sum = 0.0;
+        for _ in 0..n {
+            let mut attenuation = Vec3::default();
+            let mut scattered = Ray::new(&Vec3::default(), &Vec3::default(), 0.0);
+            if material.scatter(r, rec, &mut attenuation, &mut scattered) {
+                assert!(scattered.dir().z() > 0.0);
+                sum += attenuation.x();
+            }
+        }
+        sum / n as f64
+    }
+
+    // The integral of f(cos theta) over the cosine weighted hemisphere
+    fn cosine_mean(f: impl Fn(f64) -> f64) -> f64 {
+        let n = 100_000;
+        (0..n)
+            .map(|i| {
+                let cos = (i as f64 + 0.5) / n as f64;
+                2.0 * cos * f(cos) / n as f64
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_diffuse_base() {
+        // Light reflected by the coat, plus light that gets in and reaches
+        // the base again and again until it leaves, seeing the top from
+        // inside with its cosine weighted mean reflectance
+        let a = 0.8;
+        let coated = Coated::new(Arc::new(Lambertian::new(&Vec3::new(a, a, a))), 1.5);
+        let (r, rec) = hit_from(&Vec3::new(0.6, 0.0, -0.8));
+
+        let f = fresnel_dielectric(0.8, 1.5);
+        let f_inside = cosine_mean(|cos| fresnel_dielectric(cos, 1.0 / 1.5));
+        let expected = f + (1.0 - f) * a * (1.0 - f_inside) / (1.0 - a * f_inside);
+
+        let estimate = albedo(&coated, &r, &rec, 200_000);
+        assert!(
+            (estimate - expected).abs() < 0.01,
+            "{} != {}",
+            estimate,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_absorption() {
+        // Without a change of index light crosses the coat once each way
+        let (a, sigma, thickness) = (0.8, 2.0, 0.25);
+        let coated = Coated::new_with_absorption(
+            Arc::new(Lambertian::new(&Vec3::new(a, a, a))),
+            1.0,
+            thickness,
+            &Vec3::new(sigma, sigma, sigma),
+        );
+        let (r, rec) = hit_from(&Vec3::new(0.6, 0.0, -0.8));
+
+        let down = (-sigma * thickness / 0.8).exp();
+        let up = cosine_mean(|cos| (-sigma * thickness / cos).exp());
+        let expected = down * a * up;
+
+        let estimate = albedo(&coated, &r, &rec, 200_000);
+        assert!(
+            (estimate - expected).abs() < 0.01,
+            "{} != {}",
+            estimate,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_mirror_base() {
+        // Every pass through the coat follows the same path, so the walk
+        // sums a geometric series
+        let (a, sigma, thickness) = (0.9, 1.0, 0.1);
+        let coated = Coated::new_with_absorption(
+            Arc::new(Metal::new(&Vec3::new(a, a, a), 0.0)),
+            1.5,
+            thickness,
+            &Vec3::new(sigma, sigma, sigma),
+        );
+        let dir = Vec3::new(0.6, 0.0, -0.8);
+        let (r, rec) = hit_from(&dir);
+
+        let f = fresnel_dielectric(0.8, 1.5);
+        let cos_t = (1.0 - 0.36 / 2.25f64).sqrt();
+        let round_trip = a * (-2.0 * sigma * thickness / cos_t).exp();
+        let expected = f + (1.0 - f) * (1.0 - f) * round_trip / (1.0 - f * round_trip);
+
+        let estimate = albedo(&coated, &r, &rec, 200_000);
+        assert!(
+            (estimate - expected).abs() < 0.01,
+            "{} != {}",
+            estimate,
+            expected
+        );
+
+        // And always comes out in the mirror direction
+        let mut attenuation = Vec3::default();
+        let mut scattered = Ray::new(&Vec3::default(), &Vec3::default(), 0.0);
+        while !coated.scatter(&r, &rec, &mut attenuation, &mut scattered) {}
+        assert!((scattered.dir().unit_vector() - Vec3::new(0.6, 0.0, 0.8)).length() < 1e-9);
+    }
+}
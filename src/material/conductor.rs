@@ -0,0 +1,182 @@
+use crate::model::{hit::HitRecord, onb::Onb, ray::Ray, vec3::Vec3};
+
+use super::{
+    material::Material,
+    microfacet::{fresnel_conductor, reflect, Ggx},
+};
+
+// A metal with GGX microfacet roughness, its color coming from the Fresnel
+// reflectance of its complex index of refraction eta + ik, per channel.
+// Light that would bounce between microfacets more than once is lost, so very
+// rough metals come out slightly dark.
+pub struct Conductor {
+    pub eta: Vec3,
+    pub k: Vec3,
+    pub roughness: f64,
+}
+
+impl Conductor {
+    pub fn new(eta: &Vec3, k: &Vec3, roughness: f64) -> Self {
+        Self {
+            eta: *eta,
+            k: *k,
+            roughness,
+        }
+    }
+
+    pub fn gold(roughness: f64) -> Self {
+        Conductor::new(
+            &Vec3::new(0.143, 0.374, 1.442),
+            &Vec3::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Conductor::new(
+            &Vec3::new(0.155, 0.117, 0.138),
+            &Vec3::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Conductor::new(
+            &Vec3::new(0.200, 0.924, 1.102),
+            &Vec3::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Conductor::new(
+            &Vec3::new(1.657, 0.880, 0.521),
+            &Vec3::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn iron(roughness: f64) -> Self {
+        Conductor::new(
+            &Vec3::new(2.911, 2.950, 2.585),
+            &Vec3::new(3.089, 2.932, 2.767),
+            roughness,
+        )
+    }
+
+    // The index is relative to whatever the metal sits in
+    fn fresnel(&self, r_in: &Ray, cos_i: f64) -> Vec3 {
+        let outside = r_in.media().ior(r_in.wavelength());
+        fresnel_conductor(cos_i, &(self.eta / outside), &(self.k / outside))
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Vec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        let frame = Onb::new_from_w(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir().unit_vector());
+        let ggx = Ggx::new_from_roughness(self.roughness, 0.0);
+
+        // Sampling visible normals leaves only the Fresnel term and the
+        // shadowing of the outgoing direction in the weight
+        let m = ggx.sample_visible(&wo);
+        let wi = reflect(&wo, &m);
+        if wi.z() <= 0.0 {
+            return false;
+        }
+
+        *attenuation = self.fresnel(r_in, wo.dot(&m)) * (ggx.g(&wo, &wi) / ggx.g1(&wo));
+        *scattered = Ray::new(&rec.p, &frame.local(&wi), r_in.time());
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Vec3 {
+        let frame = Onb::new_from_w(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir().unit_vector());
+        let wi = frame.to_local(&dir.unit_vector());
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+
+        let ggx = Ggx::new_from_roughness(self.roughness, 0.0);
+        let m = (wo + wi).unit_vector();
+        self.fresnel(r_in, wo.dot(&m)) * (ggx.d(&m) * ggx.g(&wo, &wi) / (4.0 * wo.z()))
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> f64 {
+        let frame = Onb::new_from_w(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir().unit_vector());
+        let wi = frame.to_local(&dir.unit_vector());
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let ggx = Ggx::new_from_roughness(self.roughness, 0.0);
+        let m = (wo + wi).unit_vector();
+        ggx.pdf_visible(&wo, &m) / (4.0 * wo.dot(&m))
+    }
+
+    fn is_specular(&self, _r_in: &Ray, _rec: &HitRecord) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::microfacet::fresnel_conductor;
+
+    use super::*;
+
+    fn hit_from(dir: &Vec3) -> (Ray, HitRecord) {
+        let r = Ray::new(&(-dir), dir, 0.0);
+        let mut rec = HitRecord::default();
+        rec.set_face_normal(&r, &Vec3::new(0.0, 0.0, 1.0));
+        (r, rec)
+    }
+
+    #[test]
+    fn test_scatter_matches_eval_and_pdf() {
+        let metals = [Conductor::gold(0.4), Conductor::iron(0.8)];
+        for metal in metals {
+            let (r, rec) = hit_from(&Vec3::new(0.6, 0.2, -0.7).unit_vector());
+            let mut scattered_any = false;
+            for _ in 0..1000 {
+                let mut attenuation = Vec3::default();
+                let mut scattered = Ray::new(&Vec3::default(), &Vec3::default(), 0.0);
+                if !metal.scatter(&r, &rec, &mut attenuation, &mut scattered) {
+                    continue;
+                }
+                scattered_any = true;
+
+                let expected =
+                    metal.eval(&r, &rec, scattered.dir()) / metal.pdf(&r, &rec, scattered.dir());
+                assert!(
+                    (attenuation - expected).length() < 1e-9 * expected.length(),
+                    "{} != {}",
+                    attenuation,
+                    expected
+                );
+            }
+            assert!(scattered_any);
+        }
+    }
+
+    #[test]
+    fn test_smooth_metal_is_a_mirror() {
+        let metal = Conductor::copper(0.0);
+        let (r, rec) = hit_from(&Vec3::new(0.0, 0.0, -1.0));
+        let mut attenuation = Vec3::default();
+        let mut scattered = Ray::new(&Vec3::default(), &Vec3::default(), 0.0);
+        assert!(metal.scatter(&r, &rec, &mut attenuation, &mut scattered));
+
+        let normal = fresnel_conductor(1.0, &metal.eta, &metal.k);
+        assert!((attenuation - normal).length() < 1e-3);
+        assert!(scattered.dir().z() > 0.999);
+    }
+}
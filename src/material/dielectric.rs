@@ -1,21 +1,75 @@
+use std::sync::Arc;
+
 use crate::{
+    media::{
+        homogeneous::HomogeneousMedium,
+        medium::Medium,
+        stack::{Interior, MediumStack},
+    },
     model::{hit::HitRecord, ray::Ray, vec3::Vec3},
+    spectrum::ior::Ior,
     util::rtweekend::random_double,
 };
 
 use super::material::Material;
 
+// A refracting surface. The index of refraction on each side comes from the
+// objects the ray is inside of, so nested and touching dielectrics bend light
+// by their relative index, and an index that varies with wavelength splits
+// white light into colors when rendering spectrally. Where dielectrics overlap,
+// the one with the higher priority fills the overlap and the surfaces of the
+// other are ignored there.
 pub struct Dielectric {
-    pub ir: f64,
+    pub ior: Ior,
+    pub priority: i32,
+    // What the inside is filled with, None for clear
+    pub medium: Option<Arc<dyn Medium + Sync + Send>>,
 }
 
 impl Dielectric {
     pub fn new(index_of_refraction: f64) -> Self {
+        Dielectric::new_with_ior(Ior::Constant(index_of_refraction))
+    }
+
+    pub fn new_with_ior(ior: Ior) -> Self {
+        Self {
+            ior,
+            priority: 0,
+            medium: None,
+        }
+    }
+
+    pub fn new_with_priority(index_of_refraction: f64, priority: i32) -> Self {
         Self {
-            ir: index_of_refraction,
+            ior: Ior::Constant(index_of_refraction),
+            priority,
+            medium: None,
         }
     }
 
+    pub fn new_with_medium(
+        index_of_refraction: f64,
+        medium: Arc<dyn Medium + Sync + Send>,
+    ) -> Self {
+        Self {
+            ior: Ior::Constant(index_of_refraction),
+            priority: 0,
+            medium: Some(medium),
+        }
+    }
+
+    // Tinted glass, through which light keeps `color` of its intensity for
+    // every `distance` it travels inside, following Beer-Lambert's law.
+    pub fn new_with_absorption(index_of_refraction: f64, color: &Vec3, distance: f64) -> Self {
+        let mut sigma_a = Vec3::default();
+        for c in 0..3 {
+            sigma_a[c] = -color[c].clamp(1e-6, 1.0).ln() / distance;
+        }
+        let medium = HomogeneousMedium::new(&sigma_a, &Vec3::default());
+
+        Dielectric::new_with_medium(index_of_refraction, Arc::new(medium))
+    }
+
     pub fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
         let mut r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
         r0 = r0 * r0;
@@ -33,26 +87,41 @@ impl Material for Dielectric {
         scattered: &mut Ray,
     ) -> bool {
         *attenuation = Vec3::new(1.0, 1.0, 1.0);
-        let refraction_ratio = if rec.front_face {
-            1.0 / self.ir
-        } else {
-            self.ir
-        };
-
         let unit_direction = r_in.dir().unit_vector();
+
+        let interior = self.interior().unwrap();
+        let (near, far) = r_in.media().crossing(&interior, rec.front_face);
+        // Schlick's approximation still reflects at grazing angles when the
+        // indices match, so only a change of index counts as a surface
+        let (n_near, n_far) = (near.ior(r_in.wavelength()), far.ior(r_in.wavelength()));
+        if !MediumStack::is_interface(&near, &far) || n_near == n_far {
+            *scattered = Ray::new(&rec.p, &unit_direction, r_in.time());
+            return true;
+        }
+
+        let refraction_ratio = n_near / n_far;
         let cos_theta = (-unit_direction).dot(&rec.normal).min(1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let direction: Vec3;
-        if cannot_refract || Dielectric::reflectance(cos_theta, refraction_ratio) > random_double()
+        let direction = if cannot_refract
+            || Dielectric::reflectance(cos_theta, refraction_ratio) > random_double()
         {
-            direction = unit_direction.reflect(&rec.normal);
+            unit_direction.reflect(&rec.normal)
         } else {
-            direction = unit_direction.refract(&rec.normal, refraction_ratio);
-        }
+            unit_direction.refract(&rec.normal, refraction_ratio)
+        };
 
         *scattered = Ray::new(&rec.p, &direction, r_in.time());
-        return true;
+        true
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        Some(Interior {
+            id: self as *const Self as usize,
+            priority: self.priority,
+            ior: Some(self.ior),
+            medium: self.medium.clone(),
+        })
     }
 }
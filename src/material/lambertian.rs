@@ -3,6 +3,7 @@ use std::sync::Arc;
 use crate::{
     model::{hit::HitRecord, ray::Ray, vec3::Vec3},
     texture::{solid_color::SolidColor, texture::Texture},
+    util::rtweekend::PI,
 };
 
 use super::material::Material;
@@ -39,7 +40,20 @@ impl Material for Lambertian {
         }
 
         *scattered = Ray::new(&rec.p, &scatter_direction, r_in.time());
-        *attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
+        *attenuation = self.albedo.value_at(rec);
         return true;
     }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Vec3 {
+        self.albedo.value_at(rec) * self.pdf(r_in, rec, dir)
+    }
+
+    // Scattering around the normal picks cosine weighted directions
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> f64 {
+        (rec.normal.dot(&dir.unit_vector()) / PI).max(0.0)
+    }
+
+    fn is_specular(&self, _r_in: &Ray, _rec: &HitRecord) -> bool {
+        false
+    }
 }
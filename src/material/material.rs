@@ -1,4 +1,7 @@
-use crate::model::{hit::HitRecord, ray::Ray, vec3::Vec3};
+use crate::{
+    media::stack::Interior,
+    model::{hit::HitRecord, ray::Ray, vec3::Vec3},
+};
 
 use Vec3 as Point3;
 
@@ -14,4 +17,34 @@ pub trait Material {
     fn emitted(&self, u: f64, v: f64, p: &Point3) -> Vec3 {
         Vec3::new(0.0, 0.0, 0.0)
     }
+
+    // The BSDF times the cosine to the normal for light arriving from dir
+    // and leaving back along r_in, and the density with which scatter picks
+    // dir. Specular materials cannot be evaluated and leave both at zero.
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _dir: &Vec3) -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _dir: &Vec3) -> f64 {
+        0.0
+    }
+
+    // Whether scatter may pick a direction that eval and pdf cannot see at
+    // this hit. Light sampling is only mixed in where it cannot, and this is
+    // asked before sampling so the choice does not depend on the direction.
+    fn is_specular(&self, _r_in: &Ray, _rec: &HitRecord) -> bool {
+        true
+    }
+
+    // Whether rays go straight through the surface unchanged, so that shadow
+    // rays can see past it.
+    fn is_pass_through(&self) -> bool {
+        false
+    }
+
+    // What fills the inside of surfaces made of this material. Rays that
+    // cross into them carry it until they cross back out.
+    fn interior(&self) -> Option<Interior> {
+        None
+    }
 }
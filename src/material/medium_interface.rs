@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use crate::{
+    media::{medium::Medium, stack::Interior},
+    model::{hit::HitRecord, ray::Ray, vec3::Vec3},
+};
+
+use super::material::Material;
+
+// An invisible surface that only marks the boundary of a medium. Rays pass
+// straight through it, into the medium when they hit the front face and back
+// out when they hit the back.
+pub struct MediumInterface {
+    pub medium: Arc<dyn Medium + Sync + Send>,
+}
+
+impl MediumInterface {
+    pub fn new(medium: Arc<dyn Medium + Sync + Send>) -> Self {
+        Self { medium }
+    }
+}
+
+impl Material for MediumInterface {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Vec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        *scattered = Ray::new(&rec.p, r_in.dir(), r_in.time());
+        *attenuation = Vec3::new(1.0, 1.0, 1.0);
+
+        true
+    }
+
+    fn is_pass_through(&self) -> bool {
+        true
+    }
+
+    // The boundary does not bend light, so the inside keeps the index of
+    // refraction around it
+    fn interior(&self) -> Option<Interior> {
+        Some(Interior {
+            id: self as *const Self as usize,
+            priority: 0,
+            ior: None,
+            medium: Some(self.medium.clone()),
+        })
+    }
+}
@@ -0,0 +1,429 @@
+use crate::{
+    model::vec3::Vec3,
+    util::rtweekend::{random_double, PI},
+};
+
+// Shared pieces of the microfacet materials. Directions are in a local frame
+// with the surface normal along z, and point away from the surface.
+
+// The anisotropic GGX (Trowbridge-Reitz) distribution of microfacet normals,
+// with roughness alpha_x along x and alpha_y along y.
+#[derive(Debug, Copy, Clone)]
+pub struct Ggx {
+    pub alpha_x: f64,
+    pub alpha_y: f64,
+}
+
+impl Ggx {
+    pub fn new(alpha_x: f64, alpha_y: f64) -> Self {
+        // Perfectly smooth surfaces are left to the specular materials
+        Self {
+            alpha_x: alpha_x.max(1e-3),
+            alpha_y: alpha_y.max(1e-3),
+        }
+    }
+
+    // The perceptual roughness of the principled model, squared into alpha,
+    // and stretched along x by anisotropy in [0, 1].
+    pub fn new_from_roughness(roughness: f64, anisotropic: f64) -> Self {
+        let alpha = roughness * roughness;
+        let aspect = (1.0 - 0.9 * anisotropic.clamp(0.0, 1.0)).sqrt();
+
+        Ggx::new(alpha / aspect, alpha * aspect)
+    }
+
+    pub fn d(&self, m: &Vec3) -> f64 {
+        if m.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let (x, y) = (m.x() / self.alpha_x, m.y() / self.alpha_y);
+        let e = x * x + y * y + m.z() * m.z();
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    fn lambda(&self, w: &Vec3) -> f64 {
+        let (x, y) = (w.x() * self.alpha_x, w.y() * self.alpha_y);
+        let tan2_theta = (x * x + y * y) / (w.z() * w.z());
+
+        0.5 * (-1.0 + (1.0 + tan2_theta).sqrt())
+    }
+
+    // Smith masking of one direction
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    // Height-correlated Smith masking-shadowing of a pair of directions
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // A microfacet normal as seen from wo, drawn in proportion to how much of
+    // it is visible (Heitz 2018). wo must be above the surface.
+    pub fn sample_visible(&self, wo: &Vec3) -> Vec3 {
+        // Stretch to the unit roughness configuration
+        let vh = Vec3::new(self.alpha_x * wo.x(), self.alpha_y * wo.y(), wo.z()).unit_vector();
+
+        let len_sq = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if len_sq > 0.0 {
+            Vec3::new(-vh.y(), vh.x(), 0.0) / len_sq.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(&t1);
+
+        // A point on the projected hemisphere, squashed towards vh
+        let r = random_double().sqrt();
+        let phi = 2.0 * PI * random_double();
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+
+        Vec3::new(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        )
+        .unit_vector()
+    }
+
+    // The density of sample_visible choosing m
+    pub fn pdf_visible(&self, wo: &Vec3, m: &Vec3) -> f64 {
+        if wo.z() <= 0.0 {
+            return 0.0;
+        }
+
+        self.g1(wo) * wo.dot(m).max(0.0) * self.d(m) / wo.z()
+    }
+}
+
+pub fn reflect(w: &Vec3, m: &Vec3) -> Vec3 {
+    -w + 2.0 * w.dot(m) * m
+}
+
+// Refracts w through a facet with normal m on the same side, where eta is
+// the index on the far side over the index on w's side. None on total
+// internal reflection.
+pub fn refract(w: &Vec3, m: &Vec3, eta: f64) -> Option<Vec3> {
+    let cos_i = w.dot(m);
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-w / eta + (cos_i / eta - cos_t) * m)
+}
+
+// The exact unpolarized Fresnel reflectance of a dielectric boundary, eta
+// being the index on the far side over the index on the incident side.
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let (cos_i, eta) = if cos_i < 0.0 {
+        (-cos_i, 1.0 / eta)
+    } else {
+        (cos_i, eta)
+    };
+    let cos_i = cos_i.min(1.0);
+
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+
+    0.5 * (r_parallel * r_parallel + r_perpendicular * r_perpendicular)
+}
+
+// The Fresnel reflectance of a conductor with complex index eta + ik, per
+// channel.
+pub fn fresnel_conductor(cos_i: f64, eta: &Vec3, k: &Vec3) -> Vec3 {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    let cos2 = cos_i * cos_i;
+    let sin2 = 1.0 - cos2;
+
+    let mut reflectance = Vec3::default();
+    for c in 0..3 {
+        let (eta2, k2) = (eta[c] * eta[c], k[c] * k[c]);
+        let t0 = eta2 - k2 - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+
+        let t1 = a2_plus_b2 + cos2;
+        let t2 = 2.0 * cos_i * a;
+        let r_s = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let r_p = r_s * (t3 - t4) / (t3 + t4);
+
+        reflectance[c] = 0.5 * (r_s + r_p);
+    }
+
+    reflectance
+}
+
+// A rough dielectric boundary (Walter et al. 2007): the BSDF times |cos
+// theta_i| for light arriving from wi and leaving towards wo above the
+// surface, and the density with which sample_rough_dielectric picks wi. eta
+// is the index below over the index above. As with the smooth Dielectric,
+// radiance is not rescaled by the squared index ratio, which cancels for
+// closed objects anyway.
+pub fn evaluate_rough_dielectric(ggx: &Ggx, wo: &Vec3, wi: &Vec3, eta: f64) -> (f64, f64) {
+    if wo.z() <= 0.0 || wi.z() == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    if wi.z() > 0.0 {
+        let m = (wo + wi).unit_vector();
+        let fresnel = fresnel_dielectric(wo.dot(&m), eta);
+        let value = fresnel * ggx.d(&m) * ggx.g(wo, wi) / (4.0 * wo.z());
+
+        // The half vector changes 4 |wo.m| times slower than wi
+        let pdf = ggx.pdf_visible(wo, &m) * fresnel / (4.0 * wo.dot(&m));
+        return (value, pdf);
+    }
+
+    // The generalized half vector of a refraction, facing up. It vanishes
+    // for going straight through between equal indices.
+    let m = wo + eta * wi;
+    if m.near_zero() {
+        return (0.0, 0.0);
+    }
+    let mut m = m.unit_vector();
+    if m.z() < 0.0 {
+        m = -m;
+    }
+    let (cos_o, cos_i) = (wo.dot(&m), wi.dot(&m));
+    if cos_o <= 0.0 || cos_i >= 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let fresnel = fresnel_dielectric(cos_o, eta);
+    let denom = (cos_o + eta * cos_i) * (cos_o + eta * cos_i);
+    let jacobian = eta * eta * cos_i.abs() / denom;
+    let value = (1.0 - fresnel) * ggx.d(&m) * ggx.g(wo, wi) * cos_o * jacobian / wo.z();
+    let pdf = ggx.pdf_visible(wo, &m) * (1.0 - fresnel) * jacobian;
+
+    (value, pdf)
+}
+
+// Picks a visible microfacet, then reflects or refracts wo through it in
+// proportion to its Fresnel reflectance. Directions that end up on the wrong
+// side of the surface are lost, since the density on that side only counts
+// the other kind.
+pub fn sample_rough_dielectric(ggx: &Ggx, wo: &Vec3, eta: f64) -> Option<Vec3> {
+    let m = ggx.sample_visible(wo);
+    if random_double() < fresnel_dielectric(wo.dot(&m), eta) {
+        Some(reflect(wo, &m)).filter(|wi| wi.z() > 0.0)
+    } else {
+        refract(wo, &m, eta).filter(|wi| wi.z() < 0.0)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // The integral of f over the upper hemisphere, by the midpoint rule in
+    // spherical coordinates
+    fn integrate(f: impl Fn(&Vec3) -> f64) -> f64 {
+        let (n_theta, n_phi) = (1000, 400);
+        let (d_theta, d_phi) = (0.5 * PI / n_theta as f64, 2.0 * PI / n_phi as f64);
+
+        let mut sum = 0.0;
+        for i in 0..n_theta {
+            let theta = (i as f64 + 0.5) * d_theta;
+            for j in 0..n_phi {
+                let phi = (j as f64 + 0.5) * d_phi;
+                let w = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                );
+                sum += f(&w) * theta.sin() * d_theta * d_phi;
+            }
+        }
+        sum
+    }
+
+    // The same over the whole sphere
+    pub(crate) fn integrate_sphere(f: impl Fn(&Vec3) -> f64) -> f64 {
+        integrate(&f) + integrate(|w| f(&Vec3::new(w.x(), w.y(), -w.z())))
+    }
+
+    fn distributions() -> [Ggx; 3] {
+        [
+            Ggx::new(0.3, 0.3),
+            Ggx::new(0.5, 0.2),
+            Ggx::new_from_roughness(0.9, 0.5),
+        ]
+    }
+
+    fn directions() -> [Vec3; 3] {
+        [
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.5, 0.3, 0.8).unit_vector(),
+            Vec3::new(-0.2, 0.9, 0.3).unit_vector(),
+        ]
+    }
+
+    #[test]
+    fn test_projected_area() {
+        for ggx in distributions() {
+            let area = integrate(|m| ggx.d(m) * m.z());
+            assert!((area - 1.0).abs() < 1e-3, "{}", area);
+        }
+    }
+
+    #[test]
+    fn test_pdf_visible_is_normalized() {
+        for ggx in distributions() {
+            for wo in directions() {
+                let total = integrate(|m| ggx.pdf_visible(&wo, m));
+                assert!((total - 1.0).abs() < 1e-3, "{}", total);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample_visible() {
+        // The mean sampled normal against the mean under pdf_visible
+        let n = 100_000;
+        for ggx in distributions() {
+            for wo in directions() {
+                let mut mean = Vec3::default();
+                for _ in 0..n {
+                    let m = ggx.sample_visible(&wo);
+                    assert!(m.z() > 0.0 && wo.dot(&m) >= -1e-9);
+                    mean += m / n as f64;
+                }
+
+                let expected = Vec3::new(
+                    integrate(|m| m.x() * ggx.pdf_visible(&wo, m)),
+                    integrate(|m| m.y() * ggx.pdf_visible(&wo, m)),
+                    integrate(|m| m.z() * ggx.pdf_visible(&wo, m)),
+                );
+                assert!(
+                    (mean - expected).length() < 0.01,
+                    "{} != {}",
+                    mean,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fresnel_dielectric() {
+        // Normal incidence, from either side
+        assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-12);
+        assert!((fresnel_dielectric(-1.0, 1.5) - 0.04).abs() < 1e-12);
+        assert!(fresnel_dielectric(0.3, 1.0) < 1e-12);
+
+        // Only the perpendicular polarization reflects at Brewster's angle
+        let cos_b = 1.5f64.atan().cos();
+        let cos_t = (1.0 - (1.0 - cos_b * cos_b) / 2.25).sqrt();
+        let r_perpendicular = (cos_b - 1.5 * cos_t) / (cos_b + 1.5 * cos_t);
+        let expected = 0.5 * r_perpendicular * r_perpendicular;
+        assert!((fresnel_dielectric(cos_b, 1.5) - expected).abs() < 1e-12);
+
+        // Total internal reflection past the critical angle inside glass
+        assert_eq!(fresnel_dielectric(-0.5, 1.5), 1.0);
+        assert!(fresnel_dielectric(-0.8, 1.5) < 1.0);
+    }
+
+    #[test]
+    fn test_fresnel_conductor() {
+        // Without absorption a conductor is a dielectric
+        let eta = Vec3::new(1.2, 1.5, 2.0);
+        for cos_i in [1.0, 0.7, 0.3, 0.05] {
+            let r = fresnel_conductor(cos_i, &eta, &Vec3::default());
+            for c in 0..3 {
+                assert!((r[c] - fresnel_dielectric(cos_i, eta[c])).abs() < 1e-9);
+            }
+        }
+
+        // At normal incidence, ((n - 1)² + k²) / ((n + 1)² + k²)
+        let k = Vec3::new(3.0, 2.0, 1.0);
+        let r = fresnel_conductor(1.0, &eta, &k);
+        for c in 0..3 {
+            let (n, k) = (eta[c], k[c]);
+            let expected = ((n - 1.0).powi(2) + k * k) / ((n + 1.0).powi(2) + k * k);
+            assert!((r[c] - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_reflect_and_refract() {
+        let m = Vec3::new(0.0, 0.0, 1.0);
+        let w = Vec3::new(0.6, 0.0, 0.8);
+        assert!((reflect(&w, &m) - Vec3::new(-0.6, 0.0, 0.8)).length() < 1e-12);
+
+        // Snell's law, sin θt = sin θi / eta
+        let t = refract(&w, &m, 1.5).unwrap();
+        assert!((t.length() - 1.0).abs() < 1e-12);
+        assert!((t.x() + 0.6 / 1.5).abs() < 1e-12 && t.z() < 0.0);
+
+        // Going out of glass, up to the critical angle only
+        assert!(refract(&w, &m, 1.0 / 1.5).is_some());
+        assert!(refract(&Vec3::new(0.8, 0.0, 0.6), &m, 1.0 / 1.5).is_none());
+    }
+
+    #[test]
+    fn test_rough_dielectric_pdf() {
+        let n = 100_000;
+        for ggx in distributions() {
+            for wo in directions() {
+                for eta in [1.5, 1.0 / 1.5] {
+                    // The samples that land on the side their lobe covers
+                    let kept = (0..n)
+                        .filter(|_| {
+                            let m = ggx.sample_visible(&wo);
+                            if random_double() < fresnel_dielectric(wo.dot(&m), eta) {
+                                reflect(&wo, &m).z() > 0.0
+                            } else {
+                                refract(&wo, &m, eta).is_some_and(|wi| wi.z() < 0.0)
+                            }
+                        })
+                        .count() as f64
+                        / n as f64;
+                    let total =
+                        integrate_sphere(|wi| evaluate_rough_dielectric(&ggx, &wo, wi, eta).1);
+                    assert!((total - kept).abs() < 0.01, "{} != {}", total, kept);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rough_dielectric_weights() {
+        // With visible normals the D, Fresnel and Jacobian terms cancel both
+        // ways, leaving G / G1
+        for ggx in distributions() {
+            for wo in directions() {
+                for eta in [1.5, 1.0 / 1.5] {
+                    for _ in 0..1000 {
+                        let wi = match sample_rough_dielectric(&ggx, &wo, eta) {
+                            Some(wi) => wi,
+                            None => continue,
+                        };
+                        let (value, pdf) = evaluate_rough_dielectric(&ggx, &wo, &wi, eta);
+                        if pdf == 0.0 {
+                            // Sampled below the surface on the wrong side
+                            assert_eq!(value, 0.0);
+                            continue;
+                        }
+
+                        let expected = ggx.g(&wo, &wi) / ggx.g1(&wo);
+                        assert!((value / pdf - expected).abs() < 1e-6 * expected);
+                    }
+                }
+            }
+        }
+    }
+}
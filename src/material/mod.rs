@@ -1,6 +1,13 @@
+pub mod coated;
+pub mod conductor;
 pub mod dielectric;
 pub mod diffuse_light;
 pub mod isotropic;
 pub mod lambertian;
 pub mod material;
+pub mod medium_interface;
 pub mod metal;
+pub mod microfacet;
+pub mod phase_material;
+pub mod principled;
+pub mod rough_dielectric;
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use crate::{
+    media::phase::PhaseFunction,
+    model::{hit::HitRecord, ray::Ray, vec3::Vec3},
+    texture::{solid_color::SolidColor, texture::Texture},
+};
+
+use super::material::Material;
+
+// Scattering inside a medium following any phase function. The phase function
+// is sampled exactly, so the only attenuation is the single-scattering albedo,
+// and it can be evaluated in any direction, so the lights are sampled too.
+pub struct PhaseMaterial {
+    pub phase: Arc<dyn PhaseFunction + Sync + Send>,
+    pub albedo: Arc<dyn Texture + Sync + Send>,
+}
+
+impl PhaseMaterial {
+    pub fn new(
+        phase: Arc<dyn PhaseFunction + Sync + Send>,
+        a: Arc<dyn Texture + Sync + Send>,
+    ) -> Self {
+        Self { phase, albedo: a }
+    }
+
+    pub fn new_with_color(phase: Arc<dyn PhaseFunction + Sync + Send>, c: Vec3) -> Self {
+        Self {
+            phase,
+            albedo: Arc::new(SolidColor::new(&c)),
+        }
+    }
+}
+
+impl Material for PhaseMaterial {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Vec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        *scattered = Ray::new(&rec.p, &self.phase.sample(r_in.dir()), r_in.time());
+        *attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
+
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Vec3 {
+        self.albedo.value(rec.u, rec.v, &rec.p) * self.phase.pdf(r_in.dir(), dir)
+    }
+
+    fn pdf(&self, r_in: &Ray, _rec: &HitRecord, dir: &Vec3) -> f64 {
+        self.phase.pdf(r_in.dir(), dir)
+    }
+
+    fn is_specular(&self, _r_in: &Ray, _rec: &HitRecord) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{material::microfacet::tests::integrate_sphere, media::phase::HenyeyGreenstein};
+
+    use super::*;
+
+    #[test]
+    fn test_scatter_matches_eval_and_pdf() {
+        let fog = PhaseMaterial::new_with_color(
+            Arc::new(HenyeyGreenstein::new(0.6)),
+            Vec3::new(0.9, 0.7, 0.5),
+        );
+        let dir = Vec3::new(0.6, 0.2, -0.7).unit_vector();
+        let r = Ray::new(&(-dir), &dir, 0.0);
+        let rec = HitRecord::default();
+
+        let total = integrate_sphere(|w| fog.pdf(&r, &rec, w));
+        assert!((total - 1.0).abs() < 1e-2, "{}", total);
+
+        for _ in 0..1000 {
+            let mut attenuation = Vec3::default();
+            let mut scattered = Ray::new(&Vec3::default(), &Vec3::default(), 0.0);
+            assert!(fog.scatter(&r, &rec, &mut attenuation, &mut scattered));
+
+            let expected = fog.eval(&r, &rec, scattered.dir()) / fog.pdf(&r, &rec, scattered.dir());
+            assert!(
+                (attenuation - expected).length() < 1e-9,
+                "{} != {}",
+                attenuation,
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,495 @@
+use std::sync::Arc;
+
+use crate::{
+    media::stack::{Interior, MediumStack},
+    model::{hit::HitRecord, onb::Onb, ray::Ray, vec3::Vec3},
+    spectrum::ior::Ior,
+    texture::{solid_color::SolidColor, texture::Texture},
+    util::rtweekend::{random_double, PI},
+};
+
+use super::{
+    material::Material,
+    microfacet::{
+        evaluate_rough_dielectric, fresnel_dielectric, reflect, sample_rough_dielectric, Ggx,
+    },
+};
+
+// The clearcoat is a fixed, fairly glossy layer, and sheen takes on half of
+// the hue of the base color
+const CLEARCOAT_ROUGHNESS: f64 = 0.1;
+const SHEEN_TINT: f64 = 0.5;
+
+// The Disney principled BSDF (Burley 2012, 2015). A Burley diffuse lobe with
+// sheen, a GGX specular lobe that goes from dielectric to metallic, rough
+// glass for transmission and a clearcoat are blended by the parameters.
+// Every parameter is a texture, the scalar ones read as the mean of their
+// channels and kept in [0, 1]. specular scales the reflectance of the
+// opaque dielectric, 0.5 giving that of the index of refraction itself, and
+// anisotropy stretches the highlights along an arbitrary tangent.
+pub struct Principled {
+    pub base_color: Arc<dyn Texture + Sync + Send>,
+    pub metallic: Arc<dyn Texture + Sync + Send>,
+    pub roughness: Arc<dyn Texture + Sync + Send>,
+    pub specular: Arc<dyn Texture + Sync + Send>,
+    pub sheen: Arc<dyn Texture + Sync + Send>,
+    pub clearcoat: Arc<dyn Texture + Sync + Send>,
+    pub transmission: Arc<dyn Texture + Sync + Send>,
+    pub anisotropy: Arc<dyn Texture + Sync + Send>,
+    pub ior: f64,
+    pub priority: i32,
+}
+
+// The parameters at one point of the surface, and the index ratios across
+// it for the glass and for the opaque lobes
+struct Parameters {
+    base_color: Vec3,
+    metallic: f64,
+    roughness: f64,
+    specular: f64,
+    sheen: f64,
+    clearcoat: f64,
+    transmission: f64,
+    anisotropy: f64,
+    eta: f64,
+    opaque_eta: f64,
+}
+
+// How likely each lobe is to be sampled
+struct LobeWeights {
+    diffuse: f64,
+    specular: f64,
+    glass: f64,
+    clearcoat: f64,
+}
+
+impl Principled {
+    pub fn new(a: &Vec3) -> Self {
+        Principled::new_with_texture(Arc::new(SolidColor::new(a)))
+    }
+
+    // A rough plastic-like dielectric, with everything else turned off.
+    pub fn new_with_texture(a: Arc<dyn Texture + Sync + Send>) -> Self {
+        let constant = |value: f64| -> Arc<dyn Texture + Sync + Send> {
+            Arc::new(SolidColor::new(&Vec3::new(value, value, value)))
+        };
+
+        Self {
+            base_color: a,
+            metallic: constant(0.0),
+            roughness: constant(0.5),
+            specular: constant(0.5),
+            sheen: constant(0.0),
+            clearcoat: constant(0.0),
+            transmission: constant(0.0),
+            anisotropy: constant(0.0),
+            ior: 1.5,
+            priority: 0,
+        }
+    }
+
+    fn parameters(&self, r_in: &Ray, rec: &HitRecord, eta: f64) -> Parameters {
+        let scalar = |texture: &Arc<dyn Texture + Sync + Send>| {
+            let value = texture.value(rec.u, rec.v, &rec.p);
+            ((value.x() + value.y() + value.z()) / 3.0).clamp(0.0, 1.0)
+        };
+
+        Parameters {
+            base_color: self.base_color.value_at(rec),
+            metallic: scalar(&self.metallic),
+            roughness: scalar(&self.roughness),
+            specular: scalar(&self.specular),
+            sheen: scalar(&self.sheen),
+            clearcoat: scalar(&self.clearcoat),
+            transmission: scalar(&self.transmission),
+            anisotropy: scalar(&self.anisotropy),
+            eta,
+            // Light never gets inside the opaque part, so it reflects as if
+            // entered from whatever the ray is in, whichever face was hit
+            opaque_eta: (self.ior / r_in.media().ior(r_in.wavelength())).max(1.0),
+        }
+    }
+
+    // The local frame at the hit, the direction back along r_in in it, and
+    // the index below the surface over the index above. None inside a higher
+    // priority object, where the surface is not there.
+    fn local_frame(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Onb, Vec3, f64)> {
+        let interior = self.interior().unwrap();
+        let (near, far) = r_in.media().crossing(&interior, rec.front_face);
+        if !MediumStack::is_interface(&near, &far) {
+            return None;
+        }
+
+        let eta = far.ior(r_in.wavelength()) / near.ior(r_in.wavelength());
+        let frame = Onb::new_from_w(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir().unit_vector());
+        Some((frame, wo, eta))
+    }
+
+    // Roughly how much each lobe reflects towards wo, normalized
+    fn lobe_weights(p: &Parameters, wo: &Vec3) -> LobeWeights {
+        let dielectric = (1.0 - p.metallic) * (1.0 - p.transmission);
+        let fresnel = (2.0 * p.specular * fresnel_dielectric(wo.z(), p.opaque_eta)).min(1.0);
+        let metal = luminance(&schlick(&p.base_color, wo.z()));
+
+        let mut weights = LobeWeights {
+            diffuse: dielectric * luminance(&p.base_color),
+            specular: dielectric * fresnel + p.metallic * metal,
+            glass: (1.0 - p.metallic) * p.transmission,
+            clearcoat: 0.25 * p.clearcoat * (0.04 + 0.96 * schlick_weight(wo.z())),
+        };
+
+        let total = weights.diffuse + weights.specular + weights.glass + weights.clearcoat;
+        if total > 0.0 {
+            weights.diffuse /= total;
+            weights.specular /= total;
+            weights.glass /= total;
+            weights.clearcoat /= total;
+        }
+
+        weights
+    }
+
+    // The BSDF times |cos theta_i| and the density of sampling wi, in the
+    // local frame.
+    fn evaluate(p: &Parameters, wo: &Vec3, wi: &Vec3) -> (Vec3, f64) {
+        let weights = Principled::lobe_weights(p, wo);
+        let ggx = Ggx::new_from_roughness(p.roughness, p.anisotropy);
+        let coat = Ggx::new_from_roughness(CLEARCOAT_ROUGHNESS, 0.0);
+        let dielectric = (1.0 - p.metallic) * (1.0 - p.transmission);
+        let glass = (1.0 - p.metallic) * p.transmission;
+
+        let mut value = Vec3::new(0.0, 0.0, 0.0);
+        let mut pdf = 0.0;
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return (value, pdf);
+        }
+
+        // Glass covers both sides, tinting what it lets through
+        let (glass_value, glass_pdf) = evaluate_rough_dielectric(&ggx, wo, wi, p.eta);
+        let tint = if wi.z() < 0.0 {
+            p.base_color
+        } else {
+            Vec3::new(1.0, 1.0, 1.0)
+        };
+        value += glass * glass_value * tint;
+        pdf += weights.glass * glass_pdf;
+
+        if wi.z() < 0.0 {
+            return (value, pdf);
+        }
+
+        let h = (wo + wi).unit_vector();
+        let cos_d = wi.dot(&h);
+
+        // Burley diffuse with retro-reflection at grazing angles, and sheen
+        let (f_l, f_v) = (schlick_weight(wi.z()), schlick_weight(wo.z()));
+        let retro = 2.0 * p.roughness * cos_d * cos_d;
+        let diffuse =
+            (1.0 - 0.5 * f_l) * (1.0 - 0.5 * f_v) + retro * (f_l + f_v + f_l * f_v * (retro - 1.0));
+        let tint = if luminance(&p.base_color) > 0.0 {
+            p.base_color / luminance(&p.base_color)
+        } else {
+            Vec3::new(1.0, 1.0, 1.0)
+        };
+        let sheen_color = (1.0 - SHEEN_TINT) * Vec3::new(1.0, 1.0, 1.0) + SHEEN_TINT * tint;
+        let sheen = p.sheen * schlick_weight(cos_d) * sheen_color;
+        value += dielectric * (diffuse / PI * p.base_color + sheen) * wi.z();
+        pdf += weights.diffuse * wi.z() / PI;
+
+        // The specular lobe of the opaque dielectric and the metal
+        let fresnel = dielectric
+            * (2.0 * p.specular * fresnel_dielectric(cos_d, p.opaque_eta)).min(1.0)
+            * Vec3::new(1.0, 1.0, 1.0)
+            + p.metallic * schlick(&p.base_color, cos_d);
+        value += fresnel * (ggx.d(&h) * ggx.g(wo, wi) / (4.0 * wo.z()));
+        pdf += weights.specular * ggx.pdf_visible(wo, &h) / (4.0 * wo.dot(&h));
+
+        let coat_fresnel = 0.04 + 0.96 * schlick_weight(cos_d);
+        value += Vec3::new(1.0, 1.0, 1.0)
+            * (0.25 * p.clearcoat * coat_fresnel * coat.d(&h) * coat.g(wo, wi) / (4.0 * wo.z()));
+        pdf += weights.clearcoat * coat.pdf_visible(wo, &h) / (4.0 * wo.dot(&h));
+
+        (value, pdf)
+    }
+
+    fn evaluate_world(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> (Vec3, f64) {
+        match self.local_frame(r_in, rec) {
+            Some((frame, wo, eta)) => Principled::evaluate(
+                &self.parameters(r_in, rec, eta),
+                &wo,
+                &frame.to_local(&dir.unit_vector()),
+            ),
+            None => (Vec3::new(0.0, 0.0, 0.0), 0.0),
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Vec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        let (frame, wo, eta) = match self.local_frame(r_in, rec) {
+            Some(local) => local,
+            None => {
+                *attenuation = Vec3::new(1.0, 1.0, 1.0);
+                *scattered = Ray::new(&rec.p, &r_in.dir().unit_vector(), r_in.time());
+                return true;
+            }
+        };
+
+        // Pick a lobe, sample it, and weight by the density of all of them
+        let p = self.parameters(r_in, rec, eta);
+        let weights = Principled::lobe_weights(&p, &wo);
+        let xi = random_double();
+        let wi = if xi < weights.diffuse {
+            let mut wi = Vec3::new(0.0, 0.0, 1.0) + Vec3::random_unit_vector();
+            if wi.near_zero() {
+                wi = Vec3::new(0.0, 0.0, 1.0);
+            }
+            Some(wi.unit_vector())
+        } else if xi < weights.diffuse + weights.specular {
+            let ggx = Ggx::new_from_roughness(p.roughness, p.anisotropy);
+            Some(reflect(&wo, &ggx.sample_visible(&wo))).filter(|wi| wi.z() > 0.0)
+        } else if xi < weights.diffuse + weights.specular + weights.glass {
+            // Glass between equal indices lets light straight through, which
+            // only sampling can find
+            if p.eta == 1.0 {
+                *attenuation = (1.0 - p.metallic) * p.transmission / weights.glass * p.base_color;
+                *scattered = Ray::new(&rec.p, &r_in.dir().unit_vector(), r_in.time());
+                return true;
+            }
+
+            let ggx = Ggx::new_from_roughness(p.roughness, p.anisotropy);
+            sample_rough_dielectric(&ggx, &wo, p.eta)
+        } else {
+            let coat = Ggx::new_from_roughness(CLEARCOAT_ROUGHNESS, 0.0);
+            Some(reflect(&wo, &coat.sample_visible(&wo))).filter(|wi| wi.z() > 0.0)
+        };
+
+        // Directions on the wrong side of the surface for their lobe are
+        // lost, as they are for the conductor
+        let wi = match wi {
+            Some(wi) => wi,
+            None => return false,
+        };
+        let (value, pdf) = Principled::evaluate(&p, &wo, &wi);
+        if pdf <= 0.0 {
+            return false;
+        }
+
+        *attenuation = value / pdf;
+        *scattered = Ray::new(&rec.p, &frame.local(&wi), r_in.time());
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Vec3 {
+        self.evaluate_world(r_in, rec, dir).0
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> f64 {
+        self.evaluate_world(r_in, rec, dir).1
+    }
+
+    // Light passes straight through where the surface is not there, and
+    // through glass between equal indices
+    fn is_specular(&self, r_in: &Ray, rec: &HitRecord) -> bool {
+        match self.local_frame(r_in, rec) {
+            Some((_, wo, eta)) => {
+                let p = self.parameters(r_in, rec, eta);
+                eta == 1.0 && Principled::lobe_weights(&p, &wo).glass > 0.0
+            }
+            None => true,
+        }
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        Some(Interior {
+            id: self as *const Self as usize,
+            priority: self.priority,
+            ior: Some(Ior::Constant(self.ior)),
+            medium: None,
+        })
+    }
+}
+
+fn luminance(c: &Vec3) -> f64 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+fn schlick_weight(cos: f64) -> f64 {
+    (1.0 - cos.clamp(0.0, 1.0)).powi(5)
+}
+
+fn schlick(f0: &Vec3, cos: f64) -> Vec3 {
+    f0 + (Vec3::new(1.0, 1.0, 1.0) - f0) * schlick_weight(cos)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::microfacet::tests::integrate_sphere;
+
+    use super::*;
+
+    fn constant(value: f64) -> Arc<dyn Texture + Sync + Send> {
+        Arc::new(SolidColor::new(&Vec3::new(value, value, value)))
+    }
+
+    fn plastic() -> Principled {
+        Principled::new(&Vec3::new(0.8, 0.3, 0.2))
+    }
+
+    fn metal() -> Principled {
+        let mut metal = Principled::new(&Vec3::new(0.9, 0.6, 0.3));
+        metal.metallic = constant(1.0);
+        metal.roughness = constant(0.4);
+        metal.anisotropy = constant(0.6);
+        metal
+    }
+
+    fn coated() -> Principled {
+        let mut coated = Principled::new(&Vec3::new(0.2, 0.4, 0.8));
+        coated.sheen = constant(1.0);
+        coated.clearcoat = constant(1.0);
+        coated
+    }
+
+    fn glass() -> Principled {
+        let mut glass = Principled::new(&Vec3::new(0.9, 1.0, 0.9));
+        glass.transmission = constant(1.0);
+        glass.roughness = constant(0.6);
+        glass
+    }
+
+    fn hit_from(dir: &Vec3) -> (Ray, HitRecord) {
+        let r = Ray::new(&(-dir), dir, 0.0);
+        let mut rec = HitRecord::default();
+        rec.set_face_normal(&r, &Vec3::new(0.0, 0.0, 1.0));
+        (r, rec)
+    }
+
+    fn weights(material: &Principled, wo: &Vec3) -> LobeWeights {
+        let (r, rec) = hit_from(&-wo);
+        let p = material.parameters(&r, &rec, material.ior);
+        Principled::lobe_weights(&p, wo)
+    }
+
+    #[test]
+    fn test_lobe_weights() {
+        let wo = Vec3::new(0.3, 0.0, 0.9).unit_vector();
+        for material in [plastic(), metal(), coated(), glass()] {
+            let w = weights(&material, &wo);
+            let total = w.diffuse + w.specular + w.glass + w.clearcoat;
+            assert!((total - 1.0).abs() < 1e-12);
+        }
+
+        let w = weights(&plastic(), &wo);
+        assert!(w.diffuse > w.specular && w.specular > 0.0);
+        assert_eq!((w.glass, w.clearcoat), (0.0, 0.0));
+
+        let w = weights(&metal(), &wo);
+        assert_eq!(
+            (w.diffuse, w.specular, w.glass, w.clearcoat),
+            (0.0, 1.0, 0.0, 0.0)
+        );
+
+        let w = weights(&coated(), &wo);
+        assert!(w.clearcoat > 0.0 && w.glass == 0.0);
+
+        let w = weights(&glass(), &wo);
+        assert_eq!((w.diffuse, w.specular, w.glass), (0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_pdf_matches_sampling() {
+        // The density integrates to the fraction of samples that are kept
+        let dir = Vec3::new(0.5, 0.3, -0.8).unit_vector();
+        let (r, rec) = hit_from(&dir);
+        let n = 20_000;
+        for material in [plastic(), metal(), coated(), glass()] {
+            let mut kept = 0;
+            for _ in 0..n {
+                let mut attenuation = Vec3::default();
+                let mut scattered = Ray::new(&Vec3::default(), &Vec3::default(), 0.0);
+                if material.scatter(&r, &rec, &mut attenuation, &mut scattered) {
+                    kept += 1;
+                }
+            }
+
+            let kept = kept as f64 / n as f64;
+            let total = integrate_sphere(|dir| material.pdf(&r, &rec, dir));
+            assert!((total - kept).abs() < 0.02, "{} != {}", total, kept);
+        }
+    }
+
+    #[test]
+    fn test_scatter_matches_eval_and_pdf() {
+        let dir = Vec3::new(0.5, 0.3, -0.8).unit_vector();
+        let (r, rec) = hit_from(&dir);
+        for material in [plastic(), metal(), coated(), glass()] {
+            assert!(!material.is_specular(&r, &rec));
+            for _ in 0..1000 {
+                let mut attenuation = Vec3::default();
+                let mut scattered = Ray::new(&Vec3::default(), &Vec3::default(), 0.0);
+                if !material.scatter(&r, &rec, &mut attenuation, &mut scattered) {
+                    continue;
+                }
+
+                let expected = material.eval(&r, &rec, scattered.dir())
+                    / material.pdf(&r, &rec, scattered.dir());
+                assert!((attenuation - expected).length() < 1e-9 * expected.length());
+            }
+        }
+    }
+
+    #[test]
+    fn test_white_furnace() {
+        // Half rough glass, half plastic, lit evenly from every direction.
+        // What scatter carries off averages to what eval integrates to, and
+        // is never more than came in.
+        let mut material = Principled::new(&Vec3::new(1.0, 1.0, 1.0));
+        material.transmission = constant(0.5);
+        material.roughness = constant(1.0);
+        for dir in [Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.6, 0.0, -0.8)] {
+            let (r, rec) = hit_from(&dir);
+            let n = 100_000;
+            let mut total = 0.0;
+            for _ in 0..n {
+                let mut attenuation = Vec3::default();
+                let mut scattered = Ray::new(&Vec3::default(), &Vec3::default(), 0.0);
+                if material.scatter(&r, &rec, &mut attenuation, &mut scattered) {
+                    total += attenuation.x();
+                }
+            }
+
+            let albedo = total / n as f64;
+            let expected = integrate_sphere(|dir| material.eval(&r, &rec, dir).x());
+            assert!(
+                (albedo - expected).abs() < 0.01,
+                "{} != {}",
+                albedo,
+                expected
+            );
+            assert!(albedo < 1.0, "{}", albedo);
+        }
+    }
+
+    #[test]
+    fn test_glass_between_equal_indices() {
+        let mut material = glass();
+        material.ior = 1.0;
+        let (r, rec) = hit_from(&Vec3::new(0.6, 0.0, -0.8));
+        assert!(material.is_specular(&r, &rec));
+
+        // Straight through, tinted by the base color
+        let mut attenuation = Vec3::default();
+        let mut scattered = Ray::new(&Vec3::default(), &Vec3::default(), 0.0);
+        assert!(material.scatter(&r, &rec, &mut attenuation, &mut scattered));
+        assert!((attenuation - Vec3::new(0.9, 1.0, 0.9)).length() < 1e-12);
+        assert!((scattered.dir() - r.dir()).length() < 1e-12);
+    }
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use crate::{
+    media::{
+        medium::Medium,
+        stack::{Interior, MediumStack},
+    },
+    model::{hit::HitRecord, onb::Onb, ray::Ray, vec3::Vec3},
+    spectrum::ior::Ior,
+};
+
+use super::{
+    material::Material,
+    microfacet::{evaluate_rough_dielectric, sample_rough_dielectric, Ggx},
+};
+
+// Frosted glass: a dielectric whose surface is made of GGX microfacets, each
+// reflecting or refracting by its own Fresnel term. It takes its indices from
+// the ray's medium stack just like Dielectric.
+pub struct RoughDielectric {
+    pub ior: Ior,
+    pub roughness: f64,
+    pub priority: i32,
+    pub medium: Option<Arc<dyn Medium + Sync + Send>>,
+}
+
+impl RoughDielectric {
+    pub fn new(index_of_refraction: f64, roughness: f64) -> Self {
+        RoughDielectric::new_with_ior(Ior::Constant(index_of_refraction), roughness)
+    }
+
+    pub fn new_with_ior(ior: Ior, roughness: f64) -> Self {
+        Self {
+            ior,
+            roughness,
+            priority: 0,
+            medium: None,
+        }
+    }
+
+    pub fn new_with_medium(
+        index_of_refraction: f64,
+        roughness: f64,
+        medium: Arc<dyn Medium + Sync + Send>,
+    ) -> Self {
+        Self {
+            ior: Ior::Constant(index_of_refraction),
+            roughness,
+            priority: 0,
+            medium: Some(medium),
+        }
+    }
+
+    // The local frame at the hit, the direction back along r_in in it, and
+    // the index below the surface over the index above. None where the
+    // surface is not really there: inside a higher priority object, or
+    // between equal indices.
+    fn local_frame(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Onb, Vec3, f64)> {
+        let interior = self.interior().unwrap();
+        let (near, far) = r_in.media().crossing(&interior, rec.front_face);
+        let (n_near, n_far) = (near.ior(r_in.wavelength()), far.ior(r_in.wavelength()));
+        if !MediumStack::is_interface(&near, &far) || n_near == n_far {
+            return None;
+        }
+
+        let frame = Onb::new_from_w(&rec.normal);
+        let wo = frame.to_local(&-r_in.dir().unit_vector());
+        Some((frame, wo, n_far / n_near))
+    }
+
+    fn evaluate(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> (f64, f64) {
+        match self.local_frame(r_in, rec) {
+            Some((frame, wo, eta)) => {
+                let ggx = Ggx::new_from_roughness(self.roughness, 0.0);
+                evaluate_rough_dielectric(&ggx, &wo, &frame.to_local(&dir.unit_vector()), eta)
+            }
+            None => (0.0, 0.0),
+        }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Vec3,
+        scattered: &mut Ray,
+    ) -> bool {
+        let (frame, wo, eta) = match self.local_frame(r_in, rec) {
+            Some(local) => local,
+            None => {
+                *attenuation = Vec3::new(1.0, 1.0, 1.0);
+                *scattered = Ray::new(&rec.p, &r_in.dir().unit_vector(), r_in.time());
+                return true;
+            }
+        };
+
+        let ggx = Ggx::new_from_roughness(self.roughness, 0.0);
+        let wi = match sample_rough_dielectric(&ggx, &wo, eta) {
+            Some(wi) => wi,
+            None => return false,
+        };
+
+        let (value, pdf) = evaluate_rough_dielectric(&ggx, &wo, &wi, eta);
+        if pdf <= 0.0 {
+            return false;
+        }
+
+        *attenuation = Vec3::new(1.0, 1.0, 1.0) * (value / pdf);
+        *scattered = Ray::new(&rec.p, &frame.local(&wi), r_in.time());
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Vec3 {
+        let (value, _) = self.evaluate(r_in, rec, dir);
+        Vec3::new(value, value, value)
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> f64 {
+        self.evaluate(r_in, rec, dir).1
+    }
+
+    // Where the surface is not there, light passes straight through it
+    fn is_specular(&self, r_in: &Ray, rec: &HitRecord) -> bool {
+        self.local_frame(r_in, rec).is_none()
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        Some(Interior {
+            id: self as *const Self as usize,
+            priority: self.priority,
+            ior: Some(self.ior),
+            medium: self.medium.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit_from(dir: &Vec3) -> (Ray, HitRecord) {
+        let r = Ray::new(&(-dir), dir, 0.0);
+        let mut rec = HitRecord::default();
+        rec.set_face_normal(&r, &Vec3::new(0.0, 0.0, 1.0));
+        (r, rec)
+    }
+
+    #[test]
+    fn test_scatter_matches_eval_and_pdf() {
+        let glass = RoughDielectric::new(1.5, 0.5);
+        let (r, rec) = hit_from(&Vec3::new(0.6, 0.2, -0.7).unit_vector());
+        assert!(!glass.is_specular(&r, &rec));
+
+        let (mut reflected, mut refracted) = (0, 0);
+        for _ in 0..1000 {
+            let mut attenuation = Vec3::default();
+            let mut scattered = Ray::new(&Vec3::default(), &Vec3::default(), 0.0);
+            if !glass.scatter(&r, &rec, &mut attenuation, &mut scattered) {
+                continue;
+            }
+            if scattered.dir().z() > 0.0 {
+                reflected += 1;
+            } else {
+                refracted += 1;
+            }
+
+            let expected =
+                glass.eval(&r, &rec, scattered.dir()) / glass.pdf(&r, &rec, scattered.dir());
+            assert!((attenuation - expected).length() < 1e-9 * expected.length());
+        }
+        assert!(reflected > 0 && refracted > reflected);
+    }
+
+    #[test]
+    fn test_equal_indices_pass_through() {
+        let glass = RoughDielectric::new(1.0, 0.5);
+        let dir = Vec3::new(0.6, 0.0, -0.8);
+        let (r, rec) = hit_from(&dir);
+        assert!(glass.is_specular(&r, &rec));
+
+        let mut attenuation = Vec3::default();
+        let mut scattered = Ray::new(&Vec3::default(), &Vec3::default(), 0.0);
+        assert!(glass.scatter(&r, &rec, &mut attenuation, &mut scattered));
+        assert_eq!(attenuation, Vec3::new(1.0, 1.0, 1.0));
+        assert!((scattered.dir() - dir).length() < 1e-12);
+        assert_eq!(glass.pdf(&r, &rec, &dir), 0.0);
+    }
+}
@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use crate::{
+    model::{ray::Ray, vec3::Vec3},
+    spectrum::smits::rgb_to_spectrum,
+    util::rtweekend::{random_int, INFINITY},
+};
+
+use super::{
+    medium::{Medium, MediumSample},
+    phase::{IsotropicPhase, PhaseFunction},
+    tracking::sample_exponential,
+};
+
+fn average(v: &Vec3) -> f64 {
+    (v.x() + v.y() + v.z()) / 3.0
+}
+
+// A medium with per-channel absorption and scattering coefficients, per unit
+// of distance. Flights are sampled with the extinction of one channel picked
+// at random, and weighted by the average density over all three channels
+// (spectral MIS), so every channel stays unbiased however far apart their
+// extinctions are.
+pub struct HomogeneousMedium {
+    pub sigma_a: Vec3,
+    pub sigma_s: Vec3,
+    pub phase: Arc<dyn PhaseFunction + Sync + Send>,
+}
+
+impl HomogeneousMedium {
+    pub fn new(sigma_a: &Vec3, sigma_s: &Vec3) -> Self {
+        HomogeneousMedium::new_with_phase(sigma_a, sigma_s, Arc::new(IsotropicPhase))
+    }
+
+    pub fn new_with_phase(
+        sigma_a: &Vec3,
+        sigma_s: &Vec3,
+        phase: Arc<dyn PhaseFunction + Sync + Send>,
+    ) -> Self {
+        Self {
+            sigma_a: *sigma_a,
+            sigma_s: *sigma_s,
+            phase,
+        }
+    }
+
+    // The coefficients seen by r: as given, or the same for every channel at
+    // the ray's wavelength when rendering spectrally.
+    fn coefficients(&self, r: &Ray) -> (Vec3, Vec3) {
+        match r.wavelength() {
+            Some(lambda) => {
+                let sigma_a = rgb_to_spectrum(&self.sigma_a, lambda);
+                let sigma_s = rgb_to_spectrum(&self.sigma_s, lambda);
+                (
+                    Vec3::new(sigma_a, sigma_a, sigma_a),
+                    Vec3::new(sigma_s, sigma_s, sigma_s),
+                )
+            }
+            None => (self.sigma_a, self.sigma_s),
+        }
+    }
+
+    fn transmittance_over(sigma_t: &Vec3, distance: f64) -> Vec3 {
+        let mut transmittance = Vec3::default();
+        for c in 0..3 {
+            transmittance[c] = if sigma_t[c] > 0.0 {
+                (-sigma_t[c] * distance).exp()
+            } else {
+                1.0
+            };
+        }
+
+        transmittance
+    }
+}
+
+impl Medium for HomogeneousMedium {
+    fn sample(&self, r: &Ray, t_max: f64) -> MediumSample {
+        let ray_length = r.dir().length();
+        let (sigma_a, sigma_s) = self.coefficients(r);
+        let sigma_t = sigma_a + sigma_s;
+        let max_distance = t_max * ray_length;
+
+        // With nothing to scatter off there are no events to sample, just
+        // Beer-Lambert attenuation over the whole flight
+        if (0..3).all(|c| sigma_s[c] <= 0.0) {
+            return MediumSample::Pass {
+                weight: HomogeneousMedium::transmittance_over(&sigma_t, max_distance),
+            };
+        }
+
+        let channel = random_int(0, 3);
+        let distance = if sigma_t[channel] > 0.0 {
+            sample_exponential(sigma_t[channel])
+        } else {
+            INFINITY
+        };
+
+        if distance < max_distance {
+            let transmittance = HomogeneousMedium::transmittance_over(&sigma_t, distance);
+            let pdf = average(&(sigma_t * transmittance));
+            return MediumSample::Scatter {
+                t: distance / ray_length,
+                weight: sigma_s * transmittance / pdf,
+                phase: self.phase.clone(),
+            };
+        }
+
+        // Escaping is as likely as the average transmittance
+        let transmittance = HomogeneousMedium::transmittance_over(&sigma_t, max_distance);
+        let pdf = average(&transmittance);
+        MediumSample::Pass {
+            weight: if pdf > 0.0 {
+                transmittance / pdf
+            } else {
+                Vec3::default()
+            },
+        }
+    }
+
+    fn transmittance(&self, r: &Ray, t_max: f64) -> Vec3 {
+        let (sigma_a, sigma_s) = self.coefficients(r);
+        HomogeneousMedium::transmittance_over(&(sigma_a + sigma_s), t_max * r.dir().length())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transmittance() {
+        let sigma_a = Vec3::new(0.1, 0.0, 0.4);
+        let sigma_s = Vec3::new(0.2, 0.0, 0.8);
+        let medium = HomogeneousMedium::new(&sigma_a, &sigma_s);
+
+        // t is in units of the ray direction, which is 3 long here
+        let r = Ray::new(&Vec3::new(1.0, 2.0, 3.0), &Vec3::new(0.0, 0.0, -3.0), 0.0);
+        let distance = 2.0 * 3.0;
+        let transmittance = medium.transmittance(&r, 2.0);
+        for c in 0..3 {
+            let expected = (-(sigma_a[c] + sigma_s[c]) * distance).exp();
+            assert!((transmittance[c] - expected).abs() < 1e-12);
+        }
+        assert_eq!(transmittance.y(), 1.0);
+
+        // At one wavelength every channel sees the same extinction
+        let lambda = 550.0;
+        let r = r.with_wavelength(Some(lambda));
+        let sigma_t = rgb_to_spectrum(&sigma_a, lambda) + rgb_to_spectrum(&sigma_s, lambda);
+        let expected = (-sigma_t * distance).exp();
+        let transmittance = medium.transmittance(&r, 2.0);
+        for c in 0..3 {
+            assert!((transmittance[c] - expected).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use crate::model::{ray::Ray, vec3::Vec3};
+
+use super::phase::PhaseFunction;
+
+pub enum MediumSample {
+    // Light scatters at t, following the phase function. weight is the
+    // throughput of the flight up to and including the scattering.
+    Scatter {
+        t: f64,
+        weight: Vec3,
+        phase: Arc<dyn PhaseFunction + Sync + Send>,
+    },
+    // The flight reaches t_max, carrying weight.
+    Pass {
+        weight: Vec3,
+    },
+}
+
+// A medium that rays travel through between surfaces, as opposed to the media
+// that are objects in the scene. Rays carry the medium they are in, and the
+// integrator asks it where along the ray light interacts before the next
+// surface.
+pub trait Medium {
+    fn sample(&self, r: &Ray, t_max: f64) -> MediumSample;
+
+    // The fraction of each channel that makes it from the ray origin to t_max.
+    fn transmittance(&self, r: &Ray, t_max: f64) -> Vec3;
+}
@@ -0,0 +1,5 @@
+pub mod homogeneous;
+pub mod medium;
+pub mod phase;
+pub mod stack;
+pub mod tracking;
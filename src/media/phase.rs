@@ -0,0 +1,206 @@
+use crate::{
+    model::{onb::Onb, vec3::Vec3},
+    util::{
+        polynomial::solve_cubic,
+        rtweekend::{random_double, PI},
+    },
+};
+
+// How a medium redistributes the light it scatters. Directions are the ways
+// the light travels, so cos_theta = 1 means carrying straight on. Every phase
+// function here is sampled exactly, so the pdf is also its value.
+pub trait PhaseFunction {
+    fn p(&self, cos_theta: f64) -> f64;
+
+    // A cos_theta drawn with density p(cos_theta) * 2 pi
+    fn sample_cos_theta(&self) -> f64;
+
+    fn pdf(&self, dir_in: &Vec3, dir_out: &Vec3) -> f64 {
+        self.p(dir_in.unit_vector().dot(&dir_out.unit_vector()))
+    }
+
+    // A unit direction for light arriving along dir_in to scatter into.
+    fn sample(&self, dir_in: &Vec3) -> Vec3 {
+        let cos_theta = self.sample_cos_theta().clamp(-1.0, 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = 2.0 * PI * random_double();
+
+        Onb::new_from_w(dir_in).local(&Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+}
+
+pub struct IsotropicPhase;
+
+impl PhaseFunction for IsotropicPhase {
+    fn p(&self, _cos_theta: f64) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn sample_cos_theta(&self) -> f64 {
+        1.0 - 2.0 * random_double()
+    }
+}
+
+// Forward scattering for g > 0, backward for g < 0 and isotropic at g = 0.
+// g is the mean cosine of the scattering angle.
+pub struct HenyeyGreenstein {
+    pub g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(g: f64) -> Self {
+        Self {
+            g: g.clamp(-0.999, 0.999),
+        }
+    }
+}
+
+impl PhaseFunction for HenyeyGreenstein {
+    fn p(&self, cos_theta: f64) -> f64 {
+        let g = self.g;
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+
+        (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+    }
+
+    fn sample_cos_theta(&self) -> f64 {
+        let g = self.g;
+        if g.abs() < 1e-3 {
+            return 1.0 - 2.0 * random_double();
+        }
+
+        let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * random_double());
+        (1.0 + g * g - s * s) / (2.0 * g)
+    }
+}
+
+// A blend of a forward and a backward Henyey-Greenstein lobe, weight being the
+// share of the first. Clouds use it for a bright forward peak with a softer
+// back glow.
+pub struct DoubleHenyeyGreenstein {
+    pub forward: HenyeyGreenstein,
+    pub backward: HenyeyGreenstein,
+    pub weight: f64,
+}
+
+impl DoubleHenyeyGreenstein {
+    pub fn new(g_forward: f64, g_backward: f64, weight: f64) -> Self {
+        Self {
+            forward: HenyeyGreenstein::new(g_forward),
+            backward: HenyeyGreenstein::new(g_backward),
+            weight: weight.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl PhaseFunction for DoubleHenyeyGreenstein {
+    fn p(&self, cos_theta: f64) -> f64 {
+        self.weight * self.forward.p(cos_theta) + (1.0 - self.weight) * self.backward.p(cos_theta)
+    }
+
+    fn sample_cos_theta(&self) -> f64 {
+        if random_double() < self.weight {
+            self.forward.sample_cos_theta()
+        } else {
+            self.backward.sample_cos_theta()
+        }
+    }
+}
+
+// Scattering by particles much smaller than the wavelength, as in clear air.
+pub struct Rayleigh;
+
+impl PhaseFunction for Rayleigh {
+    fn p(&self, cos_theta: f64) -> f64 {
+        3.0 / (16.0 * PI) * (1.0 + cos_theta * cos_theta)
+    }
+
+    // Inverts the CDF (x^3 + 3x + 4) / 8, a cubic with a single real root
+    fn sample_cos_theta(&self) -> f64 {
+        solve_cubic(1.0, 0.0, 3.0, 4.0 - 8.0 * random_double())[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The probability p puts on cos_theta in [a, b], by the midpoint rule
+    fn integrate(phase: &dyn PhaseFunction, a: f64, b: f64) -> f64 {
+        let steps = 10_000;
+        let h = (b - a) / steps as f64;
+        (0..steps)
+            .map(|i| 2.0 * PI * phase.p(a + (i as f64 + 0.5) * h) * h)
+            .sum()
+    }
+
+    // Sampled cosines land in each bin as often as p says they should
+    fn check_sampling(phase: &dyn PhaseFunction) {
+        let total = integrate(phase, -1.0, 1.0);
+        assert!((total - 1.0).abs() < 1e-3, "{}", total);
+
+        let (n, bins) = (200_000, 20);
+        let mut counts = vec![0; bins];
+        for _ in 0..n {
+            let cos_theta = phase.sample_cos_theta();
+            assert!((-1.0..=1.0).contains(&cos_theta));
+            let bin = ((cos_theta + 1.0) / 2.0 * bins as f64) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+
+        for (i, count) in counts.iter().enumerate() {
+            let a = -1.0 + 2.0 * i as f64 / bins as f64;
+            let expected = integrate(phase, a, a + 2.0 / bins as f64);
+            let observed = *count as f64 / n as f64;
+            let tolerance = 5.0 * (expected / n as f64).sqrt() + 1e-4;
+            assert!(
+                (observed - expected).abs() < tolerance,
+                "bin {}: {} != {}",
+                i,
+                observed,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_isotropic() {
+        check_sampling(&IsotropicPhase);
+    }
+
+    #[test]
+    fn test_henyey_greenstein() {
+        for g in [-0.7, 0.0, 0.3, 0.9] {
+            check_sampling(&HenyeyGreenstein::new(g));
+        }
+    }
+
+    #[test]
+    fn test_double_henyey_greenstein() {
+        check_sampling(&DoubleHenyeyGreenstein::new(0.8, -0.4, 0.7));
+    }
+
+    #[test]
+    fn test_rayleigh() {
+        check_sampling(&Rayleigh);
+    }
+
+    #[test]
+    fn test_sample_direction() {
+        // g is the mean cosine between the incoming and scattered directions
+        let phase = HenyeyGreenstein::new(0.6);
+        let dir_in = Vec3::new(1.0, 2.0, -2.0);
+        let n = 100_000;
+        let mut mean = 0.0;
+        for _ in 0..n {
+            let dir_out = phase.sample(&dir_in);
+            assert!((dir_out.length() - 1.0).abs() < 1e-9);
+            mean += dir_in.unit_vector().dot(&dir_out) / n as f64;
+        }
+        assert!((mean - 0.6).abs() < 0.01, "{}", mean);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{cmp::Reverse, sync::Arc};
+
+use crate::spectrum::ior::Ior;
+
+use super::medium::Medium;
+
+// What fills a closed surface: an index of refraction, or None to take on the
+// index of whatever surrounds it, and a medium, or None for clear space.
+// Where objects overlap, the one with the highest priority wins.
+#[derive(Clone)]
+pub struct Interior {
+    // Tells apart the objects a ray is inside of, typically the address of
+    // their material
+    pub id: usize,
+    pub priority: i32,
+    pub ior: Option<Ior>,
+    pub medium: Option<Arc<dyn Medium + Sync + Send>>,
+}
+
+// The objects a ray is inside of, in the order it entered them. Among equal
+// priorities the one entered last wins.
+#[derive(Clone, Default)]
+pub struct MediumStack {
+    entries: Vec<Interior>,
+}
+
+impl MediumStack {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    // The entries from the winning one down.
+    fn ranked(&self) -> Vec<&Interior> {
+        let mut ranked: Vec<&Interior> = self.entries.iter().rev().collect();
+        ranked.sort_by_key(|e| Reverse(e.priority));
+        ranked
+    }
+
+    pub fn top(&self) -> Option<&Interior> {
+        self.ranked().first().copied()
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.entries.iter().any(|e| e.id == id)
+    }
+
+    pub fn ior(&self, wavelength: Option<f64>) -> f64 {
+        self.ranked()
+            .iter()
+            .find_map(|e| e.ior)
+            .map_or(1.0, |ior| ior.at(wavelength))
+    }
+
+    pub fn medium(&self) -> Option<&Arc<dyn Medium + Sync + Send>> {
+        self.top().and_then(|e| e.medium.as_ref())
+    }
+
+    pub fn push(&mut self, interior: &Interior) {
+        self.entries.push(interior.clone());
+    }
+
+    pub fn remove(&mut self, id: usize) {
+        if let Some(i) = self.entries.iter().rposition(|e| e.id == id) {
+            self.entries.remove(i);
+        }
+    }
+
+    // The stacks on the near and far side of the surface of `interior`, for
+    // a ray entering or leaving it. A ray leaving an object it never entered,
+    // such as one starting inside it, is taken to have been inside.
+    pub fn crossing(&self, interior: &Interior, entering: bool) -> (MediumStack, MediumStack) {
+        let mut near = self.clone();
+        if !entering && !near.contains(interior.id) {
+            near.push(interior);
+        }
+
+        let mut far = near.clone();
+        if entering {
+            far.push(interior);
+        } else {
+            far.remove(interior.id);
+        }
+
+        (near, far)
+    }
+
+    // Crossing a surface is a real interface only if it changes which object
+    // wins. Otherwise the surface is inside a higher priority object and
+    // light passes it untouched.
+    pub fn is_interface(near: &MediumStack, far: &MediumStack) -> bool {
+        near.top().map(|e| e.id) != far.top().map(|e| e.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interior(id: usize, priority: i32, ior: Option<f64>) -> Interior {
+        Interior {
+            id,
+            priority,
+            ior: ior.map(Ior::Constant),
+            medium: None,
+        }
+    }
+
+    fn top_id(stack: &MediumStack) -> Option<usize> {
+        stack.top().map(|e| e.id)
+    }
+
+    #[test]
+    fn test_priorities() {
+        let glass = interior(1, 0, Some(1.5));
+        let water = interior(2, 1, Some(1.33));
+        let bubble = interior(3, 0, None);
+
+        let mut stack = MediumStack::new();
+        assert_eq!(stack.ior(None), 1.0);
+        stack.push(&glass);
+        stack.push(&water);
+        assert_eq!(top_id(&stack), Some(2));
+
+        // A lower priority object entered later does not win
+        stack.push(&bubble);
+        assert_eq!(top_id(&stack), Some(2));
+        assert_eq!(stack.ior(None), 1.33);
+
+        // Among equal priorities the last entered wins, and one without an
+        // index of refraction takes it from the next one down
+        stack.remove(2);
+        assert_eq!(top_id(&stack), Some(3));
+        assert_eq!(stack.ior(None), 1.5);
+        stack.remove(3);
+        assert_eq!(top_id(&stack), Some(1));
+
+        // Removing what is not there changes nothing
+        stack.remove(3);
+        assert_eq!(top_id(&stack), Some(1));
+        stack.remove(1);
+        assert_eq!(top_id(&stack), None);
+    }
+
+    #[test]
+    fn test_crossing() {
+        let glass = interior(1, 0, Some(1.5));
+        let water = interior(2, 1, Some(1.33));
+
+        // Glass inside water is hidden by it
+        let mut stack = MediumStack::new();
+        stack.push(&water);
+        let (near, far) = stack.crossing(&glass, true);
+        assert!(!MediumStack::is_interface(&near, &far));
+        assert_eq!(top_id(&far), Some(2));
+
+        // Water inside glass is a real interface, both ways
+        let mut stack = MediumStack::new();
+        stack.push(&glass);
+        let (near, far) = stack.crossing(&water, true);
+        assert!(MediumStack::is_interface(&near, &far));
+        assert_eq!((near.ior(None), far.ior(None)), (1.5, 1.33));
+        let (near, far) = far.crossing(&water, false);
+        assert!(MediumStack::is_interface(&near, &far));
+        assert_eq!((near.ior(None), far.ior(None)), (1.33, 1.5));
+
+        // Leaving something never entered counts as having been inside it
+        let (near, far) = MediumStack::new().crossing(&glass, false);
+        assert_eq!(top_id(&near), Some(1));
+        assert_eq!(top_id(&far), None);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{
+    model::{hit::Hittable, ray::Ray, vec3::Vec3},
+    util::rtweekend::{random_double, INFINITY},
+};
+
+use Vec3 as Point3;
+
+// The transmittance below which ratio tracking plays Russian roulette
+const ROULETTE_THRESHOLD: f64 = 0.1;
+
+// A free-flight distance in a medium of constant extinction `sigma`, sampled
+// exactly from the exponential distribution sigma * exp(-sigma * d).
+pub fn sample_exponential(sigma: f64) -> f64 {
+    -(1.0 - random_double()).ln() / sigma
+}
+
+// The spans of r within (t_min, t_max) that lie inside the closed surface
+// `boundary`. A non-convex boundary gives one span per entry and exit.
+pub fn boundary_intervals(
+    boundary: &dyn Hittable,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Vec<(f64, f64)> {
+    let hits = boundary.hit_all(r, t_min, INFINITY);
+
+    // A ray starts inside the boundary if its first crossing is an exit
+    let mut start = if hits.first().is_some_and(|h| !h.front_face) {
+        Some(t_min)
+    } else {
+        None
+    };
+
+    let mut intervals = Vec::new();
+    for rec in hits.iter() {
+        let t = rec.t.min(t_max);
+        if rec.front_face {
+            start = start.or(Some(t));
+        } else if let Some(s) = start.take() {
+            if s < t {
+                intervals.push((s, t));
+            }
+        }
+
+        if rec.t >= t_max {
+            break;
+        }
+    }
+
+    intervals
+}
+
+// Delta tracking: flights are sampled against the majorant, and each tentative
+// collision is a real one with probability density / majorant. Returns the
+// first real collision within the intervals. The density must never exceed
+// the majorant.
+pub fn delta_tracking(
+    r: &Ray,
+    intervals: &[(f64, f64)],
+    majorant: f64,
+    density: impl Fn(&Point3) -> f64,
+) -> Option<f64> {
+    if majorant <= 0.0 {
+        return None;
+    }
+
+    let ray_length = r.dir().length();
+    for &(t0, t1) in intervals {
+        let mut t = t0;
+        loop {
+            t += sample_exponential(majorant) / ray_length;
+            if t >= t1 {
+                break;
+            }
+            if random_double() * majorant < density(&r.at(t)) {
+                return Some(t);
+            }
+        }
+    }
+
+    None
+}
+
+// Ratio tracking: an unbiased estimate of the transmittance through the
+// intervals, weighting by the chance of each tentative collision being a
+// null one instead of stopping at the first real one. Once little light is
+// left, it is only tracked further half of the time, doubled to make up for
+// it.
+pub fn ratio_tracking(
+    r: &Ray,
+    intervals: &[(f64, f64)],
+    majorant: f64,
+    density: impl Fn(&Point3) -> f64,
+) -> f64 {
+    if majorant <= 0.0 {
+        return 1.0;
+    }
+
+    let ray_length = r.dir().length();
+    let mut transmittance = 1.0;
+    for &(t0, t1) in intervals {
+        let mut t = t0;
+        loop {
+            t += sample_exponential(majorant) / ray_length;
+            if t >= t1 {
+                break;
+            }
+            transmittance *= 1.0 - density(&r.at(t)) / majorant;
+            if transmittance < ROULETTE_THRESHOLD {
+                if random_double() < 0.5 {
+                    return 0.0;
+                }
+                transmittance *= 2.0;
+            }
+        }
+    }
+
+    transmittance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mean(n: usize, f: impl Fn() -> f64) -> f64 {
+        (0..n).map(|_| f()).sum::<f64>() / n as f64
+    }
+
+    #[test]
+    fn test_ratio_tracking_constant_density() {
+        // Two spans 1.5 and 2 long in all, along a ray of length 2
+        let r = Ray::new(&Point3::default(), &Vec3::new(0.0, 2.0, 0.0), 0.0);
+        let intervals = [(0.0, 0.25), (1.0, 2.0)];
+        let density: f64 = 0.4;
+        let expected = (-density * 1.25 * 2.0).exp();
+
+        for majorant in [density, 1.0, 3.0] {
+            let estimate = mean(200_000, || {
+                ratio_tracking(&r, &intervals, majorant, |_| density)
+            });
+            assert!(
+                (estimate - expected).abs() < 5e-3,
+                "majorant {}: {} != {}",
+                majorant,
+                estimate,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_ratio_tracking_varying_density() {
+        // Density y along the unit segment, so the optical depth is 1/2
+        let r = Ray::new(&Point3::default(), &Vec3::new(0.0, 1.0, 0.0), 0.0);
+        let estimate = mean(200_000, || {
+            ratio_tracking(&r, &[(0.0, 1.0)], 1.0, |p| p.y())
+        });
+
+        assert!((estimate - (-0.5f64).exp()).abs() < 5e-3, "{}", estimate);
+    }
+
+    #[test]
+    fn test_delta_tracking_escapes_as_often_as_transmitted() {
+        let r = Ray::new(&Point3::default(), &Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let escaped = mean(200_000, || {
+            match delta_tracking(&r, &[(0.0, 2.0)], 1.5, |_| 0.6) {
+                Some(_) => 0.0,
+                None => 1.0,
+            }
+        });
+
+        assert!((escaped - (-1.2f64).exp()).abs() < 5e-3, "{}", escaped);
+    }
+}
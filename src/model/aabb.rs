@@ -32,6 +32,28 @@ impl Aabb {
         return true;
     }
 
+    // Like hit, but returns the parametric interval the ray spends inside.
+    pub fn hit_interval(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let mut tmin = t_min;
+        let mut tmax = t_max;
+
+        for a in 0..3 {
+            let inv_d = 1.0 / r.dir()[a];
+            let mut t0 = (self.minimum[a] - r.origin()[a]) * inv_d;
+            let mut t1 = (self.maximum[a] - r.origin()[a]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            tmin = t0.max(tmin);
+            tmax = t1.min(tmax);
+            if tmax < tmin {
+                return None;
+            }
+        }
+
+        Some((tmin, tmax))
+    }
+
     pub fn surrounding_box(&self, box1: &Aabb) -> Aabb {
         let small = Vec3::new(
             self.minimum.x().min(box1.minimum.x()),
@@ -0,0 +1,225 @@
+use std::sync::Arc;
+
+use crate::util::rtweekend::INFINITY;
+
+use super::{aabb::Aabb, hit::Hittable, quaternion::Quaternion, ray::Ray, vec3::Vec3};
+
+use Vec3 as Point3;
+
+// Number of sub-steps used to sweep the bounding box between keyframes.
+const BOUNDS_STEPS: usize = 32;
+
+#[derive(Clone)]
+pub struct Keyframe {
+    pub time: f64,
+    pub translation: Vec3,
+    pub rotation: Quaternion,
+    pub scale: Vec3,
+}
+
+impl Keyframe {
+    pub fn new(time: f64, translation: Vec3, rotation: Quaternion, scale: Vec3) -> Self {
+        Self {
+            time,
+            translation,
+            rotation: rotation.normalize(),
+            scale,
+        }
+    }
+
+    fn to_world(&self, p: &Point3) -> Point3 {
+        self.rotation.rotate(&(self.scale * p)) + self.translation
+    }
+
+    fn to_object(&self, p: &Point3) -> Point3 {
+        self.rotation.conjugate().rotate(&(p - self.translation)) / self.scale
+    }
+
+    fn vector_to_object(&self, v: &Vec3) -> Vec3 {
+        self.rotation.conjugate().rotate(v) / self.scale
+    }
+
+    // Normals go through the inverse transpose, i.e. R * S^-1.
+    fn normal_to_world(&self, n: &Vec3) -> Vec3 {
+        self.rotation.rotate(&(n / self.scale)).unit_vector()
+    }
+}
+
+pub struct AnimatedTransform {
+    hittable: Arc<dyn Hittable + Sync + Send>,
+    keyframes: Vec<Keyframe>,
+}
+
+impl AnimatedTransform {
+    pub fn new(p: Arc<dyn Hittable + Sync + Send>, mut keyframes: Vec<Keyframe>) -> Self {
+        assert!(
+            !keyframes.is_empty(),
+            "AnimatedTransform needs at least one keyframe"
+        );
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        Self {
+            hittable: p,
+            keyframes,
+        }
+    }
+
+    // Translation and scale are lerped, rotation is slerped. Times outside the
+    // keyframe range hold the first/last pose.
+    pub fn interpolate(&self, time: f64) -> Keyframe {
+        let first = &self.keyframes[0];
+        let last = &self.keyframes[self.keyframes.len() - 1];
+        if time <= first.time {
+            return first.clone();
+        }
+        if time >= last.time {
+            return last.clone();
+        }
+
+        let i = self.keyframes.partition_point(|k| k.time <= time);
+        let k0 = &self.keyframes[i - 1];
+        let k1 = &self.keyframes[i];
+        let t = (time - k0.time) / (k1.time - k0.time);
+
+        Keyframe {
+            time,
+            translation: (1.0 - t) * k0.translation + t * k1.translation,
+            rotation: k0.rotation.slerp(&k1.rotation, t),
+            scale: (1.0 - t) * k0.scale + t * k1.scale,
+        }
+    }
+
+    fn sample_times(&self, time0: f64, time1: f64) -> Vec<f64> {
+        let mut stops = vec![time0];
+        stops.extend(
+            self.keyframes
+                .iter()
+                .map(|k| k.time)
+                .filter(|t| *t > time0 && *t < time1),
+        );
+        stops.push(time1);
+
+        let mut times = vec![time0];
+        for w in stops.windows(2) {
+            for s in 1..=BOUNDS_STEPS {
+                times.push(w[0] + (w[1] - w[0]) * s as f64 / BOUNDS_STEPS as f64);
+            }
+        }
+
+        times
+    }
+}
+
+impl Hittable for AnimatedTransform {
+    fn hit(
+        &self,
+        r: &super::ray::Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut super::hit::HitRecord,
+    ) -> bool {
+        let pose = self.interpolate(r.time());
+        let local_r = Ray::new(
+            &pose.to_object(r.origin()),
+            &pose.vector_to_object(r.dir()),
+            r.time(),
+        );
+
+        if !self.hittable.hit(&local_r, t_min, t_max, rec) {
+            return false;
+        }
+
+        // The transform is affine, so t is unchanged and the normal keeps facing
+        // the ray; front_face stays valid.
+        rec.p = pose.to_world(&rec.p);
+        rec.normal = pose.normal_to_world(&rec.normal);
+
+        true
+    }
+
+    // Sweeps the eight corners of the child's box through the motion. Each corner
+    // moves along a smooth curve, so padding by the largest step between samples
+    // keeps the box conservative between them.
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        let mut bbox = Aabb::default();
+        if !self.hittable.bounding_box(time0, time1, &mut bbox) {
+            return false;
+        }
+
+        let mut corners = Vec::with_capacity(8);
+        for i in 0..2 {
+            for j in 0..2 {
+                for k in 0..2 {
+                    let x = i as f64 * bbox.maximum.x() + (1.0 - i as f64) * bbox.minimum.x();
+                    let y = j as f64 * bbox.maximum.y() + (1.0 - j as f64) * bbox.minimum.y();
+                    let z = k as f64 * bbox.maximum.z() + (1.0 - k as f64) * bbox.minimum.z();
+                    corners.push(Point3::new(x, y, z));
+                }
+            }
+        }
+
+        let mut min = Point3::new(INFINITY, INFINITY, INFINITY);
+        let mut max = Point3::new(-INFINITY, -INFINITY, -INFINITY);
+        let mut previous: Option<Vec<Point3>> = None;
+        let mut max_step: f64 = 0.0;
+
+        for time in self.sample_times(time0, time1) {
+            let pose = self.interpolate(time);
+            let moved: Vec<Point3> = corners.iter().map(|c| pose.to_world(c)).collect();
+
+            for p in moved.iter() {
+                for c in 0..3 {
+                    min[c] = min[c].min(p[c]);
+                    max[c] = max[c].max(p[c]);
+                }
+            }
+
+            if let Some(prev) = previous {
+                for (a, b) in prev.iter().zip(moved.iter()) {
+                    max_step = max_step.max((b - a).length());
+                }
+            }
+            previous = Some(moved);
+        }
+
+        let pad = Vec3::new(max_step, max_step, max_step);
+        *output_box = Aabb::new(min - pad, max + pad);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{material::lambertian::Lambertian, model::sphere::Sphere};
+
+    #[test]
+    fn test_swept_bounding_box() {
+        // A sphere off the y axis, swung half way around it and moved up
+        let sphere = Arc::new(Sphere::new(
+            Point3::new(2.0, 0.0, 0.0),
+            0.5,
+            Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5))),
+        ));
+        let y = Vec3::new(0.0, 1.0, 0.0);
+        let one = Vec3::new(1.0, 1.0, 1.0);
+        let animated = AnimatedTransform::new(
+            sphere,
+            vec![
+                Keyframe::new(0.0, Vec3::default(), Quaternion::identity(), one),
+                Keyframe::new(1.0, y, Quaternion::from_axis_angle(&y, 180.0), one),
+            ],
+        );
+
+        let mut bbox = Aabb::default();
+        assert!(animated.bounding_box(0.0, 1.0, &mut bbox));
+        for i in 0..=100 {
+            let pose = animated.interpolate(i as f64 / 100.0);
+            let center = pose.to_world(&Point3::new(2.0, 0.0, 0.0));
+            for c in 0..3 {
+                assert!(center[c] - 0.5 >= bbox.minimum[c] - 1e-9);
+                assert!(center[c] + 0.5 <= bbox.maximum[c] + 1e-9);
+            }
+        }
+    }
+}
@@ -7,10 +7,8 @@ use crate::material::material::Material;
 use super::{
     aabb::Aabb,
     hit::{Hittable, HittableList},
+    quad::Quad,
     vec3::Vec3,
-    xy_rect::XyRect,
-    xz_rect::XzRect,
-    yz_rect::YzRect,
 };
 
 pub struct Box {
@@ -21,60 +19,52 @@ pub struct Box {
 
 impl Box {
     pub fn new(p0: &Point3, p1: &Point3, mat: Arc<dyn Material + Sync + Send>) -> Self {
-        let box_min = p0.clone();
-        let box_max = p1.clone();
+        let box_min = Point3::new(p0.x().min(p1.x()), p0.y().min(p1.y()), p0.z().min(p1.z()));
+        let box_max = Point3::new(p0.x().max(p1.x()), p0.y().max(p1.y()), p0.z().max(p1.z()));
 
+        let dx = Vec3::new(box_max.x() - box_min.x(), 0.0, 0.0);
+        let dy = Vec3::new(0.0, box_max.y() - box_min.y(), 0.0);
+        let dz = Vec3::new(0.0, 0.0, box_max.z() - box_min.z());
+
+        // Each side's edges are ordered so that u x v points out of the box
         let mut sides = HittableList::new();
-        sides.add(Arc::new(XyRect::new(
-            p0.x(),
-            p1.x(),
-            p0.y(),
-            p1.y(),
-            p1.z(),
-            mat.clone(),
-        )));
-        sides.add(Arc::new(XyRect::new(
-            p0.x(),
-            p1.x(),
-            p0.y(),
-            p1.y(),
-            p0.z(),
+        let (min, max) = (box_min, box_max);
+        sides.add(Arc::new(Quad::new(
+            &Point3::new(min.x(), min.y(), max.z()),
+            &dx,
+            &dy,
             mat.clone(),
-        )));
-
-        sides.add(Arc::new(XzRect::new(
-            p0.x(),
-            p1.x(),
-            p0.z(),
-            p1.z(),
-            p1.y(),
+        ))); // front
+        sides.add(Arc::new(Quad::new(
+            &Point3::new(max.x(), min.y(), max.z()),
+            &-dz,
+            &dy,
             mat.clone(),
-        )));
-        sides.add(Arc::new(XzRect::new(
-            p0.x(),
-            p1.x(),
-            p0.z(),
-            p1.z(),
-            p0.y(),
+        ))); // right
+        sides.add(Arc::new(Quad::new(
+            &Point3::new(max.x(), min.y(), min.z()),
+            &-dx,
+            &dy,
             mat.clone(),
-        )));
-
-        sides.add(Arc::new(YzRect::new(
-            p0.y(),
-            p1.y(),
-            p0.z(),
-            p1.z(),
-            p1.x(),
+        ))); // back
+        sides.add(Arc::new(Quad::new(
+            &Point3::new(min.x(), min.y(), min.z()),
+            &dz,
+            &dy,
             mat.clone(),
-        )));
-        sides.add(Arc::new(YzRect::new(
-            p0.y(),
-            p1.y(),
-            p0.z(),
-            p1.z(),
-            p0.x(),
+        ))); // left
+        sides.add(Arc::new(Quad::new(
+            &Point3::new(min.x(), max.y(), max.z()),
+            &dx,
+            &-dz,
             mat.clone(),
-        )));
+        ))); // top
+        sides.add(Arc::new(Quad::new(
+            &Point3::new(min.x(), min.y(), min.z()),
+            &dx,
+            &dz,
+            mat,
+        ))); // bottom
 
         Self {
             box_min,
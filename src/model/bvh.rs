@@ -55,7 +55,7 @@ impl BvhNode {
                 right = objects[start].clone();
             }
         } else {
-            objects.sort_by(|a, b| comparator(a, b));
+            objects[start..end].sort_by(|a, b| comparator(a, b));
 
             let mid = start + object_span / 2;
             left = Arc::new(BvhNode::new(&objects, start, mid, time0, time1));
@@ -89,16 +89,51 @@ impl Hittable for BvhNode {
         t_max: f64,
         rec: &mut super::hit::HitRecord,
     ) -> bool {
-        if self.bounding_box.hit(r, t_min, t_max) {
+        if !self.bounding_box.hit(r, t_min, t_max) {
             return false;
         }
 
         let hit_left = self.left.hit(r, t_min, t_max, rec);
-        let hit_right = self.right.hit(r, t_min, t_max, rec);
+        let hit_right = self
+            .right
+            .hit(r, t_min, if hit_left { rec.t } else { t_max }, rec);
 
         hit_left || hit_right
     }
 
+    fn hit_surface(
+        &self,
+        r: &super::ray::Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut super::hit::HitRecord,
+    ) -> bool {
+        if !self.bounding_box.hit(r, t_min, t_max) {
+            return false;
+        }
+
+        let hit_left = self.left.hit_surface(r, t_min, t_max, rec);
+        let hit_right = self
+            .right
+            .hit_surface(r, t_min, if hit_left { rec.t } else { t_max }, rec);
+
+        hit_left || hit_right
+    }
+
+    fn transmittance(&self, r: &super::ray::Ray, t_min: f64, t_max: f64) -> f64 {
+        if !self.bounding_box.hit(r, t_min, t_max) {
+            return 1.0;
+        }
+
+        // A single object sits on both sides
+        let left = self.left.transmittance(r, t_min, t_max);
+        if Arc::ptr_eq(&self.left, &self.right) {
+            return left;
+        }
+
+        left * self.right.transmittance(r, t_min, t_max)
+    }
+
     fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
         *output_box = self.bounding_box.clone();
         return true;
@@ -147,3 +182,59 @@ fn box_z_compare(
 ) -> Ordering {
     box_compare(a, b, 2)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        material::lambertian::Lambertian,
+        model::{hit::HitRecord, ray::Ray, sphere::Sphere},
+    };
+
+    use super::*;
+
+    use Vec3 as Point3;
+
+    #[test]
+    fn test_closest_hit_in_either_child() {
+        // A row of overlapping spheres along z, nudged apart on the other axes
+        // so that every split axis orders them differently
+        let material = Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5)));
+        let spheres: Vec<Arc<dyn Hittable + Sync + Send>> = (0..5)
+            .map(|i| {
+                let center = Point3::new(0.1 * (i % 2) as f64, -0.1 * i as f64, -(i as f64));
+                Arc::new(Sphere::new(center, 1.0, material.clone())) as _
+            })
+            .collect();
+
+        // From either end the sphere there hides all the others. Building
+        // from every rotation of the list, on random axes, puts it in the
+        // left child of some nodes and the right child of others.
+        for start in 0..spheres.len() {
+            let mut list = HittableList::new();
+            for i in 0..spheres.len() {
+                list.add(spheres[(start + i) % spheres.len()].clone());
+            }
+
+            for _ in 0..10 {
+                let bvh = BvhNode::new_with_list(&list, 0.0, 1.0);
+                for (origin, dir, first) in [
+                    (Point3::new(0.0, 0.0, 10.0), Vec3::new(0.0, 0.0, -1.0), 0),
+                    (Point3::new(0.0, -0.4, -10.0), Vec3::new(0.0, 0.0, 1.0), 4),
+                ] {
+                    let r = Ray::new(&origin, &dir, 0.0);
+                    let mut expected = HitRecord::default();
+                    assert!(spheres[first].hit(&r, 0.001, f64::INFINITY, &mut expected));
+
+                    let mut rec = HitRecord::default();
+                    assert!(bvh.hit(&r, 0.001, f64::INFINITY, &mut rec));
+                    assert!(
+                        (rec.t - expected.t).abs() < 1e-9,
+                        "{} != {}",
+                        rec.t,
+                        expected.t
+                    );
+                }
+            }
+        }
+    }
+}
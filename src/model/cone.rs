@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use crate::{material::material::Material, util::rtweekend::degrees_to_radians};
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    quadric::{nearest_hit, phi},
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+// A cone with its base of the given radius at z = 0 and its apex at
+// z = height. Clipping z_min and z_max below the apex gives a frustum.
+pub struct Cone {
+    pub height: f64,
+    pub radius: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    pub phi_max: f64,
+    pub material: Arc<dyn Material + Sync + Send>,
+}
+
+impl Cone {
+    pub fn new(
+        height: f64,
+        radius: f64,
+        phi_max: f64,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        Cone::new_clipped(height, radius, 0.0, height, phi_max, mat)
+    }
+
+    pub fn new_clipped(
+        height: f64,
+        radius: f64,
+        z_min: f64,
+        z_max: f64,
+        phi_max: f64,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        let (z_min, z_max) = (
+            z_min.min(z_max).clamp(0.0, height),
+            z_min.max(z_max).clamp(0.0, height),
+        );
+        assert!(height > 0.0 && radius > 0.0, "Cone must have a size");
+        assert!(z_min < z_max, "Cone must not be clipped to nothing");
+
+        Self {
+            height,
+            radius,
+            z_min,
+            z_max,
+            phi_max: degrees_to_radians(phi_max.clamp(0.0, 360.0)),
+            material: mat,
+        }
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        // x^2 + y^2 = k * (z - height)^2
+        let k = (self.radius / self.height) * (self.radius / self.height);
+        let (o, d) = (r.origin(), r.dir());
+        let oz = o.z() - self.height;
+        let a = d.x() * d.x() + d.y() * d.y() - k * d.z() * d.z();
+        let b = 2.0 * (d.x() * o.x() + d.y() * o.y() - k * d.z() * oz);
+        let c = o.x() * o.x() + o.y() * o.y() - k * oz * oz;
+
+        let hit = nearest_hit(r, (a, b, c), t_min, t_max, |p| {
+            let phi = phi(p);
+            if p.z() < self.z_min || p.z() > self.z_max || phi > self.phi_max {
+                return None;
+            }
+            Some((
+                phi / self.phi_max,
+                (p.z() - self.z_min) / (self.z_max - self.z_min),
+            ))
+        });
+        let (t, p, (u, v)) = match hit {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = u;
+        rec.v = v;
+        let outward_normal = Vec3::new(p.x(), p.y(), k * (self.height - p.z())).unit_vector();
+        rec.set_face_normal(r, &outward_normal);
+        rec.material = self.material.clone();
+
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        // The cone is widest at z_min
+        let radius = self.radius * (1.0 - self.z_min / self.height);
+        *output_box = Aabb::new(
+            Point3::new(-radius, -radius, self.z_min),
+            Point3::new(radius, radius, self.z_max),
+        );
+        true
+    }
+}
@@ -1,20 +1,28 @@
 use std::sync::Arc;
 
 use crate::{
-    material::{isotropic::Isotropic, material::Material},
+    material::{isotropic::Isotropic, material::Material, phase_material::PhaseMaterial},
+    media::{
+        phase::PhaseFunction,
+        tracking::{boundary_intervals, delta_tracking},
+    },
     texture::texture::Texture,
-    util::rtweekend::{random_double, INFINITY},
 };
 
 use super::{
+    aabb::Aabb,
     hit::{HitRecord, Hittable},
+    ray::Ray,
     vec3::Vec3,
 };
 
+// A medium of constant density filling a closed boundary, which may be
+// non-convex. Free flights are sampled exactly from the exponential
+// distribution over each span of the ray inside the boundary.
 pub struct ConstantMedium {
     pub boundary: Arc<dyn Hittable + Sync + Send>,
     pub phase_function: Arc<dyn Material + Sync + Send>,
-    pub neg_inv_density: f64,
+    pub density: f64,
 }
 
 impl ConstantMedium {
@@ -25,7 +33,7 @@ impl ConstantMedium {
     ) -> ConstantMedium {
         ConstantMedium {
             boundary: b,
-            neg_inv_density: -1.0 / d,
+            density: d,
             phase_function: Arc::new(Isotropic::new(a)),
         }
     }
@@ -33,79 +41,60 @@ impl ConstantMedium {
     pub fn new(b: Arc<dyn Hittable + Sync + Send>, d: f64, c: Vec3) -> Self {
         Self {
             boundary: b,
-            neg_inv_density: -1.0 / d,
+            density: d,
             phase_function: Arc::new(Isotropic::new_with_color(c)),
         }
     }
-}
-
-impl Hittable for ConstantMedium {
-    fn hit(
-        &self,
-        r: &super::ray::Ray,
-        t_min: f64,
-        t_max: f64,
-        rec: &mut super::hit::HitRecord,
-    ) -> bool {
-        let enable_debugging = false;
-        let debugging = enable_debugging && random_double() < 0.00001;
-
-        let mut rec1 = HitRecord::default();
-        let mut rec2: HitRecord = HitRecord::default();
-
-        if !self.boundary.hit(&r, -INFINITY, INFINITY, &mut rec1) {
-            return false;
-        }
-
-        if !self.boundary.hit(&r, rec1.t + 0.0001, INFINITY, &mut rec2) {
-            return false;
-        }
 
-        if debugging {
-            eprintln!("\nt_min={}, t_max={}", rec1.t, rec2.t);
-        }
-
-        if rec1.t < t_min {
-            rec1.t = t_min;
-        }
-
-        if rec2.t > t_max {
-            rec2.t = t_max;
+    // Scatters following `phase` instead of equally in all directions.
+    pub fn new_with_phase(
+        b: Arc<dyn Hittable + Sync + Send>,
+        d: f64,
+        phase: Arc<dyn PhaseFunction + Sync + Send>,
+        a: Arc<dyn Texture + Sync + Send>,
+    ) -> Self {
+        Self {
+            boundary: b,
+            density: d,
+            phase_function: Arc::new(PhaseMaterial::new(phase, a)),
         }
+    }
+}
 
-        if rec1.t >= rec2.t {
-            return false;
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let intervals = boundary_intervals(&*self.boundary, r, t_min, t_max);
+
+        // With the density equal to the majorant every tentative collision is
+        // real, so this is plain exponential sampling
+        match delta_tracking(r, &intervals, self.density, |_| self.density) {
+            Some(t) => {
+                rec.t = t;
+                rec.p = r.at(t);
+                rec.normal = Vec3::new(1.0, 0.0, 0.0);
+                rec.front_face = true;
+                rec.material = self.phase_function.clone();
+                true
+            }
+            None => false,
         }
+    }
 
-        if rec1.t < 0.0 {
-            rec1.t = 0.0;
-        }
+    // Light passes through the medium, thinned out by Beer-Lambert's law over
+    // the spans inside the boundary
+    fn hit_surface(&self, _r: &Ray, _t_min: f64, _t_max: f64, _rec: &mut HitRecord) -> bool {
+        false
+    }
 
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
         let ray_length = r.dir().length();
-        let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
-        let hit_distance = self.neg_inv_density * random_double().log10();
-
-        if hit_distance > distance_inside_boundary {
-            return false;
-        }
-
-        rec.t = rec1.t + hit_distance / ray_length;
-        rec.p = r.at(rec.t);
-
-        if debugging {
-            eprintln!("hit_distance = {}", hit_distance);
-            eprintln!("rec.t = {}", rec.t);
-            eprintln!("rec.p = {}", rec.p);
-        }
-
-        rec.normal = Vec3::new(1.0, 0.0, 0.0);
-        rec.front_face = true;
-        rec.material = self.phase_function.clone();
-
-        return true;
+        boundary_intervals(&*self.boundary, r, t_min, t_max)
+            .iter()
+            .map(|(t0, t1)| (-self.density * (t1 - t0) * ray_length).exp())
+            .product()
     }
 
-    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut super::aabb::Aabb) -> bool {
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
         self.boundary.bounding_box(time0, time1, output_box)
     }
 }
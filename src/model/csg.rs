@@ -0,0 +1,276 @@
+use std::sync::Arc;
+
+use crate::util::rtweekend::INFINITY;
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    ray::Ray,
+    vec3::Vec3,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    // a with b cut out of it
+    Difference,
+}
+
+impl CsgOperation {
+    fn inside(&self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_a || in_b,
+            CsgOperation::Intersection => in_a && in_b,
+            CsgOperation::Difference => in_a && !in_b,
+        }
+    }
+}
+
+// Combines two closed solids. Both operands report every crossing along the
+// ray, and the crossings where the combined inside state changes are the
+// surface of the result.
+pub struct Csg {
+    pub a: Arc<dyn Hittable + Sync + Send>,
+    pub b: Arc<dyn Hittable + Sync + Send>,
+    pub operation: CsgOperation,
+    bbox: Option<Aabb>,
+}
+
+impl Csg {
+    pub fn new(
+        operation: CsgOperation,
+        a: Arc<dyn Hittable + Sync + Send>,
+        b: Arc<dyn Hittable + Sync + Send>,
+    ) -> Self {
+        let bbox = Csg::compute_bounding_box(operation, &a, &b);
+        Self {
+            a,
+            b,
+            operation,
+            bbox,
+        }
+    }
+
+    pub fn union(a: Arc<dyn Hittable + Sync + Send>, b: Arc<dyn Hittable + Sync + Send>) -> Self {
+        Csg::new(CsgOperation::Union, a, b)
+    }
+
+    pub fn intersection(
+        a: Arc<dyn Hittable + Sync + Send>,
+        b: Arc<dyn Hittable + Sync + Send>,
+    ) -> Self {
+        Csg::new(CsgOperation::Intersection, a, b)
+    }
+
+    pub fn difference(
+        a: Arc<dyn Hittable + Sync + Send>,
+        b: Arc<dyn Hittable + Sync + Send>,
+    ) -> Self {
+        Csg::new(CsgOperation::Difference, a, b)
+    }
+
+    fn compute_bounding_box(
+        operation: CsgOperation,
+        a: &Arc<dyn Hittable + Sync + Send>,
+        b: &Arc<dyn Hittable + Sync + Send>,
+    ) -> Option<Aabb> {
+        let mut box_a = Aabb::default();
+        let mut box_b = Aabb::default();
+        let has_a = a.bounding_box(0.0, 1.0, &mut box_a);
+        let has_b = b.bounding_box(0.0, 1.0, &mut box_b);
+
+        match operation {
+            CsgOperation::Union if has_a && has_b => Some(box_a.surrounding_box(&box_b)),
+            CsgOperation::Union => None,
+            // The overlap of the two boxes, or whichever one is bounded
+            CsgOperation::Intersection => match (has_a, has_b) {
+                (true, true) => {
+                    let mut min = Vec3::default();
+                    let mut max = Vec3::default();
+                    for c in 0..3 {
+                        min[c] = box_a.minimum[c].max(box_b.minimum[c]);
+                        max[c] = box_a.maximum[c].min(box_b.maximum[c]).max(min[c]);
+                    }
+                    Some(Aabb::new(min, max))
+                }
+                (true, false) => Some(box_a),
+                (false, true) => Some(box_b),
+                (false, false) => None,
+            },
+            CsgOperation::Difference if has_a => Some(box_a),
+            CsgOperation::Difference => None,
+        }
+    }
+
+    // Boundary crossings of the result within (t_min, t_max). The operands are
+    // traced to infinity, as a crossing past t_max is what tells us the ray
+    // starts inside them.
+    fn boundary(&self, r: &Ray, t_min: f64, t_max: f64, first_only: bool) -> Vec<HitRecord> {
+        let hits_a = self.a.hit_all(r, t_min, INFINITY);
+        let hits_b = self.b.hit_all(r, t_min, INFINITY);
+
+        // A ray starts inside a solid if its first crossing is an exit
+        let mut in_a = hits_a.first().is_some_and(|h| !h.front_face);
+        let mut in_b = hits_b.first().is_some_and(|h| !h.front_face);
+        let mut inside = self.operation.inside(in_a, in_b);
+
+        let mut events: Vec<(bool, &HitRecord)> = hits_a
+            .iter()
+            .map(|h| (true, h))
+            .chain(hits_b.iter().map(|h| (false, h)))
+            .collect();
+        events.sort_by(|x, y| x.1.t.total_cmp(&y.1.t));
+
+        let mut boundary = Vec::new();
+        for (from_a, rec) in events {
+            if rec.t > t_max {
+                break;
+            }
+            if from_a {
+                in_a = rec.front_face;
+            } else {
+                in_b = rec.front_face;
+            }
+
+            let now_inside = self.operation.inside(in_a, in_b);
+            if now_inside == inside {
+                continue;
+            }
+            inside = now_inside;
+
+            // The normal already faces the ray; whether this crossing enters
+            // the result decides front_face, which flips it on cut surfaces
+            let mut rec = rec.clone();
+            rec.front_face = inside;
+            boundary.push(rec);
+            if first_only {
+                break;
+            }
+        }
+
+        boundary
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        if let Some(bbox) = &self.bbox {
+            if !bbox.hit(r, t_min, t_max) {
+                return false;
+            }
+        }
+
+        match self.boundary(r, t_min, t_max, true).pop() {
+            Some(hit) => {
+                *rec = hit;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        match &self.bbox {
+            Some(bbox) => {
+                *output_box = bbox.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord> {
+        self.boundary(r, t_min, t_max, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{material::lambertian::Lambertian, model::sphere::Sphere};
+
+    use super::*;
+
+    fn sphere(x: f64, radius: f64) -> Arc<dyn Hittable + Sync + Send> {
+        Arc::new(Sphere::new(
+            Vec3::new(x, 0.0, 0.0),
+            radius,
+            Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5))),
+        ))
+    }
+
+    // Where a ray along +x from x0 crosses the solid, and whether it enters
+    fn crossings(solid: &dyn Hittable, x0: f64) -> Vec<(f64, bool)> {
+        let r = Ray::new(&Vec3::new(x0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0), 0.0);
+        solid
+            .hit_all(&r, 0.001, INFINITY)
+            .iter()
+            .map(|h| (h.p.x(), h.front_face))
+            .collect()
+    }
+
+    fn assert_crossings(solid: &dyn Hittable, x0: f64, expected: &[(f64, bool)]) {
+        let actual = crossings(solid, x0);
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for ((x, entering), (ex, e_entering)) in actual.iter().zip(expected) {
+            assert!((x - ex).abs() < 1e-9, "{:?}", actual);
+            assert_eq!(entering, e_entering, "{:?}", actual);
+        }
+    }
+
+    // Two unit spheres overlapping over [-0.5, 0.5]
+    fn left() -> Arc<dyn Hittable + Sync + Send> {
+        sphere(-0.5, 1.0)
+    }
+
+    fn right() -> Arc<dyn Hittable + Sync + Send> {
+        sphere(0.5, 1.0)
+    }
+
+    #[test]
+    fn test_union() {
+        let union = Csg::union(left(), right());
+        assert_crossings(&union, -5.0, &[(-1.5, true), (1.5, false)]);
+        assert_crossings(&union, 0.0, &[(1.5, false)]);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let intersection = Csg::intersection(left(), right());
+        assert_crossings(&intersection, -5.0, &[(-0.5, true), (0.5, false)]);
+
+        // Starting in one operand only is still outside the result
+        assert_crossings(&intersection, -1.0, &[(-0.5, true), (0.5, false)]);
+    }
+
+    #[test]
+    fn test_difference() {
+        let difference = Csg::difference(left(), right());
+        assert_crossings(&difference, -5.0, &[(-1.5, true), (-0.5, false)]);
+
+        // The cut surface faces out of the result, along the ray
+        let r = Ray::new(&Vec3::new(-1.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::default();
+        assert!(difference.hit(&r, 0.001, INFINITY, &mut rec));
+        assert!(!rec.front_face);
+        assert!((rec.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_nested() {
+        // A hollow in the middle of the union
+        let hollow = Csg::difference(Arc::new(Csg::union(left(), right())), sphere(0.0, 0.25));
+        assert_crossings(
+            &hollow,
+            -5.0,
+            &[(-1.5, true), (-0.25, false), (0.25, true), (1.5, false)],
+        );
+
+        // hit finds the first crossing within t_max only
+        let r = Ray::new(&Vec3::new(-5.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::default();
+        assert!(hollow.hit(&r, 4.0, 6.0, &mut rec));
+        assert!((rec.p.x() + 0.25).abs() < 1e-9);
+        assert!(!hollow.hit(&r, 5.0, 5.2, &mut rec));
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use crate::{material::material::Material, util::rtweekend::degrees_to_radians};
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    quadric::{nearest_hit, phi},
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+// A cylinder of the given radius around the z axis, between z_min and z_max.
+// Capped cylinders are closed by disks at both ends, so they can hold glass
+// or a medium.
+pub struct Cylinder {
+    pub radius: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    pub phi_max: f64,
+    pub capped: bool,
+    pub material: Arc<dyn Material + Sync + Send>,
+}
+
+impl Cylinder {
+    pub fn new(
+        radius: f64,
+        z_min: f64,
+        z_max: f64,
+        phi_max: f64,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        Self {
+            radius,
+            z_min: z_min.min(z_max),
+            z_max: z_min.max(z_max),
+            phi_max: degrees_to_radians(phi_max.clamp(0.0, 360.0)),
+            capped: false,
+            material: mat,
+        }
+    }
+
+    pub fn new_capped(
+        radius: f64,
+        z_min: f64,
+        z_max: f64,
+        phi_max: f64,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        Self {
+            capped: true,
+            ..Cylinder::new(radius, z_min, z_max, phi_max, mat)
+        }
+    }
+
+    fn hit_cap(&self, r: &Ray, z: f64, t_min: f64, t_max: f64) -> Option<(f64, Point3)> {
+        if r.dir().z() == 0.0 {
+            return None;
+        }
+
+        let t = (z - r.origin().z()) / r.dir().z();
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let p = r.at(t);
+        if p.x() * p.x() + p.y() * p.y() > self.radius * self.radius || phi(&p) > self.phi_max {
+            return None;
+        }
+
+        Some((t, p))
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let (o, d) = (r.origin(), r.dir());
+        let a = d.x() * d.x() + d.y() * d.y();
+        let b = 2.0 * (d.x() * o.x() + d.y() * o.y());
+        let c = o.x() * o.x() + o.y() * o.y() - self.radius * self.radius;
+
+        let mut hit = nearest_hit(r, (a, b, c), t_min, t_max, |p| {
+            let phi = phi(p);
+            if p.z() < self.z_min || p.z() > self.z_max || phi > self.phi_max {
+                return None;
+            }
+            Some((
+                phi / self.phi_max,
+                (p.z() - self.z_min) / (self.z_max - self.z_min),
+            ))
+        })
+        .map(|(t, p, uv)| (t, p, uv, Vec3::new(p.x(), p.y(), 0.0) / self.radius));
+
+        if self.capped {
+            // On the caps v runs from the axis out to the rim
+            for (z, outward_normal) in [
+                (self.z_min, Vec3::new(0.0, 0.0, -1.0)),
+                (self.z_max, Vec3::new(0.0, 0.0, 1.0)),
+            ] {
+                let closest = hit.as_ref().map_or(t_max, |h| h.0);
+                if let Some((t, p)) = self.hit_cap(r, z, t_min, closest) {
+                    let uv = (
+                        phi(&p) / self.phi_max,
+                        (p.x() * p.x() + p.y() * p.y()).sqrt() / self.radius,
+                    );
+                    hit = Some((t, p, uv, outward_normal));
+                }
+            }
+        }
+
+        let (t, p, (u, v), outward_normal) = match hit {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = u;
+        rec.v = v;
+        rec.set_face_normal(r, &outward_normal);
+        rec.material = self.material.clone();
+
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = Aabb::new(
+            Point3::new(-self.radius, -self.radius, self.z_min),
+            Point3::new(self.radius, self.radius, self.z_max),
+        );
+        true
+    }
+}
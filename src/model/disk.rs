@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use crate::{material::material::Material, util::rtweekend::degrees_to_radians};
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    quadric::phi,
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+// A disk (or annulus when inner_radius > 0) in the plane z = height, facing +z.
+pub struct Disk {
+    pub height: f64,
+    pub radius: f64,
+    pub inner_radius: f64,
+    pub phi_max: f64,
+    pub material: Arc<dyn Material + Sync + Send>,
+}
+
+impl Disk {
+    pub fn new(
+        height: f64,
+        radius: f64,
+        inner_radius: f64,
+        phi_max: f64,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        Self {
+            height,
+            radius,
+            inner_radius,
+            phi_max: degrees_to_radians(phi_max.clamp(0.0, 360.0)),
+            material: mat,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        if r.dir().z() == 0.0 {
+            return false;
+        }
+
+        let t = (self.height - r.origin().z()) / r.dir().z();
+        if t < t_min || t > t_max {
+            return false;
+        }
+
+        let p = r.at(t);
+        let dist2 = p.x() * p.x() + p.y() * p.y();
+        if dist2 > self.radius * self.radius || dist2 < self.inner_radius * self.inner_radius {
+            return false;
+        }
+
+        let phi = phi(&p);
+        if phi > self.phi_max {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = phi / self.phi_max;
+        rec.v = (self.radius - dist2.sqrt()) / (self.radius - self.inner_radius);
+        rec.set_face_normal(r, &Vec3::new(0.0, 0.0, 1.0));
+        rec.material = self.material.clone();
+
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        // The bounding box must have non-zero width in each dimension, so pad the Z dimension a small amount
+        *output_box = Aabb::new(
+            Point3::new(-self.radius, -self.radius, self.height - 0.0001),
+            Point3::new(self.radius, self.radius, self.height + 0.0001),
+        );
+        true
+    }
+}
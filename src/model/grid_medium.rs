@@ -0,0 +1,168 @@
+use std::sync::Arc;
+
+use crate::{
+    material::{isotropic::Isotropic, material::Material},
+    media::tracking::{delta_tracking, ratio_tracking},
+    texture::perlin::Perlin,
+};
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+// A dense nx by ny by nz grid of densities, stored x fastest, then y, then z.
+// Each value sits at the center of its voxel.
+pub struct DensityGrid {
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+    pub values: Vec<f64>,
+}
+
+impl DensityGrid {
+    pub fn new(nx: usize, ny: usize, nz: usize, values: Vec<f64>) -> Self {
+        assert!(
+            nx > 0 && ny > 0 && nz > 0,
+            "DensityGrid needs at least one voxel"
+        );
+        assert_eq!(
+            values.len(),
+            nx * ny * nz,
+            "DensityGrid voxel count mismatch"
+        );
+
+        Self { nx, ny, nz, values }
+    }
+
+    // Perlin turbulence over the unit cube, with `scale` noise features per
+    // unit.
+    pub fn new_from_perlin(nx: usize, ny: usize, nz: usize, scale: f64) -> Self {
+        let noise = Perlin::new();
+        let mut values = Vec::with_capacity(nx * ny * nz);
+        for k in 0..nz {
+            for j in 0..ny {
+                for i in 0..nx {
+                    let p = Point3::new(
+                        (i as f64 + 0.5) / nx as f64,
+                        (j as f64 + 0.5) / ny as f64,
+                        (k as f64 + 0.5) / nz as f64,
+                    );
+                    values.push(noise.turb(&(scale * p), 7));
+                }
+            }
+        }
+
+        DensityGrid::new(nx, ny, nz, values)
+    }
+
+    pub fn max(&self) -> f64 {
+        self.values.iter().cloned().fold(0.0, f64::max)
+    }
+
+    fn value(&self, i: usize, j: usize, k: usize) -> f64 {
+        self.values[(k * self.ny + j) * self.nx + i]
+    }
+
+    // Trilinear interpolation at a point of the unit cube, clamping to the
+    // outermost voxel centers.
+    pub fn sample(&self, p: &Point3) -> f64 {
+        let axis = |x: f64, n: usize| {
+            let s = (x * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            let i = (s.floor() as usize).min(n.saturating_sub(2));
+            (i, (i + 1).min(n - 1), s - i as f64)
+        };
+        let (i0, i1, fx) = axis(p.x(), self.nx);
+        let (j0, j1, fy) = axis(p.y(), self.ny);
+        let (k0, k1, fz) = axis(p.z(), self.nz);
+
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let plane = |k: usize| {
+            lerp(
+                lerp(self.value(i0, j0, k), self.value(i1, j0, k), fx),
+                lerp(self.value(i0, j1, k), self.value(i1, j1, k), fx),
+                fy,
+            )
+        };
+
+        lerp(plane(k0), plane(k1), fz)
+    }
+}
+
+// A heterogeneous medium filling the box `bbox` with a density grid. Free
+// flights are sampled by delta tracking against the grid's largest density,
+// which stays unbiased however the density varies.
+pub struct GridMedium {
+    pub bbox: Aabb,
+    pub grid: DensityGrid,
+    pub density_scale: f64,
+    pub max_density: f64,
+    pub phase_function: Arc<dyn Material + Sync + Send>,
+}
+
+impl GridMedium {
+    pub fn new(bbox: Aabb, grid: DensityGrid, density_scale: f64, c: Vec3) -> Self {
+        let max_density = grid.max() * density_scale;
+
+        Self {
+            bbox,
+            grid,
+            density_scale,
+            max_density,
+            phase_function: Arc::new(Isotropic::new_with_color(c)),
+        }
+    }
+
+    pub fn density(&self, p: &Point3) -> f64 {
+        let min = &self.bbox.minimum;
+        let extent = self.bbox.maximum - self.bbox.minimum;
+        let local = Point3::new(
+            (p.x() - min.x()) / extent.x(),
+            (p.y() - min.y()) / extent.y(),
+            (p.z() - min.z()) / extent.z(),
+        );
+
+        self.grid.sample(&local) * self.density_scale
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let interval = match self.bbox.hit_interval(r, t_min, t_max) {
+            Some(interval) => interval,
+            None => return false,
+        };
+
+        match delta_tracking(r, &[interval], self.max_density, |p| self.density(p)) {
+            Some(t) => {
+                rec.t = t;
+                rec.p = r.at(t);
+                rec.normal = Vec3::new(1.0, 0.0, 0.0);
+                rec.front_face = true;
+                rec.material = self.phase_function.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn hit_surface(&self, _r: &Ray, _t_min: f64, _t_max: f64, _rec: &mut HitRecord) -> bool {
+        false
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
+        match self.bbox.hit_interval(r, t_min, t_max) {
+            Some(interval) => ratio_tracking(r, &[interval], self.max_density, |p| self.density(p)),
+            None => 1.0,
+        }
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = self.bbox.clone();
+        true
+    }
+}
@@ -0,0 +1,329 @@
+use std::{fs, sync::Arc};
+
+use crate::{
+    loader::error::LoadError,
+    material::material::Material,
+    texture::{image::ImageTexture, perlin::Perlin},
+    util::rtweekend::INFINITY,
+};
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    ray::Ray,
+    triangle::{intersect_triangle, set_triangle_hit_record},
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+// A terrain over the rectangle [0, size.x] x [0, size.z] of the y = 0 plane,
+// from an nx by nz grid of heights in [0, 1] scaled by size.y. Each grid cell
+// is split into two triangles, and rays walk the cells they cross in order
+// instead of testing every triangle.
+pub struct Heightfield {
+    nx: usize,
+    nz: usize,
+    size: Vec3,
+    heights: Vec<f64>,
+    normals: Vec<Vec3>,
+    bbox: Aabb,
+    pub material: Arc<dyn Material + Sync + Send>,
+}
+
+impl Heightfield {
+    // heights[j * nx + i] is the height at x = i / (nx - 1) * size.x and
+    // z = j / (nz - 1) * size.z.
+    pub fn new(
+        heights: Vec<f64>,
+        nx: usize,
+        nz: usize,
+        size: &Vec3,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        assert!(nx >= 2 && nz >= 2, "Heightfield needs at least 2x2 samples");
+        assert_eq!(heights.len(), nx * nz, "Heightfield sample count mismatch");
+
+        let heights: Vec<f64> = heights.iter().map(|h| h * size.y()).collect();
+        let min = heights.iter().cloned().fold(INFINITY, f64::min);
+        let max = heights.iter().cloned().fold(-INFINITY, f64::max);
+
+        let mut field = Self {
+            nx,
+            nz,
+            size: *size,
+            heights,
+            normals: Vec::new(),
+            // The bounding box must have non-zero height, so pad the Y dimension a small amount
+            bbox: Aabb::new(
+                Point3::new(0.0, min - 0.0001, 0.0),
+                Point3::new(size.x(), max + 0.0001, size.z()),
+            ),
+            material: mat,
+        };
+        field.normals = field.vertex_normals();
+
+        field
+    }
+
+    // Uses the brightness of an image as the height, with the top row of the
+    // image at z = 0 so that the same image used as a texture lines up.
+    pub fn new_from_image(
+        path: &str,
+        size: &Vec3,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Result<Self, LoadError> {
+        let data = fs::read(path).map_err(|e| LoadError::io(path, e))?;
+        let image = ImageTexture::new_from_memory(&data);
+        let (nx, nz) = (image.width(), image.height());
+        if nx == 0 || nz == 0 {
+            return Err(LoadError::invalid(path, "image could not be decoded"));
+        }
+
+        let mut heights = Vec::with_capacity((nx * nz) as usize);
+        for j in 0..nz {
+            for i in 0..nx {
+                let c = image.pixel(i, j);
+                heights.push(0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z());
+            }
+        }
+
+        Ok(Heightfield::new(
+            heights,
+            nx as usize,
+            nz as usize,
+            size,
+            mat,
+        ))
+    }
+
+    // Turbulence sampled across the grid, with `scale` noise features per unit
+    // of size, normalized so that the highest sample reaches size.y.
+    pub fn new_from_perlin(
+        nx: usize,
+        nz: usize,
+        scale: f64,
+        size: &Vec3,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        let noise = Perlin::new();
+        let mut heights = Vec::with_capacity(nx * nz);
+        for j in 0..nz {
+            for i in 0..nx {
+                let x = i as f64 / (nx - 1) as f64 * size.x();
+                let z = j as f64 / (nz - 1) as f64 * size.z();
+                heights.push(noise.turb(&Point3::new(scale * x, 0.0, scale * z), 7));
+            }
+        }
+
+        let max = heights.iter().cloned().fold(0.0, f64::max);
+        if max > 0.0 {
+            heights.iter_mut().for_each(|h| *h /= max);
+        }
+
+        Heightfield::new(heights, nx, nz, size, mat)
+    }
+
+    fn cell_size(&self) -> (f64, f64) {
+        (
+            self.size.x() / (self.nx - 1) as f64,
+            self.size.z() / (self.nz - 1) as f64,
+        )
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Point3 {
+        let (dx, dz) = self.cell_size();
+        Point3::new(i as f64 * dx, self.heights[j * self.nx + i], j as f64 * dz)
+    }
+
+    fn uv(&self, i: usize, j: usize) -> (f64, f64) {
+        (
+            i as f64 / (self.nx - 1) as f64,
+            1.0 - j as f64 / (self.nz - 1) as f64,
+        )
+    }
+
+    // Central differences of the heights, one-sided along the edges.
+    fn vertex_normals(&self) -> Vec<Vec3> {
+        let (dx, dz) = self.cell_size();
+        let h = |i: usize, j: usize| self.heights[j * self.nx + i];
+
+        let mut normals = Vec::with_capacity(self.nx * self.nz);
+        for j in 0..self.nz {
+            for i in 0..self.nx {
+                let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.nx - 1));
+                let (j0, j1) = (j.saturating_sub(1), (j + 1).min(self.nz - 1));
+                let slope_x = (h(i1, j) - h(i0, j)) / ((i1 - i0) as f64 * dx);
+                let slope_z = (h(i, j1) - h(i, j0)) / ((j1 - j0) as f64 * dz);
+                normals.push(Vec3::new(-slope_x, 1.0, -slope_z).unit_vector());
+            }
+        }
+
+        normals
+    }
+
+    // Tests the two triangles of cell (i, j) and keeps the closer hit.
+    fn hit_cell(
+        &self,
+        r: &Ray,
+        i: usize,
+        j: usize,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+    ) -> bool {
+        let triangles = [
+            [(i, j), (i, j + 1), (i + 1, j)],
+            [(i + 1, j), (i, j + 1), (i + 1, j + 1)],
+        ];
+
+        let mut closest = t_max;
+        let mut hit = false;
+        for corners in triangles.iter() {
+            let vertices = corners.map(|(i, j)| self.vertex(i, j));
+            if let Some((t, b)) =
+                intersect_triangle(r, &vertices[0], &vertices[1], &vertices[2], t_min, closest)
+            {
+                let normals = corners.map(|(i, j)| self.normals[j * self.nx + i]);
+                let uvs = corners.map(|(i, j)| self.uv(i, j));
+                set_triangle_hit_record(r, t, &b, &vertices, Some(&normals), Some(&uvs), rec);
+                closest = t;
+                hit = true;
+            }
+        }
+
+        hit
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let (t_start, t_end) = match self.bbox.hit_interval(r, t_min, t_max) {
+            Some(interval) => interval,
+            None => return false,
+        };
+
+        // Walk the cells under the ray with a 2D DDA over x and z
+        let (dx, dz) = self.cell_size();
+        let (cells_x, cells_z) = (self.nx - 1, self.nz - 1);
+        let start = r.at(t_start);
+        let dir = r.dir();
+
+        let mut i = ((start.x() / dx).floor().max(0.0) as usize).min(cells_x - 1);
+        let mut j = ((start.z() / dz).floor().max(0.0) as usize).min(cells_z - 1);
+
+        let step = |d: f64| if d > 0.0 { 1 } else { -1 };
+        let next_boundary = |index: usize, d: f64, cell: f64, origin: f64| {
+            if d == 0.0 {
+                return INFINITY;
+            }
+            let edge = (index as f64 + if d > 0.0 { 1.0 } else { 0.0 }) * cell;
+            (edge - origin) / d
+        };
+        let (step_x, step_z) = (step(dir.x()), step(dir.z()));
+        let t_delta_x = if dir.x() == 0.0 {
+            INFINITY
+        } else {
+            dx / dir.x().abs()
+        };
+        let t_delta_z = if dir.z() == 0.0 {
+            INFINITY
+        } else {
+            dz / dir.z().abs()
+        };
+        let mut t_next_x = next_boundary(i, dir.x(), dx, r.origin().x());
+        let mut t_next_z = next_boundary(j, dir.z(), dz, r.origin().z());
+        let mut t_cell = t_start;
+
+        loop {
+            // Skip cells whose heights the ray passes entirely above or below
+            let t_exit = t_next_x.min(t_next_z).min(t_end);
+            let (y0, y1) = (r.at(t_cell).y(), r.at(t_exit).y());
+            let corners = [(i, j), (i + 1, j), (i, j + 1), (i + 1, j + 1)];
+            let heights = corners.map(|(i, j)| self.heights[j * self.nx + i]);
+            let low = heights.iter().cloned().fold(INFINITY, f64::min);
+            let high = heights.iter().cloned().fold(-INFINITY, f64::max);
+
+            if y0.min(y1) <= high && y0.max(y1) >= low && self.hit_cell(r, i, j, t_min, t_max, rec)
+            {
+                rec.material = self.material.clone();
+                return true;
+            }
+
+            if t_exit >= t_end {
+                return false;
+            }
+            if t_next_x < t_next_z {
+                if (step_x < 0 && i == 0) || (step_x > 0 && i + 1 == cells_x) {
+                    return false;
+                }
+                i = (i as i64 + step_x) as usize;
+                t_cell = t_next_x;
+                t_next_x += t_delta_x;
+            } else {
+                if (step_z < 0 && j == 0) || (step_z > 0 && j + 1 == cells_z) {
+                    return false;
+                }
+                j = (j as i64 + step_z) as usize;
+                t_cell = t_next_z;
+                t_next_z += t_delta_z;
+            }
+        }
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = self.bbox.clone();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::lambertian::Lambertian;
+
+    use super::*;
+
+    #[test]
+    fn test_image_errors() {
+        let mat = Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5)));
+        let size = Vec3::new(1.0, 1.0, 1.0);
+
+        let missing = std::env::temp_dir().join("heightfield-missing.png");
+        let missing = missing.to_string_lossy();
+        assert!(matches!(
+            Heightfield::new_from_image(&missing, &size, mat.clone()),
+            Err(LoadError::Io { .. })
+        ));
+
+        let broken = std::env::temp_dir().join("heightfield-broken.png");
+        fs::write(&broken, b"not an image").unwrap();
+        assert!(matches!(
+            Heightfield::new_from_image(&broken.to_string_lossy(), &size, mat),
+            Err(LoadError::Invalid { .. })
+        ));
+        fs::remove_file(&broken).unwrap();
+    }
+
+    #[test]
+    fn test_hit() {
+        // A ramp rising along x, hit from straight above
+        let heights = vec![0.0, 1.0, 0.0, 1.0];
+        let mat: Arc<dyn Material + Sync + Send> =
+            Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5)));
+        let field = Heightfield::new(heights, 2, 2, &Vec3::new(1.0, 1.0, 1.0), mat.clone());
+
+        let r = Ray::new(
+            &Point3::new(0.25, 2.0, 0.5),
+            &Vec3::new(0.0, -1.0, 0.0),
+            0.0,
+        );
+        let mut rec = HitRecord::default();
+        assert!(field.hit(&r, 0.001, INFINITY, &mut rec));
+        assert!((rec.p - Point3::new(0.25, 0.25, 0.5)).length() < 1e-9);
+        assert!(rec.front_face);
+        assert!(Arc::ptr_eq(&rec.material, &mat));
+
+        let r = Ray::new(&Point3::new(1.5, 2.0, 0.5), &Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(!field.hit(&r, 0.001, INFINITY, &mut rec));
+    }
+}
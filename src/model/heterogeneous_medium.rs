@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use crate::{
+    material::{isotropic::Isotropic, material::Material},
+    media::tracking::{boundary_intervals, delta_tracking, ratio_tracking},
+    texture::texture::Texture,
+};
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+// A medium filling a closed boundary whose density varies through space. The
+// density is max_density times the brightness of a solid texture, clamped to
+// [0, 1], so max_density bounds it everywhere and serves as the majorant for
+// delta tracking.
+pub struct HeterogeneousMedium {
+    pub boundary: Arc<dyn Hittable + Sync + Send>,
+    pub density: Arc<dyn Texture + Sync + Send>,
+    pub max_density: f64,
+    pub phase_function: Arc<dyn Material + Sync + Send>,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(
+        b: Arc<dyn Hittable + Sync + Send>,
+        max_density: f64,
+        density: Arc<dyn Texture + Sync + Send>,
+        c: Vec3,
+    ) -> Self {
+        Self {
+            boundary: b,
+            density,
+            max_density,
+            phase_function: Arc::new(Isotropic::new_with_color(c)),
+        }
+    }
+
+    // There are no surface coordinates inside a volume, so the texture is
+    // looked up by position alone.
+    pub fn density(&self, p: &Point3) -> f64 {
+        let c = self.density.value(0.0, 0.0, p);
+        let brightness = (c.x() + c.y() + c.z()) / 3.0;
+
+        self.max_density * brightness.clamp(0.0, 1.0)
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let intervals = boundary_intervals(&*self.boundary, r, t_min, t_max);
+
+        match delta_tracking(r, &intervals, self.max_density, |p| self.density(p)) {
+            Some(t) => {
+                rec.t = t;
+                rec.p = r.at(t);
+                rec.normal = Vec3::new(1.0, 0.0, 0.0);
+                rec.front_face = true;
+                rec.material = self.phase_function.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn hit_surface(&self, _r: &Ray, _t_min: f64, _t_max: f64, _rec: &mut HitRecord) -> bool {
+        false
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let intervals = boundary_intervals(&*self.boundary, r, t_min, t_max);
+        ratio_tracking(r, &intervals, self.max_density, |p| self.density(p))
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        self.boundary.bounding_box(time0, time1, output_box)
+    }
+}
@@ -1,10 +1,17 @@
 use std::sync::Arc;
 
-use crate::material::{lambertian::Lambertian, material::Material};
+use crate::{
+    material::{lambertian::Lambertian, material::Material},
+    util::rtweekend::random_int,
+};
 
 use super::{aabb::Aabb, ray::Ray, vec3::Vec3};
 use Vec3 as Point3;
 
+// Guards for the default hit_all against hits that do not move the ray on
+const MAX_HIT_ALL: usize = 64;
+const HIT_ALL_EPSILON: f64 = 1e-7;
+
 #[derive(Clone)]
 pub struct HitRecord {
     pub p: Point3,
@@ -14,6 +21,8 @@ pub struct HitRecord {
     pub u: f64,
     pub v: f64,
     pub front_face: bool,
+    // Interpolated from the vertex colors of a mesh that has them
+    pub color: Option<Vec3>,
 }
 
 impl HitRecord {
@@ -37,6 +46,7 @@ impl Default for HitRecord {
             front_face: Default::default(),
             u: Default::default(),
             v: Default::default(),
+            color: None,
         }
     }
 }
@@ -44,6 +54,44 @@ impl Default for HitRecord {
 pub trait Hittable {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool;
     fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool;
+
+    // Light sampling: the solid angle density of sampling `dir` from `origin`,
+    // and a random direction from `origin` towards the object
+    fn pdf_value(&self, _origin: &Point3, _dir: &Vec3) -> f64 {
+        0.0
+    }
+
+    fn random(&self, _origin: &Point3) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+
+    // Shadow rays: the closest surface along r, passing through participating
+    // media, and the fraction of light those media let through within
+    // (t_min, t_max). Anything else is opaque, found by hit and thinning out
+    // nothing. A medium that keeps these defaults still stops shadow rays
+    // where it would scatter, which is right on average.
+    fn hit_surface(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.hit(r, t_min, t_max, rec)
+    }
+
+    fn transmittance(&self, _r: &Ray, _t_min: f64, _t_max: f64) -> f64 {
+        1.0
+    }
+
+    // Every surface crossing within (t_min, t_max), sorted by t. The default
+    // steps past each closest hit in turn.
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord> {
+        let mut hits = Vec::new();
+        let mut rec = HitRecord::default();
+        let mut t = t_min;
+
+        while hits.len() < MAX_HIT_ALL && self.hit(r, t, t_max, &mut rec) {
+            t = rec.t + HIT_ALL_EPSILON * rec.t.abs().max(1.0);
+            hits.push(rec.clone());
+        }
+
+        hits
+    }
 }
 
 pub struct HittableList {
@@ -83,6 +131,27 @@ impl Hittable for HittableList {
         hit_anything
     }
 
+    fn hit_surface(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let mut hit_anything = false;
+        let mut closest_so_far = t_max;
+
+        for object in self.objects.iter() {
+            if object.hit_surface(r, t_min, closest_so_far, rec) {
+                hit_anything = true;
+                closest_so_far = rec.t;
+            }
+        }
+
+        hit_anything
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.objects
+            .iter()
+            .map(|object| object.transmittance(r, t_min, t_max))
+            .product()
+    }
+
     fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
         if self.objects.is_empty() {
             return false;
@@ -104,4 +173,84 @@ impl Hittable for HittableList {
         }
         return true;
     }
+
+    // Samples the objects with equal probability
+    fn pdf_value(&self, origin: &Point3, dir: &Vec3) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+
+        let weight = 1.0 / self.objects.len() as f64;
+        self.objects
+            .iter()
+            .map(|object| weight * object.pdf_value(origin, dir))
+            .sum()
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        if self.objects.is_empty() {
+            return Vec3::new(1.0, 0.0, 0.0);
+        }
+
+        let i = random_int(0, self.objects.len() as i32);
+        self.objects[i as usize].random(origin)
+    }
+
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord> {
+        let mut hits: Vec<HitRecord> = self
+            .objects
+            .iter()
+            .flat_map(|object| object.hit_all(r, t_min, t_max))
+            .collect();
+        hits.sort_by(|a, b| a.t.total_cmp(&b.t));
+
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{model::sphere::Sphere, util::rtweekend::INFINITY};
+
+    use super::*;
+
+    fn sphere(x: f64) -> Arc<dyn Hittable + Sync + Send> {
+        Arc::new(Sphere::new(
+            Point3::new(x, 0.0, 0.0),
+            1.0,
+            Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5))),
+        ))
+    }
+
+    fn xs(hits: &[HitRecord]) -> Vec<f64> {
+        hits.iter().map(|h| h.p.x()).collect()
+    }
+
+    #[test]
+    fn test_hit_all_steps_through_an_object() {
+        let r = Ray::new(&Point3::new(-5.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let sphere = sphere(0.0);
+
+        let hits = sphere.hit_all(&r, 0.001, INFINITY);
+        assert_eq!(xs(&hits), vec![-1.0, 1.0]);
+        assert!(hits[0].front_face && !hits[1].front_face);
+
+        // Only crossings inside the interval count
+        assert_eq!(xs(&sphere.hit_all(&r, 4.5, INFINITY)), vec![1.0]);
+        assert_eq!(xs(&sphere.hit_all(&r, 0.001, 5.0)), vec![-1.0]);
+        assert!(sphere.hit_all(&r, 4.1, 5.9).is_empty());
+    }
+
+    #[test]
+    fn test_list_hit_all_is_sorted() {
+        let r = Ray::new(&Point3::new(-5.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut list = HittableList::new();
+        list.add(sphere(3.0));
+        list.add(sphere(-0.5));
+
+        assert_eq!(
+            xs(&list.hit_all(&r, 0.001, INFINITY)),
+            vec![-1.5, 0.5, 2.0, 4.0]
+        );
+    }
 }
@@ -0,0 +1,197 @@
+use std::sync::Arc;
+
+use crate::{
+    material::material::Material,
+    util::rtweekend::{degrees_to_radians, PI},
+};
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    quadric::nearest_hit,
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+// How many points further along the segment the fit tries before giving up
+const MAX_FIT_STEPS: usize = 16;
+
+// The surface swept by rotating the segment p1-p2 around the z axis, which is
+// a hyperboloid of one sheet when the segment is skew to the axis.
+pub struct Hyperboloid {
+    pub p1: Point3,
+    pub p2: Point3,
+    pub z_min: f64,
+    pub z_max: f64,
+    pub r_max: f64,
+    pub phi_max: f64,
+    // a * (x^2 + y^2) - c * z^2 = 1
+    a: f64,
+    c: f64,
+    pub material: Arc<dyn Material + Sync + Send>,
+}
+
+impl Hyperboloid {
+    pub fn new(
+        p1: &Point3,
+        p2: &Point3,
+        phi_max: f64,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        assert!(
+            p1.z() != p2.z(),
+            "Hyperboloid segment must not be perpendicular to the z axis"
+        );
+
+        let (mut p1, mut p2) = (*p1, *p2);
+        let radius1 = (p1.x() * p1.x() + p1.y() * p1.y()).sqrt();
+        let radius2 = (p2.x() * p2.x() + p2.y() * p2.y()).sqrt();
+
+        // Fit the implicit coefficients through p2 and a second point further
+        // along the line, stepping again if that point is degenerate
+        if p2.z() == 0.0 {
+            std::mem::swap(&mut p1, &mut p2);
+        }
+        let mut pp = p1;
+        let (mut a, mut c) = (f64::NAN, f64::NAN);
+        for _ in 0..MAX_FIT_STEPS {
+            pp += 2.0 * (p2 - p1);
+            let xy1 = pp.x() * pp.x() + pp.y() * pp.y();
+            let xy2 = p2.x() * p2.x() + p2.y() * p2.y();
+            let z2 = p2.z() * p2.z();
+            a = (1.0 / xy1 - (pp.z() * pp.z()) / (xy1 * z2))
+                / (1.0 - (xy2 * pp.z() * pp.z()) / (xy1 * z2));
+            c = (a * xy2 - 1.0) / z2;
+            if a.is_finite() && c.is_finite() {
+                break;
+            }
+        }
+        assert!(
+            a.is_finite() && c.is_finite(),
+            "Hyperboloid segment does not sweep a hyperboloid"
+        );
+
+        Self {
+            p1,
+            p2,
+            z_min: p1.z().min(p2.z()),
+            z_max: p1.z().max(p2.z()),
+            r_max: radius1.max(radius2),
+            phi_max: degrees_to_radians(phi_max.clamp(0.0, 360.0)),
+            a,
+            c,
+            material: mat,
+        }
+    }
+}
+
+impl Hittable for Hyperboloid {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let (o, d) = (r.origin(), r.dir());
+        let qa = self.a * (d.x() * d.x() + d.y() * d.y()) - self.c * d.z() * d.z();
+        let qb = 2.0 * (self.a * (d.x() * o.x() + d.y() * o.y()) - self.c * d.z() * o.z());
+        let qc = self.a * (o.x() * o.x() + o.y() * o.y()) - self.c * o.z() * o.z() - 1.0;
+
+        let hit = nearest_hit(r, (qa, qb, qc), t_min, t_max, |p| {
+            if p.z() < self.z_min || p.z() > self.z_max {
+                return None;
+            }
+
+            // phi is measured from the generating segment at this height, so
+            // that the sweep starts on it
+            let v = (p.z() - self.p1.z()) / (self.p2.z() - self.p1.z());
+            let pr = (1.0 - v) * self.p1 + v * self.p2;
+            let mut phi = (pr.x() * p.y() - p.x() * pr.y()).atan2(p.x() * pr.x() + p.y() * pr.y());
+            if phi < 0.0 {
+                phi += 2.0 * PI;
+            }
+            if phi > self.phi_max {
+                return None;
+            }
+            Some((phi / self.phi_max, v))
+        });
+        let (t, p, (u, v)) = match hit {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = u;
+        rec.v = v;
+        let outward_normal =
+            Vec3::new(self.a * p.x(), self.a * p.y(), -self.c * p.z()).unit_vector();
+        rec.set_face_normal(r, &outward_normal);
+        rec.material = self.material.clone();
+
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = Aabb::new(
+            Point3::new(-self.r_max, -self.r_max, self.z_min),
+            Point3::new(self.r_max, self.r_max, self.z_max),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::lambertian::Lambertian;
+
+    use super::*;
+
+    fn material() -> Arc<dyn Material + Sync + Send> {
+        Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5)))
+    }
+
+    #[test]
+    fn test_fit() {
+        // The segment narrows to 1/sqrt(2) at its middle: 2 * r^2 - z^2 = 1
+        let h = Hyperboloid::new(
+            &Point3::new(1.0, 0.0, -1.0),
+            &Point3::new(0.0, 1.0, 1.0),
+            360.0,
+            material(),
+        );
+        assert!((h.a - 2.0).abs() < 1e-9 && (h.c - 1.0).abs() < 1e-9);
+
+        let r = Ray::new(&Point3::new(-5.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::default();
+        assert!(h.hit(&r, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.p.x() + 0.5f64.sqrt()).abs() < 1e-9);
+        assert!(rec.front_face);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rejects_a_point() {
+        let p = Point3::new(1.0, 0.0, 1.0);
+        Hyperboloid::new(&p, &p, 360.0, material());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rejects_a_flat_segment() {
+        Hyperboloid::new(
+            &Point3::new(1.0, 0.0, 0.0),
+            &Point3::new(0.0, 2.0, 0.0),
+            360.0,
+            material(),
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rejects_the_axis() {
+        Hyperboloid::new(
+            &Point3::new(0.0, 0.0, -1.0),
+            &Point3::new(0.0, 0.0, 1.0),
+            360.0,
+            material(),
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use crate::material::material::Material;
+
+use super::{aabb::Aabb, hit::Hittable, matrix::Mat4, transform::Transform};
+
+// A placement of shared geometry. The geometry (and any BVH inside it) is only
+// referenced, so each instance costs a transform and an optional material no
+// matter how large the geometry is.
+pub struct Instance {
+    transform: Transform,
+    material: Option<Arc<dyn Material + Sync + Send>>,
+}
+
+impl Instance {
+    pub fn new(geometry: Arc<dyn Hittable + Sync + Send>, m: &Mat4) -> Self {
+        Self {
+            transform: Transform::new_with_matrix(geometry, m),
+            material: None,
+        }
+    }
+
+    pub fn new_with_material(
+        geometry: Arc<dyn Hittable + Sync + Send>,
+        m: &Mat4,
+        material: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        Self {
+            transform: Transform::new_with_matrix(geometry, m),
+            material: Some(material),
+        }
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(
+        &self,
+        r: &super::ray::Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut super::hit::HitRecord,
+    ) -> bool {
+        if !self.transform.hit(r, t_min, t_max, rec) {
+            return false;
+        }
+
+        if let Some(material) = &self.material {
+            rec.material = material.clone();
+        }
+
+        true
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        self.transform.bounding_box(time0, time1, output_box)
+    }
+}
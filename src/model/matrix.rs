@@ -0,0 +1,286 @@
+use std::ops::Mul;
+
+use crate::util::rtweekend::degrees_to_radians;
+
+use super::{quaternion::Quaternion, vec3::Vec3};
+
+use Vec3 as Point3;
+
+// Row-major 4x4 matrix acting on column vectors.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    pub fn identity() -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+
+        Self { m }
+    }
+
+    // Sixteen values in column-major order, as glTF and OpenGL store them.
+    pub fn from_column_major(values: &[f64; 16]) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, v) in values.iter().enumerate() {
+            m[i % 4][i / 4] = *v;
+        }
+
+        Self { m }
+    }
+
+    pub fn translate(offset: &Vec3) -> Self {
+        let mut t = Mat4::identity();
+        t.m[0][3] = offset.x();
+        t.m[1][3] = offset.y();
+        t.m[2][3] = offset.z();
+
+        t
+    }
+
+    pub fn scale(s: &Vec3) -> Self {
+        let mut t = Mat4::identity();
+        t.m[0][0] = s.x();
+        t.m[1][1] = s.y();
+        t.m[2][2] = s.z();
+
+        t
+    }
+
+    pub fn rotate(q: &Quaternion) -> Self {
+        let q = q.normalize();
+        let (x, y, z, w) = (q.x, q.y, q.z, q.w);
+
+        Mat4::new([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Scale first, then rotate, then translate.
+    pub fn from_trs(translation: &Vec3, rotation: &Quaternion, scale: &Vec3) -> Self {
+        Mat4::translate(translation) * Mat4::rotate(rotation) * Mat4::scale(scale)
+    }
+
+    pub fn rotate_x(angle: f64) -> Self {
+        let (sin_theta, cos_theta) = degrees_to_radians(angle).sin_cos();
+        let mut t = Mat4::identity();
+        t.m[1][1] = cos_theta;
+        t.m[1][2] = -sin_theta;
+        t.m[2][1] = sin_theta;
+        t.m[2][2] = cos_theta;
+
+        t
+    }
+
+    pub fn rotate_y(angle: f64) -> Self {
+        let (sin_theta, cos_theta) = degrees_to_radians(angle).sin_cos();
+        let mut t = Mat4::identity();
+        t.m[0][0] = cos_theta;
+        t.m[0][2] = sin_theta;
+        t.m[2][0] = -sin_theta;
+        t.m[2][2] = cos_theta;
+
+        t
+    }
+
+    pub fn rotate_z(angle: f64) -> Self {
+        let (sin_theta, cos_theta) = degrees_to_radians(angle).sin_cos();
+        let mut t = Mat4::identity();
+        t.m[0][0] = cos_theta;
+        t.m[0][1] = -sin_theta;
+        t.m[1][0] = sin_theta;
+        t.m[1][1] = cos_theta;
+
+        t
+    }
+
+    pub fn rotate_axis(axis: &Vec3, angle: f64) -> Self {
+        Mat4::rotate(&Quaternion::from_axis_angle(axis, angle))
+    }
+
+    // Places an object at `from` with its local +z axis pointing at `to` and its
+    // +y axis as close to `up` as possible.
+    pub fn look_at(from: &Point3, to: &Point3, up: &Vec3) -> Self {
+        let w = (to - from).unit_vector();
+        let u = up.cross(&w).unit_vector();
+        let v = w.cross(&u);
+
+        Mat4::new([
+            [u.x(), v.x(), w.x(), from.x()],
+            [u.y(), v.y(), w.y(), from.y()],
+            [u.z(), v.z(), w.z(), from.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut t = [[0.0; 4]; 4];
+        for (i, row) in self.m.iter().enumerate() {
+            for (j, v) in row.iter().enumerate() {
+                t[j][i] = *v;
+            }
+        }
+
+        Mat4::new(t)
+    }
+
+    pub fn transform_point(&self, p: &Point3) -> Point3 {
+        let m = &self.m;
+        let x = m[0][0] * p.x() + m[0][1] * p.y() + m[0][2] * p.z() + m[0][3];
+        let y = m[1][0] * p.x() + m[1][1] * p.y() + m[1][2] * p.z() + m[1][3];
+        let z = m[2][0] * p.x() + m[2][1] * p.y() + m[2][2] * p.z() + m[2][3];
+        let w = m[3][0] * p.x() + m[3][1] * p.y() + m[3][2] * p.z() + m[3][3];
+
+        if w == 1.0 {
+            Point3::new(x, y, z)
+        } else {
+            Point3::new(x / w, y / w, z / w)
+        }
+    }
+
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+
+    // Determinant of the upper 3x3 block; negative when handedness flips.
+    pub fn determinant3(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    // Gauss-Jordan elimination with partial pivoting. None for singular matrices.
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = self.m;
+        let mut inv = Mat4::identity().m;
+
+        for col in 0..4 {
+            let mut pivot = col;
+            for row in col + 1..4 {
+                if a[row][col].abs() > a[pivot][col].abs() {
+                    pivot = row;
+                }
+            }
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+
+        Some(Mat4::new(inv))
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Mat4::identity()
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: Mat4) -> Mat4 {
+        let mut r = [[0.0; 4]; 4];
+        for (i, row) in r.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+
+        Mat4::new(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: &Vec3, b: &Vec3) {
+        assert!((a - b).length() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let m = Mat4::translate(&Vec3::new(1.0, -2.0, 3.0))
+            * Mat4::rotate_axis(&Vec3::new(1.0, 1.0, 0.0), 30.0)
+            * Mat4::scale(&Vec3::new(2.0, 0.5, 3.0));
+        let product = m * m.inverse().unwrap();
+
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((product.m[i][j] - expected).abs() < 1e-12);
+            }
+        }
+        assert!(Mat4::scale(&Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn test_rotations_match_quaternions() {
+        let p = Vec3::new(1.0, 2.0, 3.0);
+        let axes = [
+            (Mat4::rotate_x(40.0), Vec3::new(1.0, 0.0, 0.0)),
+            (Mat4::rotate_y(40.0), Vec3::new(0.0, 1.0, 0.0)),
+            (Mat4::rotate_z(40.0), Vec3::new(0.0, 0.0, 1.0)),
+        ];
+
+        for (m, axis) in axes.iter() {
+            let q = Quaternion::from_axis_angle(axis, 40.0);
+            assert_near(&m.transform_point(&p), &q.rotate(&p));
+        }
+        assert_near(
+            &Mat4::rotate_y(90.0).transform_vector(&Vec3::new(1.0, 0.0, 0.0)),
+            &Vec3::new(0.0, 0.0, -1.0),
+        );
+    }
+}
@@ -1,15 +1,36 @@
 pub mod aabb;
+pub mod animated_transform;
 pub mod r#box;
 pub mod bvh;
 pub mod camera;
 pub mod color;
+pub mod cone;
 pub mod constant_medium;
+pub mod csg;
+pub mod cylinder;
+pub mod disk;
+pub mod grid_medium;
+pub mod heightfield;
+pub mod heterogeneous_medium;
 pub mod hit;
+pub mod hyperboloid;
+pub mod instance;
+pub mod matrix;
 pub mod moving_sphere;
+pub mod onb;
+pub mod paraboloid;
+pub mod quad;
+pub mod quadric;
+pub mod quaternion;
 pub mod ray;
 pub mod rotate;
+pub mod sdf;
 pub mod sphere;
+pub mod torus;
+pub mod transform;
 pub mod translate;
+pub mod triangle;
+pub mod triangle_mesh;
 pub mod vec3;
 pub mod xy_rect;
 pub mod xz_rect;
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use crate::{material::material::Material, util::rtweekend::degrees_to_radians};
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    quadric::{nearest_hit, phi},
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+// A paraboloid opening towards +z from the origin, reaching the given radius
+// at z_max and clipped to [z_min, z_max].
+pub struct Paraboloid {
+    pub radius: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    pub phi_max: f64,
+    pub material: Arc<dyn Material + Sync + Send>,
+}
+
+impl Paraboloid {
+    pub fn new(
+        radius: f64,
+        z_min: f64,
+        z_max: f64,
+        phi_max: f64,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        let (z_min, z_max) = (z_min.min(z_max).max(0.0), z_min.max(z_max));
+        assert!(radius > 0.0, "Paraboloid must have a radius");
+        assert!(z_min < z_max, "Paraboloid must not be clipped to nothing");
+
+        Self {
+            radius,
+            z_min,
+            z_max,
+            phi_max: degrees_to_radians(phi_max.clamp(0.0, 360.0)),
+            material: mat,
+        }
+    }
+}
+
+impl Hittable for Paraboloid {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        // z = k * (x^2 + y^2)
+        let k = self.z_max / (self.radius * self.radius);
+        let (o, d) = (r.origin(), r.dir());
+        let a = k * (d.x() * d.x() + d.y() * d.y());
+        let b = 2.0 * k * (d.x() * o.x() + d.y() * o.y()) - d.z();
+        let c = k * (o.x() * o.x() + o.y() * o.y()) - o.z();
+
+        let hit = nearest_hit(r, (a, b, c), t_min, t_max, |p| {
+            let phi = phi(p);
+            if p.z() < self.z_min || p.z() > self.z_max || phi > self.phi_max {
+                return None;
+            }
+            Some((
+                phi / self.phi_max,
+                (p.z() - self.z_min) / (self.z_max - self.z_min),
+            ))
+        });
+        let (t, p, (u, v)) = match hit {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = u;
+        rec.v = v;
+        let outward_normal = Vec3::new(2.0 * k * p.x(), 2.0 * k * p.y(), -1.0).unit_vector();
+        rec.set_face_normal(r, &outward_normal);
+        rec.material = self.material.clone();
+
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = Aabb::new(
+            Point3::new(-self.radius, -self.radius, self.z_min),
+            Point3::new(self.radius, self.radius, self.z_max),
+        );
+        true
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use crate::{
+    material::material::Material,
+    util::rtweekend::{random_double, INFINITY},
+};
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+// A parallelogram spanned by the edges u and v from the corner q. The outward
+// normal is u x v, and (u, v) texture coordinates run along the two edges.
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    normal: Vec3,
+    d: f64,
+    area: f64,
+    material: Arc<dyn Material + Sync + Send>,
+    bbox: Aabb,
+}
+
+impl Quad {
+    pub fn new(q: &Point3, u: &Vec3, v: &Vec3, mat: Arc<dyn Material + Sync + Send>) -> Self {
+        let n = u.cross(v);
+        let normal = n.unit_vector();
+
+        Self {
+            q: *q,
+            u: *u,
+            v: *v,
+            w: n / n.dot(&n),
+            normal,
+            d: normal.dot(q),
+            area: n.length(),
+            material: mat,
+            bbox: Quad::compute_bounding_box(q, u, v),
+        }
+    }
+
+    fn compute_bounding_box(q: &Point3, u: &Vec3, v: &Vec3) -> Aabb {
+        let corners = [*q, q + u, q + v, q + u + v];
+        let mut min = corners[0];
+        let mut max = corners[0];
+
+        for corner in corners.iter() {
+            for c in 0..3 {
+                min[c] = min[c].min(corner[c]);
+                max[c] = max[c].max(corner[c]);
+            }
+        }
+
+        // The bounding box must have non-zero width in each dimension, so pad
+        // flat axes a small amount
+        for c in 0..3 {
+            if max[c] - min[c] < 0.0001 {
+                min[c] -= 0.0001;
+                max[c] += 0.0001;
+            }
+        }
+
+        Aabb::new(min, max)
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let denom = self.normal.dot(r.dir());
+
+        // No hit if the ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+
+        let t = (self.d - self.normal.dot(r.origin())) / denom;
+        if t < t_min || t > t_max {
+            return false;
+        }
+
+        // Express the hit point in the (u, v) frame of the plane
+        let p = r.at(t);
+        let planar_hitpt = p - self.q;
+        let alpha = self.w.dot(&planar_hitpt.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar_hitpt));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        rec.u = alpha;
+        rec.v = beta;
+        rec.t = t;
+        rec.p = p;
+        rec.material = self.material.clone();
+        rec.set_face_normal(r, &self.normal);
+
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = self.bbox.clone();
+        true
+    }
+
+    fn pdf_value(&self, origin: &Point3, dir: &Vec3) -> f64 {
+        let mut rec = HitRecord::default();
+        if !self.hit(&Ray::new(origin, dir, 0.0), 0.001, INFINITY, &mut rec) {
+            return 0.0;
+        }
+
+        // Convert the uniform area density to a solid angle density
+        let distance_squared = rec.t * rec.t * dir.length_squared();
+        let cosine = (dir.dot(&self.normal) / dir.length()).abs();
+
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        let p = self.q + random_double() * self.u + random_double() * self.v;
+        p - origin
+    }
+}
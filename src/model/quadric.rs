@@ -0,0 +1,45 @@
+use crate::util::{polynomial::solve_quadratic, rtweekend::PI};
+
+use super::{ray::Ray, vec3::Vec3};
+
+use Vec3 as Point3;
+
+// Helpers shared by the quadric surfaces. Each one is built around the z axis
+// in its own object space, with phi measured from +x towards +y, and is placed
+// in the scene with Translate, RotateY or Transform.
+
+// Angle of p around the z axis, in [0, 2pi).
+pub fn phi(p: &Point3) -> f64 {
+    let phi = p.y().atan2(p.x());
+    if phi < 0.0 {
+        phi + 2.0 * PI
+    } else {
+        phi
+    }
+}
+
+// Nearest root of a*t^2 + b*t + c = 0 within (t_min, t_max) whose point is on
+// the clipped surface. `surface` returns the uv of a point, or None if the
+// point lies outside the z range or the phi sweep.
+pub fn nearest_hit(
+    r: &Ray,
+    (a, b, c): (f64, f64, f64),
+    t_min: f64,
+    t_max: f64,
+    surface: impl Fn(&Point3) -> Option<(f64, f64)>,
+) -> Option<(f64, Point3, (f64, f64))> {
+    let (t0, t1) = solve_quadratic(a, b, c)?;
+
+    for t in [t0, t1] {
+        if t < t_min || t > t_max {
+            continue;
+        }
+
+        let p = r.at(t);
+        if let Some(uv) = surface(&p) {
+            return Some((t, p, uv));
+        }
+    }
+
+    None
+}
@@ -0,0 +1,137 @@
+use std::ops::Mul;
+
+use crate::util::rtweekend::degrees_to_radians;
+
+use super::vec3::Vec3;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quaternion {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub w: f64,
+}
+
+impl Quaternion {
+    pub fn new(x: f64, y: f64, z: f64, w: f64) -> Self {
+        Self { x, y, z, w }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    pub fn from_axis_angle(axis: &Vec3, angle: f64) -> Self {
+        let half = degrees_to_radians(angle) / 2.0;
+        let a = axis.unit_vector() * half.sin();
+
+        Self::new(a.x(), a.y(), a.z(), half.cos())
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn normalize(&self) -> Quaternion {
+        let len = self.dot(self).sqrt();
+        Quaternion::new(self.x / len, self.y / len, self.z / len, self.w / len)
+    }
+
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    // Spherical linear interpolation along the shortest arc.
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Quaternion {
+        let mut cos_theta = self.dot(other);
+        let mut q1 = *other;
+        if cos_theta < 0.0 {
+            q1 = Quaternion::new(-q1.x, -q1.y, -q1.z, -q1.w);
+            cos_theta = -cos_theta;
+        }
+
+        // Nearly parallel, so fall back to a normalized lerp to avoid dividing by ~0.
+        let (a, b) = if cos_theta > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos_theta.acos();
+            let sin_theta = theta.sin();
+            (
+                ((1.0 - t) * theta).sin() / sin_theta,
+                (t * theta).sin() / sin_theta,
+            )
+        };
+
+        Quaternion::new(
+            a * self.x + b * q1.x,
+            a * self.y + b * q1.y,
+            a * self.z + b * q1.z,
+            a * self.w + b * q1.w,
+        )
+        .normalize()
+    }
+
+    pub fn rotate(&self, v: &Vec3) -> Vec3 {
+        let u = Vec3::new(self.x, self.y, self.z);
+        let t = 2.0 * u.cross(v);
+
+        v + self.w * t + u.cross(&t)
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, o: Quaternion) -> Quaternion {
+        Quaternion::new(
+            self.w * o.x + self.x * o.w + self.y * o.z - self.z * o.y,
+            self.w * o.y - self.x * o.z + self.y * o.w + self.z * o.x,
+            self.w * o.z + self.x * o.y - self.y * o.x + self.z * o.w,
+            self.w * o.w - self.x * o.x - self.y * o.y - self.z * o.z,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: &Vec3, b: &Vec3) {
+        assert!((a - b).length() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_slerp_halfway() {
+        let z = Vec3::new(0.0, 0.0, 1.0);
+        let q0 = Quaternion::identity();
+        let q1 = Quaternion::from_axis_angle(&z, 90.0);
+        let half = q0.slerp(&q1, 0.5);
+
+        assert_near(
+            &half.rotate(&Vec3::new(1.0, 0.0, 0.0)),
+            &Quaternion::from_axis_angle(&z, 45.0).rotate(&Vec3::new(1.0, 0.0, 0.0)),
+        );
+        assert_near(&q0.slerp(&q1, 1.0).rotate(&z), &z);
+    }
+
+    #[test]
+    fn test_slerp_shortest_arc() {
+        // -q is the same rotation as q, and must not send slerp the long way
+        let z = Vec3::new(0.0, 0.0, 1.0);
+        let q1 = Quaternion::from_axis_angle(&z, 60.0);
+        let negated = Quaternion::new(-q1.x, -q1.y, -q1.z, -q1.w);
+        let a = Quaternion::identity().slerp(&q1, 0.5);
+        let b = Quaternion::identity().slerp(&negated, 0.5);
+
+        assert_near(
+            &a.rotate(&Vec3::new(1.0, 0.0, 0.0)),
+            &b.rotate(&Vec3::new(1.0, 0.0, 0.0)),
+        );
+    }
+}
@@ -1,3 +1,7 @@
+use std::sync::Arc;
+
+use crate::media::{medium::Medium, stack::MediumStack};
+
 use super::vec3::Vec3;
 use Vec3 as Point3;
 
@@ -5,6 +9,10 @@ pub struct Ray {
     origin: Point3,
     dir: Vec3,
     tm: f64,
+    // The objects the ray is inside of
+    media: MediumStack,
+    // In nanometers, when rendering spectrally
+    wavelength: Option<f64>,
 }
 
 impl Ray {
@@ -13,8 +21,26 @@ impl Ray {
             origin: origin.clone(),
             dir: dir.clone(),
             tm: time,
+            media: MediumStack::new(),
+            wavelength: None,
+        }
+    }
+
+    pub fn new_in_media(origin: &Point3, dir: &Vec3, time: f64, media: MediumStack) -> Self {
+        Self {
+            origin: *origin,
+            dir: *dir,
+            tm: time,
+            media,
+            wavelength: None,
         }
     }
+
+    pub fn with_wavelength(mut self, wavelength: Option<f64>) -> Self {
+        self.wavelength = wavelength;
+        self
+    }
+
     pub fn origin(&self) -> &Point3 {
         &self.origin
     }
@@ -30,4 +56,17 @@ impl Ray {
     pub fn time(&self) -> f64 {
         self.tm
     }
+
+    pub fn media(&self) -> &MediumStack {
+        &self.media
+    }
+
+    pub fn wavelength(&self) -> Option<f64> {
+        self.wavelength
+    }
+
+    // The medium the ray travels through, None for clear space
+    pub fn medium(&self) -> Option<&Arc<dyn Medium + Sync + Send>> {
+        self.media.medium()
+    }
 }
@@ -1,102 +1,102 @@
 use std::sync::Arc;
 
-use crate::util::rtweekend::{degrees_to_radians, INFINITY};
-
-use super::{aabb::Aabb, hit::Hittable, ray::Ray, vec3::Vec3};
-
-use Vec3 as Point3;
-
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    ray::Ray,
+    transform::Transform,
+};
+
+// Rotation by angle degrees about the y axis. A shorthand for the Transform
+// that does the same.
 pub struct RotateY {
-    pub hittable: Arc<dyn Hittable + Sync + Send>,
-    pub sin_theta: f64,
-    pub cos_theta: f64,
-    pub has_box: bool,
-    pub bbox: Aabb,
+    transform: Transform,
 }
 
 impl RotateY {
     pub fn new(p: Arc<dyn Hittable + Sync + Send>, angle: f64) -> Self {
-        let radians = degrees_to_radians(angle);
-        let sin_theta = radians.sin();
-        let cos_theta = radians.cos();
-        let mut bbox = Aabb::default();
-        let has_box = p.bounding_box(0.0, 1.0, &mut bbox);
-
-        let mut min = Point3::new(INFINITY, INFINITY, INFINITY);
-        let mut max = Point3::new(-INFINITY, -INFINITY, -INFINITY);
-
-        for i in 0..2 {
-            for j in 0..2 {
-                for k in 0..2 {
-                    let x = i as f64 * bbox.maximum.x() + (1.0 - i as f64) * bbox.minimum.x();
-                    let y = j as f64 * bbox.maximum.y() + (1.0 - j as f64) * bbox.minimum.y();
-                    let z = k as f64 * bbox.maximum.z() + (1.0 - k as f64) * bbox.minimum.z();
-
-                    let new_x = cos_theta * x + sin_theta * z;
-                    let new_z = -sin_theta * x + cos_theta * z;
-
-                    let tester = Vec3::new(new_x, y, new_z);
-
-                    for c in 0..3 {
-                        min[c] = min[c].min(tester[c]);
-                        max[c] = max[c].max(tester[c]);
-                    }
-                }
-            }
-        }
-
-        bbox = Aabb::new(min, max);
-
         Self {
-            hittable: p,
-            sin_theta,
-            cos_theta,
-            has_box,
-            bbox,
+            transform: Transform::new(p).rotate_y(angle),
         }
     }
 }
 
 impl Hittable for RotateY {
-    fn hit(
-        &self,
-        r: &super::ray::Ray,
-        t_min: f64,
-        t_max: f64,
-        rec: &mut super::hit::HitRecord,
-    ) -> bool {
-        let mut origin = r.origin().clone();
-        let mut direction = r.dir().clone();
-
-        origin[0] = self.cos_theta * r.origin()[0] - self.sin_theta * r.origin()[2];
-        origin[2] = self.sin_theta * r.origin()[0] + self.cos_theta * r.origin()[2];
-
-        direction[0] = self.cos_theta * r.dir()[0] - self.sin_theta * r.dir()[2];
-        direction[2] = self.sin_theta * r.dir()[0] + self.cos_theta * r.dir()[2];
-
-        let rotated_r = Ray::new(&origin, &direction, r.time());
-
-        if !self.hittable.hit(&rotated_r, t_min, t_max, rec) {
-            return false;
-        }
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.transform.hit(r, t_min, t_max, rec)
+    }
+
+    fn hit_surface(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.transform.hit_surface(r, t_min, t_max, rec)
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.transform.transmittance(r, t_min, t_max)
+    }
 
-        let mut p = rec.p;
-        let mut normal = rec.normal;
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        self.transform.bounding_box(time0, time1, output_box)
+    }
+}
 
-        p[0] = self.cos_theta * rec.p[0] + self.sin_theta * rec.p[2];
-        p[2] = -self.sin_theta * rec.p[0] + self.cos_theta * rec.p[2];
+#[cfg(test)]
+mod tests {
+    use crate::{
+        material::lambertian::Lambertian,
+        model::{cylinder::Cylinder, translate::Translate, vec3::Vec3},
+    };
 
-        normal[0] = self.cos_theta * rec.normal[0] + self.sin_theta * rec.normal[2];
-        normal[2] = -self.sin_theta * rec.normal[0] + self.cos_theta * rec.normal[2];
+    use super::*;
 
-        rec.p = p;
-        rec.set_face_normal(&rotated_r, &normal);
+    use Vec3 as Point3;
 
-        return true;
+    fn near(a: &Vec3, b: &Vec3) -> bool {
+        (*a - *b).length() < 1e-9
     }
 
-    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
-        *output_box = self.bbox.clone();
-        return self.has_box;
+    #[test]
+    fn test_quadric_inside_wrappers() {
+        // A cylinder around z, turned to lie along x and lifted to y = 3
+        let cylinder = Arc::new(Cylinder::new(
+            1.0,
+            -2.0,
+            2.0,
+            360.0,
+            Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5))),
+        ));
+        let object = Translate::new(
+            Arc::new(RotateY::new(cylinder, 90.0)),
+            &Vec3::new(0.0, 3.0, 0.0),
+        );
+
+        let mut bbox = Aabb::default();
+        assert!(object.bounding_box(0.0, 1.0, &mut bbox));
+        assert!(near(&bbox.minimum, &Point3::new(-2.0, 2.0, -1.0)));
+        assert!(near(&bbox.maximum, &Point3::new(2.0, 4.0, 1.0)));
+
+        let mut rec = HitRecord::default();
+        let r = Ray::new(
+            &Point3::new(1.0, 10.0, 0.0),
+            &Vec3::new(0.0, -1.0, 0.0),
+            0.0,
+        );
+        assert!(object.hit(&r, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - 6.0).abs() < 1e-9);
+        assert!(near(&rec.p, &Point3::new(1.0, 4.0, 0.0)));
+        assert!(near(&rec.normal, &Vec3::new(0.0, 1.0, 0.0)));
+        assert!(rec.front_face);
+
+        let r = Ray::new(&Point3::new(0.0, 3.0, 5.0), &Vec3::new(0.0, 0.0, -2.0), 0.0);
+        assert!(object.hit(&r, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - 2.0).abs() < 1e-9);
+        assert!(near(&rec.normal, &Vec3::new(0.0, 0.0, 1.0)));
+
+        // Past the end of the cylinder, which now runs along x
+        let r = Ray::new(
+            &Point3::new(2.5, 10.0, 0.0),
+            &Vec3::new(0.0, -1.0, 0.0),
+            0.0,
+        );
+        assert!(!object.hit(&r, 0.001, f64::INFINITY, &mut rec));
     }
 }
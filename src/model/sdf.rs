@@ -0,0 +1,368 @@
+use std::sync::Arc;
+
+use crate::material::material::Material;
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    ray::Ray,
+    sphere::Sphere,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+// A signed distance function: negative inside, positive outside, and never
+// more than the true distance to the surface, so sphere tracing can step by it.
+pub trait Sdf {
+    fn distance(&self, p: &Point3) -> f64;
+}
+
+// Any closure over a point can be used as a distance function.
+impl<F: Fn(&Point3) -> f64> Sdf for F {
+    fn distance(&self, p: &Point3) -> f64 {
+        self(p)
+    }
+}
+
+pub struct SdfSphere {
+    pub radius: f64,
+}
+
+impl SdfSphere {
+    pub fn new(radius: f64) -> Self {
+        Self { radius }
+    }
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, p: &Point3) -> f64 {
+        p.length() - self.radius
+    }
+}
+
+// A box with the given half extents, with its edges rounded off by `rounding`.
+pub struct SdfBox {
+    pub half_extents: Vec3,
+    pub rounding: f64,
+}
+
+impl SdfBox {
+    pub fn new(half_extents: &Vec3, rounding: f64) -> Self {
+        Self {
+            half_extents: *half_extents,
+            rounding,
+        }
+    }
+}
+
+impl Sdf for SdfBox {
+    fn distance(&self, p: &Point3) -> f64 {
+        let mut q = Vec3::default();
+        for a in 0..3 {
+            q[a] = p[a].abs() - self.half_extents[a] + self.rounding;
+        }
+        let outside = Vec3::new(q.x().max(0.0), q.y().max(0.0), q.z().max(0.0)).length();
+        let inside = q.x().max(q.y()).max(q.z()).min(0.0);
+
+        outside + inside - self.rounding
+    }
+}
+
+// A torus around the y axis.
+pub struct SdfTorus {
+    pub major_radius: f64,
+    pub minor_radius: f64,
+}
+
+impl SdfTorus {
+    pub fn new(major_radius: f64, minor_radius: f64) -> Self {
+        Self {
+            major_radius,
+            minor_radius,
+        }
+    }
+}
+
+impl Sdf for SdfTorus {
+    fn distance(&self, p: &Point3) -> f64 {
+        let ring = (p.x() * p.x() + p.z() * p.z()).sqrt() - self.major_radius;
+        (ring * ring + p.y() * p.y()).sqrt() - self.minor_radius
+    }
+}
+
+// A capsule around the segment a-b.
+pub struct SdfCapsule {
+    pub a: Point3,
+    pub b: Point3,
+    pub radius: f64,
+}
+
+impl SdfCapsule {
+    pub fn new(a: &Point3, b: &Point3, radius: f64) -> Self {
+        Self {
+            a: *a,
+            b: *b,
+            radius,
+        }
+    }
+}
+
+impl Sdf for SdfCapsule {
+    fn distance(&self, p: &Point3) -> f64 {
+        let pa = p - self.a;
+        let ba = self.b - self.a;
+        let h = (pa.dot(&ba) / ba.length_squared()).clamp(0.0, 1.0);
+
+        (pa - h * ba).length() - self.radius
+    }
+}
+
+// Blends two shapes together over a distance of k. A k of zero is a plain
+// union.
+pub struct SmoothUnion {
+    pub a: Arc<dyn Sdf + Sync + Send>,
+    pub b: Arc<dyn Sdf + Sync + Send>,
+    pub k: f64,
+}
+
+impl SmoothUnion {
+    pub fn new(a: Arc<dyn Sdf + Sync + Send>, b: Arc<dyn Sdf + Sync + Send>, k: f64) -> Self {
+        Self { a, b, k }
+    }
+}
+
+impl Sdf for SmoothUnion {
+    fn distance(&self, p: &Point3) -> f64 {
+        let (d1, d2) = (self.a.distance(p), self.b.distance(p));
+        if self.k <= 0.0 {
+            return d1.min(d2);
+        }
+
+        let h = (0.5 + 0.5 * (d2 - d1) / self.k).clamp(0.0, 1.0);
+        d2 + (d1 - d2) * h - self.k * h * (1.0 - h)
+    }
+}
+
+// Carves b out of a, rounding the cut over a distance of k.
+pub struct Subtraction {
+    pub a: Arc<dyn Sdf + Sync + Send>,
+    pub b: Arc<dyn Sdf + Sync + Send>,
+    pub k: f64,
+}
+
+impl Subtraction {
+    pub fn new(a: Arc<dyn Sdf + Sync + Send>, b: Arc<dyn Sdf + Sync + Send>, k: f64) -> Self {
+        Self { a, b, k }
+    }
+}
+
+impl Sdf for Subtraction {
+    fn distance(&self, p: &Point3) -> f64 {
+        let (d1, d2) = (self.a.distance(p), -self.b.distance(p));
+        if self.k <= 0.0 {
+            return d1.max(d2);
+        }
+
+        let h = (0.5 - 0.5 * (d2 - d1) / self.k).clamp(0.0, 1.0);
+        d2 + (d1 - d2) * h + self.k * h * (1.0 - h)
+    }
+}
+
+// Infinitely repeats a shape centered on the origin with the given period
+// along each axis. A period of zero leaves that axis alone. The shape should
+// fit inside one cell.
+pub struct Repeat {
+    pub sdf: Arc<dyn Sdf + Sync + Send>,
+    pub period: Vec3,
+}
+
+impl Repeat {
+    pub fn new(sdf: Arc<dyn Sdf + Sync + Send>, period: &Vec3) -> Self {
+        Self {
+            sdf,
+            period: *period,
+        }
+    }
+}
+
+impl Sdf for Repeat {
+    fn distance(&self, p: &Point3) -> f64 {
+        let mut q = *p;
+        for a in 0..3 {
+            if self.period[a] > 0.0 {
+                q[a] -= self.period[a] * (p[a] / self.period[a]).round();
+            }
+        }
+
+        self.sdf.distance(&q)
+    }
+}
+
+// Twists a shape around the y axis by k radians per unit of height.
+pub struct Twist {
+    pub sdf: Arc<dyn Sdf + Sync + Send>,
+    pub k: f64,
+}
+
+impl Twist {
+    pub fn new(sdf: Arc<dyn Sdf + Sync + Send>, k: f64) -> Self {
+        Self { sdf, k }
+    }
+}
+
+impl Sdf for Twist {
+    fn distance(&self, p: &Point3) -> f64 {
+        let (sin_theta, cos_theta) = (self.k * p.y()).sin_cos();
+        let q = Point3::new(
+            cos_theta * p.x() - sin_theta * p.z(),
+            p.y(),
+            sin_theta * p.x() + cos_theta * p.z(),
+        );
+
+        // Twisting stretches distances away from the axis, so scale the step
+        // back down to keep the bound conservative
+        let radius = (p.x() * p.x() + p.z() * p.z()).sqrt();
+        self.sdf.distance(&q) / (1.0 + self.k * self.k * radius * radius).sqrt()
+    }
+}
+
+// Renders a distance function by sphere tracing inside a user-supplied
+// bounding box. uv is a spherical mapping around the center of the box.
+pub struct SdfHittable {
+    pub sdf: Arc<dyn Sdf + Sync + Send>,
+    pub bbox: Aabb,
+    pub material: Arc<dyn Material + Sync + Send>,
+    pub max_steps: usize,
+    pub epsilon: f64,
+}
+
+impl SdfHittable {
+    pub fn new(
+        sdf: Arc<dyn Sdf + Sync + Send>,
+        bbox: Aabb,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        Self {
+            sdf,
+            bbox,
+            material: mat,
+            max_steps: 256,
+            epsilon: 1e-5,
+        }
+    }
+
+    fn normal(&self, p: &Point3) -> Vec3 {
+        let h = 1e-4;
+        let mut n = Vec3::default();
+        for a in 0..3 {
+            let mut offset = Vec3::default();
+            offset[a] = h;
+            n[a] = self.sdf.distance(&(p + offset)) - self.sdf.distance(&(p - offset));
+        }
+
+        n.unit_vector()
+    }
+}
+
+impl Hittable for SdfHittable {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let (mut t, t_end) = match self.bbox.hit_interval(r, t_min, t_max) {
+            Some(interval) => interval,
+            None => return false,
+        };
+
+        // A ray leaving a surface starts within epsilon of it, so step off
+        // the surface before marching, or it would be found again at once
+        let length = r.dir().length();
+        let mut steps = 0;
+        let mut start = self.sdf.distance(&r.at(t));
+        while start.abs() < self.epsilon {
+            t += self.epsilon / length;
+            steps += 1;
+            if t > t_end || steps >= self.max_steps {
+                return false;
+            }
+            start = self.sdf.distance(&r.at(t));
+        }
+
+        // March on whichever side of the surface the ray starts, so rays
+        // travelling inside the shape find the next crossing too
+        let side = start.signum();
+        loop {
+            let d = side * self.sdf.distance(&r.at(t));
+            if d < self.epsilon {
+                break;
+            }
+
+            t += d / length;
+            steps += 1;
+            if t > t_end || steps >= self.max_steps {
+                return false;
+            }
+        }
+
+        let p = r.at(t);
+        let outward_normal = self.normal(&p);
+        let center = 0.5 * (self.bbox.minimum + self.bbox.maximum);
+        let (u, v) = Sphere::get_sphere_uv(&(p - center).unit_vector());
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = u;
+        rec.v = v;
+        rec.set_face_normal(r, &outward_normal);
+        rec.material = self.material.clone();
+
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = self.bbox.clone();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::lambertian::Lambertian;
+
+    use super::*;
+
+    fn unit_sphere() -> SdfHittable {
+        SdfHittable::new(
+            Arc::new(SdfSphere::new(1.0)),
+            Aabb::new(Point3::new(-1.5, -1.5, -1.5), Point3::new(1.5, 1.5, 1.5)),
+            Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn test_hit_from_outside() {
+        let r = Ray::new(&Point3::new(-3.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::default();
+        assert!(unit_sphere().hit(&r, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - 2.0).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_rays_leaving_the_surface() {
+        let sphere = unit_sphere();
+        let mut rec = HitRecord::default();
+        let p = Point3::new(1.0, 0.0, 0.0);
+
+        // Reflected away from the surface, there is nothing else to hit
+        for dir in [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.05, 1.0, 0.0)] {
+            let r = Ray::new(&p, &dir, 0.0);
+            assert!(!sphere.hit(&r, 0.0, f64::INFINITY, &mut rec), "{}", dir);
+        }
+
+        // Refracted into it, the next crossing is the far side
+        let r = Ray::new(&p, &Vec3::new(-1.0, 0.0, 0.0), 0.0);
+        assert!(sphere.hit(&r, 0.0, f64::INFINITY, &mut rec));
+        assert!((rec.t - 2.0).abs() < 1e-4);
+        assert!(!rec.front_face);
+    }
+}
@@ -21,7 +21,7 @@ impl Sphere {
         }
     }
 
-    fn get_sphere_uv(p: &Point3) -> (f64, f64) {
+    pub fn get_sphere_uv(p: &Point3) -> (f64, f64) {
         let theta = (-p.y()).acos();
         let phi = (-p.z()).atan2(p.x()) + PI;
 
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use crate::{
+    material::material::Material,
+    util::{polynomial::solve_quartic, rtweekend::PI},
+};
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    quadric::phi,
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+// A torus around the z axis: a tube of minor_radius swept around a circle of
+// major_radius in the z = 0 plane.
+pub struct Torus {
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    pub material: Arc<dyn Material + Sync + Send>,
+}
+
+impl Torus {
+    pub fn new(major_radius: f64, minor_radius: f64, mat: Arc<dyn Material + Sync + Send>) -> Self {
+        Self {
+            major_radius,
+            minor_radius,
+            material: mat,
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let length = r.dir().length();
+        let d = r.dir() / length;
+
+        // Solve from the point on the ray closest to the center, with a unit
+        // direction, to keep the quartic coefficients small
+        let shift = -r.origin().dot(&d);
+        let o = r.origin() + shift * d;
+        let outer = self.major_radius + self.minor_radius;
+        if o.length_squared() > outer * outer {
+            return false;
+        }
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + y^2) along p = o + s*d
+        let r2 = self.major_radius * self.major_radius;
+        let k = o.length_squared() + r2 - self.minor_radius * self.minor_radius;
+        let od = o.dot(&d);
+        let roots = solve_quartic(
+            1.0,
+            4.0 * od,
+            4.0 * od * od + 2.0 * k - 4.0 * r2 * (d.x() * d.x() + d.y() * d.y()),
+            4.0 * od * k - 8.0 * r2 * (o.x() * d.x() + o.y() * d.y()),
+            k * k - 4.0 * r2 * (o.x() * o.x() + o.y() * o.y()),
+        );
+
+        let t = match roots
+            .iter()
+            .map(|s| (s + shift) / length)
+            .find(|t| *t >= t_min && *t <= t_max)
+        {
+            Some(t) => t,
+            None => return false,
+        };
+
+        let p = r.at(t);
+        let ring = (p.x() * p.x() + p.y() * p.y()).sqrt();
+        let center = Point3::new(p.x(), p.y(), 0.0) * (self.major_radius / ring);
+        let outward_normal = (p - center).unit_vector();
+
+        // v runs around the tube, starting on the outer equator
+        let mut theta = p.z().atan2(ring - self.major_radius);
+        if theta < 0.0 {
+            theta += 2.0 * PI;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = phi(&p) / (2.0 * PI);
+        rec.v = theta / (2.0 * PI);
+        rec.set_face_normal(r, &outward_normal);
+        rec.material = self.material.clone();
+
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        let outer = self.major_radius + self.minor_radius;
+        *output_box = Aabb::new(
+            Point3::new(-outer, -outer, -self.minor_radius),
+            Point3::new(outer, outer, self.minor_radius),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::lambertian::Lambertian;
+
+    use super::*;
+
+    fn torus() -> Torus {
+        Torus::new(
+            2.0,
+            0.5,
+            Arc::new(Lambertian::new(&Vec3::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn test_crossings_along_a_diameter() {
+        // An unnormalized direction, so t is half the distance travelled
+        let r = Ray::new(&Point3::new(-5.0, 0.0, 0.0), &Vec3::new(2.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::default();
+        let mut t_min = 0.001;
+        for (x, front_face) in [(-2.5, true), (-1.5, false), (1.5, true), (2.5, false)] {
+            assert!(torus().hit(&r, t_min, f64::INFINITY, &mut rec));
+            assert!((rec.p.x() - x).abs() < 1e-9, "{} != {}", rec.p.x(), x);
+            assert!((rec.t - (x + 5.0) / 2.0).abs() < 1e-9);
+            assert_eq!(rec.front_face, front_face);
+            t_min = rec.t + 1e-6;
+        }
+        assert!(!torus().hit(&r, t_min, f64::INFINITY, &mut rec));
+    }
+
+    #[test]
+    fn test_top_of_the_tube() {
+        let r = Ray::new(&Point3::new(0.0, 2.0, 5.0), &Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let mut rec = HitRecord::default();
+        assert!(torus().hit(&r, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.p - Point3::new(0.0, 2.0, 0.5)).length() < 1e-9);
+        assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-9);
+        assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.25).abs() < 1e-9);
+
+        // Straight down through the hole misses
+        let r = Ray::new(&Point3::new(0.0, 0.0, 5.0), &Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(!torus().hit(&r, 0.001, f64::INFINITY, &mut rec));
+    }
+}
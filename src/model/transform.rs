@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use crate::util::rtweekend::INFINITY;
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    matrix::Mat4,
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+pub struct Transform {
+    hittable: Arc<dyn Hittable + Sync + Send>,
+    object_to_world: Mat4,
+    world_to_object: Mat4,
+    normal_to_world: Mat4,
+}
+
+impl Transform {
+    pub fn new(p: Arc<dyn Hittable + Sync + Send>) -> Self {
+        Transform::new_with_matrix(p, &Mat4::identity())
+    }
+
+    pub fn new_with_matrix(p: Arc<dyn Hittable + Sync + Send>, m: &Mat4) -> Self {
+        let world_to_object = m.inverse().expect("Transform matrix is not invertible");
+
+        Self {
+            hittable: p,
+            object_to_world: *m,
+            world_to_object,
+            normal_to_world: world_to_object.transpose(),
+        }
+    }
+
+    // Applies `m` after the current transform, so calls chain in the order they
+    // are applied: new(p).rotate_y(15.0).translate(&offset).
+    pub fn then(self, m: &Mat4) -> Self {
+        let object_to_world = *m * self.object_to_world;
+        Transform::new_with_matrix(self.hittable, &object_to_world)
+    }
+
+    pub fn translate(self, offset: &Vec3) -> Self {
+        self.then(&Mat4::translate(offset))
+    }
+
+    pub fn scale(self, s: &Vec3) -> Self {
+        self.then(&Mat4::scale(s))
+    }
+
+    pub fn rotate_x(self, angle: f64) -> Self {
+        self.then(&Mat4::rotate_x(angle))
+    }
+
+    pub fn rotate_y(self, angle: f64) -> Self {
+        self.then(&Mat4::rotate_y(angle))
+    }
+
+    pub fn rotate_z(self, angle: f64) -> Self {
+        self.then(&Mat4::rotate_z(angle))
+    }
+
+    pub fn rotate_axis(self, axis: &Vec3, angle: f64) -> Self {
+        self.then(&Mat4::rotate_axis(axis, angle))
+    }
+
+    pub fn look_at(self, from: &Point3, to: &Point3, up: &Vec3) -> Self {
+        self.then(&Mat4::look_at(from, to, up))
+    }
+
+    // r in object space. It is not renormalized, so t carries over.
+    fn local_ray(&self, r: &Ray) -> Ray {
+        Ray::new(
+            &self.world_to_object.transform_point(r.origin()),
+            &self.world_to_object.transform_vector(r.dir()),
+            r.time(),
+        )
+    }
+
+    // Normals go through the inverse transpose, which keeps them facing the
+    // ray.
+    fn hit_to_world(&self, rec: &mut HitRecord) {
+        rec.p = self.object_to_world.transform_point(&rec.p);
+        rec.normal = self
+            .normal_to_world
+            .transform_vector(&rec.normal)
+            .unit_vector();
+    }
+}
+
+// World box of an object box under `m`, from its eight transformed corners.
+pub fn transform_bounding_box(m: &Mat4, bbox: &Aabb) -> Aabb {
+    let mut min = Point3::new(INFINITY, INFINITY, INFINITY);
+    let mut max = Point3::new(-INFINITY, -INFINITY, -INFINITY);
+
+    for i in 0..2 {
+        for j in 0..2 {
+            for k in 0..2 {
+                let x = i as f64 * bbox.maximum.x() + (1.0 - i as f64) * bbox.minimum.x();
+                let y = j as f64 * bbox.maximum.y() + (1.0 - j as f64) * bbox.minimum.y();
+                let z = k as f64 * bbox.maximum.z() + (1.0 - k as f64) * bbox.minimum.z();
+
+                let tester = m.transform_point(&Point3::new(x, y, z));
+
+                for c in 0..3 {
+                    min[c] = min[c].min(tester[c]);
+                    max[c] = max[c].max(tester[c]);
+                }
+            }
+        }
+    }
+
+    Aabb::new(min, max)
+}
+
+impl Hittable for Transform {
+    fn hit(
+        &self,
+        r: &super::ray::Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut super::hit::HitRecord,
+    ) -> bool {
+        if !self.hittable.hit(&self.local_ray(r), t_min, t_max, rec) {
+            return false;
+        }
+
+        self.hit_to_world(rec);
+        true
+    }
+
+    fn hit_surface(
+        &self,
+        r: &super::ray::Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut super::hit::HitRecord,
+    ) -> bool {
+        if !self
+            .hittable
+            .hit_surface(&self.local_ray(r), t_min, t_max, rec)
+        {
+            return false;
+        }
+
+        self.hit_to_world(rec);
+        true
+    }
+
+    fn transmittance(&self, r: &super::ray::Ray, t_min: f64, t_max: f64) -> f64 {
+        self.hittable
+            .transmittance(&self.local_ray(r), t_min, t_max)
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        let mut bbox = Aabb::default();
+        if !self.hittable.bounding_box(time0, time1, &mut bbox) {
+            return false;
+        }
+
+        *output_box = transform_bounding_box(&self.object_to_world, &bbox);
+        true
+    }
+}
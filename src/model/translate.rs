@@ -1,49 +1,41 @@
 use std::sync::Arc;
 
-use super::{aabb::Aabb, hit::Hittable, ray::Ray, vec3::Vec3};
-
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    ray::Ray,
+    transform::Transform,
+    vec3::Vec3,
+};
+
+// Translation by displacement. A shorthand for the Transform that does the
+// same.
 pub struct Translate {
-    hittable: Arc<dyn Hittable + Sync + Send>,
-    offset: Vec3,
+    transform: Transform,
 }
 
 impl Translate {
     pub fn new(p: Arc<dyn Hittable + Sync + Send>, displacement: &Vec3) -> Self {
         Self {
-            hittable: p,
-            offset: displacement.clone(),
+            transform: Transform::new(p).translate(displacement),
         }
     }
 }
 
 impl Hittable for Translate {
-    fn hit(
-        &self,
-        r: &super::ray::Ray,
-        t_min: f64,
-        t_max: f64,
-        rec: &mut super::hit::HitRecord,
-    ) -> bool {
-        let moved_r = Ray::new(&(r.origin() - self.offset), r.dir(), r.time());
-        if !self.hittable.hit(&moved_r, t_min, t_max, rec) {
-            return false;
-        }
-
-        rec.p += self.offset;
-        rec.set_face_normal(&moved_r, &rec.normal.clone());
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.transform.hit(r, t_min, t_max, rec)
+    }
 
-        return true;
+    fn hit_surface(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.transform.hit_surface(r, t_min, t_max, rec)
     }
 
-    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut super::aabb::Aabb) -> bool {
-        if !self.hittable.bounding_box(time0, time1, output_box) {
-            return false;
-        }
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.transform.transmittance(r, t_min, t_max)
+    }
 
-        *output_box = Aabb::new(
-            output_box.minimum + self.offset,
-            output_box.maximum + self.offset,
-        );
-        return true;
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        self.transform.bounding_box(time0, time1, output_box)
     }
 }
@@ -0,0 +1,194 @@
+use std::sync::Arc;
+
+use crate::material::material::Material;
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+pub struct Triangle {
+    pub vertices: [Point3; 3],
+    pub normals: Option<[Vec3; 3]>,
+    pub uvs: Option<[(f64, f64); 3]>,
+    pub material: Arc<dyn Material + Sync + Send>,
+}
+
+impl Triangle {
+    pub fn new(
+        v0: &Point3,
+        v1: &Point3,
+        v2: &Point3,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        Triangle::new_with_attributes([*v0, *v1, *v2], None, None, mat)
+    }
+
+    pub fn new_with_attributes(
+        vertices: [Point3; 3],
+        normals: Option<[Vec3; 3]>,
+        uvs: Option<[(f64, f64); 3]>,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        Self {
+            vertices,
+            normals,
+            uvs,
+            material: mat,
+        }
+    }
+}
+
+// Watertight ray/triangle intersection (Woop, Benthin and Wald, 2013). Returns the
+// ray parameter and the barycentric weights of v0, v1 and v2.
+pub fn intersect_triangle(
+    r: &Ray,
+    v0: &Point3,
+    v1: &Point3,
+    v2: &Point3,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, [f64; 3])> {
+    let dir = r.dir();
+
+    // Permute axes so that z is the dominant direction, keeping the winding.
+    let mut kz = 0;
+    for a in 1..3 {
+        if dir[a].abs() > dir[kz].abs() {
+            kz = a;
+        }
+    }
+    let mut kx = (kz + 1) % 3;
+    let mut ky = (kx + 1) % 3;
+    if dir[kz] < 0.0 {
+        std::mem::swap(&mut kx, &mut ky);
+    }
+
+    let sx = dir[kx] / dir[kz];
+    let sy = dir[ky] / dir[kz];
+    let sz = 1.0 / dir[kz];
+
+    let a = v0 - r.origin();
+    let b = v1 - r.origin();
+    let c = v2 - r.origin();
+
+    let ax = a[kx] - sx * a[kz];
+    let ay = a[ky] - sy * a[kz];
+    let bx = b[kx] - sx * b[kz];
+    let by = b[ky] - sy * b[kz];
+    let cx = c[kx] - sx * c[kz];
+    let cy = c[ky] - sy * c[kz];
+
+    let u = cx * by - cy * bx;
+    let v = ax * cy - ay * cx;
+    let w = bx * ay - by * ax;
+
+    if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+        return None;
+    }
+
+    let det = u + v + w;
+    if det == 0.0 {
+        return None;
+    }
+
+    let t_scaled = u * sz * a[kz] + v * sz * b[kz] + w * sz * c[kz];
+    let t = t_scaled / det;
+    if t < t_min || t > t_max {
+        return None;
+    }
+
+    Some((t, [u / det, v / det, w / det]))
+}
+
+// Fills a hit record from barycentric weights. Shading normals are flipped to the
+// same side as the geometric normal, which decides front_face.
+pub fn set_triangle_hit_record(
+    r: &Ray,
+    t: f64,
+    b: &[f64; 3],
+    vertices: &[Point3; 3],
+    normals: Option<&[Vec3; 3]>,
+    uvs: Option<&[(f64, f64); 3]>,
+    rec: &mut HitRecord,
+) {
+    rec.t = t;
+    rec.p = b[0] * vertices[0] + b[1] * vertices[1] + b[2] * vertices[2];
+
+    let outward_normal = (vertices[1] - vertices[0])
+        .cross(&(vertices[2] - vertices[0]))
+        .unit_vector();
+    rec.set_face_normal(r, &outward_normal);
+
+    if let Some(n) = normals {
+        let shading = (b[0] * n[0] + b[1] * n[1] + b[2] * n[2]).unit_vector();
+        if !shading.near_zero() {
+            rec.normal = if rec.front_face { shading } else { -shading };
+        }
+    }
+
+    match uvs {
+        Some(uv) => {
+            rec.u = b[0] * uv[0].0 + b[1] * uv[1].0 + b[2] * uv[2].0;
+            rec.v = b[0] * uv[0].1 + b[1] * uv[1].1 + b[2] * uv[2].1;
+        }
+        None => {
+            rec.u = b[1];
+            rec.v = b[2];
+        }
+    }
+}
+
+// Bounds of three points, padded like the axis-aligned rects when the triangle is
+// flat along an axis.
+pub fn triangle_bounding_box(vertices: &[Point3; 3]) -> Aabb {
+    let mut min = vertices[0];
+    let mut max = vertices[0];
+    for v in vertices.iter().skip(1) {
+        for c in 0..3 {
+            min[c] = min[c].min(v[c]);
+            max[c] = max[c].max(v[c]);
+        }
+    }
+
+    for c in 0..3 {
+        if max[c] - min[c] < 0.0001 {
+            min[c] -= 0.0001;
+            max[c] += 0.0001;
+        }
+    }
+
+    Aabb::new(min, max)
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let [v0, v1, v2] = &self.vertices;
+        let (t, b) = match intersect_triangle(r, v0, v1, v2, t_min, t_max) {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        set_triangle_hit_record(
+            r,
+            t,
+            &b,
+            &self.vertices,
+            self.normals.as_ref(),
+            self.uvs.as_ref(),
+            rec,
+        );
+        rec.material = self.material.clone();
+
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        *output_box = triangle_bounding_box(&self.vertices);
+        true
+    }
+}
@@ -0,0 +1,258 @@
+use std::sync::Arc;
+
+use crate::{material::material::Material, util::rtweekend::INFINITY};
+
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    ray::Ray,
+    triangle::{intersect_triangle, set_triangle_hit_record, triangle_bounding_box},
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
+
+const MAX_FACES_PER_LEAF: usize = 4;
+
+// Interior nodes keep their left child right after themselves and point at the
+// right one; leaves point at a run of faces.
+struct MeshBvhNode {
+    bbox: Aabb,
+    offset: usize,
+    count: usize,
+    axis: i32,
+}
+
+pub struct TriangleMesh {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<(f64, f64)>,
+    pub colors: Vec<Vec3>,
+    pub indices: Vec<[usize; 3]>,
+    pub material: Arc<dyn Material + Sync + Send>,
+    nodes: Vec<MeshBvhNode>,
+}
+
+impl TriangleMesh {
+    // `normals` and `uvs` are either empty or indexed like `positions`.
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<(f64, f64)>,
+        indices: Vec<[usize; 3]>,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        assert!(normals.is_empty() || normals.len() == positions.len());
+        assert!(uvs.is_empty() || uvs.len() == positions.len());
+        assert!(indices.iter().flatten().all(|i| *i < positions.len()));
+
+        let mut mesh = Self {
+            positions,
+            normals,
+            uvs,
+            colors: Vec::new(),
+            indices,
+            material: mat,
+            nodes: Vec::new(),
+        };
+        mesh.build_bvh();
+
+        mesh
+    }
+
+    // Same as `new`, with area-weighted vertex normals for smooth shading.
+    pub fn new_smooth(
+        positions: Vec<Point3>,
+        uvs: Vec<(f64, f64)>,
+        indices: Vec<[usize; 3]>,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        let normals = TriangleMesh::compute_vertex_normals(&positions, &indices);
+        TriangleMesh::new(positions, normals, uvs, indices, mat)
+    }
+
+    // Per-vertex colors, indexed like `positions`, that hits interpolate into
+    // `HitRecord::color`
+    pub fn with_colors(mut self, colors: Vec<Vec3>) -> Self {
+        assert!(colors.is_empty() || colors.len() == self.positions.len());
+        self.colors = colors;
+        self
+    }
+
+    pub fn compute_vertex_normals(positions: &[Point3], indices: &[[usize; 3]]) -> Vec<Vec3> {
+        let mut normals = vec![Vec3::default(); positions.len()];
+        for face in indices {
+            // The unnormalized cross product is proportional to the face area.
+            let n = (positions[face[1]] - positions[face[0]])
+                .cross(&(positions[face[2]] - positions[face[0]]));
+            for i in face {
+                normals[*i] += n;
+            }
+        }
+
+        normals
+            .into_iter()
+            .map(|n| if n.near_zero() { n } else { n.unit_vector() })
+            .collect()
+    }
+
+    pub fn face_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn face_vertices(&self, face: usize) -> [Point3; 3] {
+        let [i0, i1, i2] = self.indices[face];
+        [self.positions[i0], self.positions[i1], self.positions[i2]]
+    }
+
+    fn build_bvh(&mut self) {
+        self.nodes.clear();
+        if self.indices.is_empty() {
+            return;
+        }
+
+        let bounds: Vec<Aabb> = (0..self.indices.len())
+            .map(|f| triangle_bounding_box(&self.face_vertices(f)))
+            .collect();
+        let mut faces: Vec<usize> = (0..self.indices.len()).collect();
+        self.build_node(&bounds, &mut faces, 0);
+
+        // Store faces in leaf order so that leaves address contiguous runs.
+        self.indices = faces.iter().map(|f| self.indices[*f]).collect();
+    }
+
+    fn build_node(&mut self, bounds: &[Aabb], faces: &mut [usize], offset: usize) -> usize {
+        let bbox = faces.iter().skip(1).fold(bounds[faces[0]].clone(), |b, f| {
+            b.surrounding_box(&bounds[*f])
+        });
+
+        let index = self.nodes.len();
+        self.nodes.push(MeshBvhNode {
+            bbox,
+            offset,
+            count: faces.len(),
+            axis: 0,
+        });
+        if faces.len() <= MAX_FACES_PER_LEAF {
+            return index;
+        }
+
+        // Split at the median centroid along the widest axis of the centroids.
+        let centroid = |f: usize| 0.5 * (bounds[f].minimum + bounds[f].maximum);
+        let mut min = Point3::new(INFINITY, INFINITY, INFINITY);
+        let mut max = Point3::new(-INFINITY, -INFINITY, -INFINITY);
+        for f in faces.iter() {
+            let c = centroid(*f);
+            for a in 0..3 {
+                min[a] = min[a].min(c[a]);
+                max[a] = max[a].max(c[a]);
+            }
+        }
+        let extent = max - min;
+        let mut axis = 0;
+        for a in 1..3 {
+            if extent[a] > extent[axis] {
+                axis = a;
+            }
+        }
+
+        let mid = faces.len() / 2;
+        faces.select_nth_unstable_by(mid, |a, b| {
+            centroid(*a)[axis].total_cmp(&centroid(*b)[axis])
+        });
+
+        let (left, right) = faces.split_at_mut(mid);
+        self.build_node(bounds, left, offset);
+        let right_index = self.build_node(bounds, right, offset + mid);
+
+        let node = &mut self.nodes[index];
+        node.offset = right_index;
+        node.count = 0;
+        node.axis = axis;
+
+        index
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let mut closest_so_far = t_max;
+        let mut closest = None;
+        let mut stack = vec![0];
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bbox.hit(r, t_min, closest_so_far) {
+                continue;
+            }
+
+            if node.count > 0 {
+                for face in node.offset..node.offset + node.count {
+                    let [v0, v1, v2] = self.face_vertices(face);
+                    if let Some((t, b)) =
+                        intersect_triangle(r, &v0, &v1, &v2, t_min, closest_so_far)
+                    {
+                        closest_so_far = t;
+                        closest = Some((face, t, b));
+                    }
+                }
+            } else if r.dir()[node.axis] < 0.0 {
+                // Visit the nearer child first so the far one can be culled.
+                stack.push(index + 1);
+                stack.push(node.offset);
+            } else {
+                stack.push(node.offset);
+                stack.push(index + 1);
+            }
+        }
+
+        let (face, t, b) = match closest {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        let [i0, i1, i2] = self.indices[face];
+        let normals = if self.normals.is_empty() {
+            None
+        } else {
+            Some([self.normals[i0], self.normals[i1], self.normals[i2]])
+        };
+        let uvs = if self.uvs.is_empty() {
+            None
+        } else {
+            Some([self.uvs[i0], self.uvs[i1], self.uvs[i2]])
+        };
+
+        set_triangle_hit_record(
+            r,
+            t,
+            &b,
+            &self.face_vertices(face),
+            normals.as_ref(),
+            uvs.as_ref(),
+            rec,
+        );
+        rec.color = if self.colors.is_empty() {
+            None
+        } else {
+            Some(b[0] * self.colors[i0] + b[1] * self.colors[i1] + b[2] * self.colors[i2])
+        };
+        rec.material = self.material.clone();
+
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut Aabb) -> bool {
+        match self.nodes.first() {
+            Some(root) => {
+                *output_box = root.bbox.clone();
+                true
+            }
+            None => false,
+        }
+    }
+}
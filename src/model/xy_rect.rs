@@ -2,15 +2,18 @@ use std::sync::Arc;
 
 use crate::material::material::Material;
 
-use super::{aabb::Aabb, hit::Hittable, vec3::Vec3};
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    quad::Quad,
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
 
 pub struct XyRect {
-    mp: Arc<dyn Material + Sync + Send>,
-    x0: f64,
-    x1: f64,
-    y0: f64,
-    y1: f64,
-    k: f64,
+    quad: Quad,
 }
 
 impl XyRect {
@@ -23,50 +26,30 @@ impl XyRect {
         mat: Arc<dyn Material + Sync + Send>,
     ) -> Self {
         Self {
-            x0,
-            x1,
-            y0,
-            y1,
-            k,
-            mp: mat,
+            quad: Quad::new(
+                &Point3::new(x0, y0, k),
+                &Vec3::new(x1 - x0, 0.0, 0.0),
+                &Vec3::new(0.0, y1 - y0, 0.0),
+                mat,
+            ),
         }
     }
 }
 
 impl Hittable for XyRect {
-    fn hit(
-        &self,
-        r: &super::ray::Ray,
-        t_min: f64,
-        t_max: f64,
-        rec: &mut super::hit::HitRecord,
-    ) -> bool {
-        let t = (self.k - r.origin().z()) / r.dir().z();
-        if t < t_min || t > t_max {
-            return false;
-        }
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.quad.hit(r, t_min, t_max, rec)
+    }
 
-        let x = r.origin().x() + t * r.dir().x();
-        let y = r.origin().y() + t * r.dir().y();
-        if x < self.x0 || x > self.x1 || y < self.y0 || y > self.y1 {
-            return false;
-        }
-        rec.u = (x - self.x0) / (self.x1 - self.x0);
-        rec.v = (y - self.y0) / (self.y1 - self.y0);
-        rec.t = t;
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        self.quad.bounding_box(time0, time1, output_box)
+    }
 
-        let outward_normal = Vec3::new(0.0, 0.0, 1.0);
-        rec.set_face_normal(r, &outward_normal);
-        rec.material = self.mp.clone();
-        rec.p = r.at(t);
-        return true;
+    fn pdf_value(&self, origin: &Point3, dir: &Vec3) -> f64 {
+        self.quad.pdf_value(origin, dir)
     }
 
-    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut super::aabb::Aabb) -> bool {
-        *output_box = Aabb::new(
-            Vec3::new(self.x0, self.y0, self.k - 0.0001),
-            Vec3::new(self.x1, self.y1, self.k + 0.0001),
-        );
-        return true;
+    fn random(&self, origin: &Point3) -> Vec3 {
+        self.quad.random(origin)
     }
 }
@@ -2,15 +2,18 @@ use std::sync::Arc;
 
 use crate::material::material::Material;
 
-use super::{aabb::Aabb, hit::Hittable, vec3::Vec3};
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    quad::Quad,
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
 
 pub struct XzRect {
-    mp: Arc<dyn Material + Sync + Send>,
-    x0: f64,
-    x1: f64,
-    z0: f64,
-    z1: f64,
-    k: f64,
+    quad: Quad,
 }
 
 impl XzRect {
@@ -23,51 +26,37 @@ impl XzRect {
         mat: Arc<dyn Material + Sync + Send>,
     ) -> Self {
         Self {
-            x0,
-            x1,
-            z0,
-            z1,
-            k,
-            mp: mat,
+            quad: Quad::new(
+                &Point3::new(x0, k, z0),
+                &Vec3::new(0.0, 0.0, z1 - z0),
+                &Vec3::new(x1 - x0, 0.0, 0.0),
+                mat,
+            ),
         }
     }
 }
 
 impl Hittable for XzRect {
-    fn hit(
-        &self,
-        r: &super::ray::Ray,
-        t_min: f64,
-        t_max: f64,
-        rec: &mut super::hit::HitRecord,
-    ) -> bool {
-        let t = (self.k - r.origin().y()) / r.dir().y();
-        if t < t_min || t > t_max {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        if !self.quad.hit(r, t_min, t_max, rec) {
             return false;
         }
 
-        let x = r.origin().x() + t * r.dir().x();
-        let z = r.origin().z() + t * r.dir().z();
-        if z < self.z0 || z > self.z1 || x < self.x0 || x > self.x1 {
-            return false;
-        }
-        rec.u = (x - self.x0) / (self.x1 - self.x0);
-        rec.v = (z - self.z0) / (self.z1 - self.z0);
-        rec.t = t;
+        // The quad runs along z then x to keep the normal at +y, so swap its
+        // texture coordinates back to (x, z)
+        std::mem::swap(&mut rec.u, &mut rec.v);
+        true
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        self.quad.bounding_box(time0, time1, output_box)
+    }
 
-        let outward_normal = Vec3::new(0.0, 1.0, 0.0);
-        rec.set_face_normal(r, &outward_normal);
-        rec.material = self.mp.clone();
-        rec.p = r.at(t);
-        return true;
+    fn pdf_value(&self, origin: &Point3, dir: &Vec3) -> f64 {
+        self.quad.pdf_value(origin, dir)
     }
 
-    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut super::aabb::Aabb) -> bool {
-        // The bounding box must have non-zero width in each dimension, so pad the Y dimension a small amount
-        *output_box = Aabb::new(
-            Vec3::new(self.x0, self.k - 0.0001, self.z0),
-            Vec3::new(self.x1, self.k + 0.0001, self.z1),
-        );
-        return true;
+    fn random(&self, origin: &Point3) -> Vec3 {
+        self.quad.random(origin)
     }
 }
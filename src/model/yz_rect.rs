@@ -2,15 +2,18 @@ use std::sync::Arc;
 
 use crate::material::material::Material;
 
-use super::{aabb::Aabb, hit::Hittable, vec3::Vec3};
+use super::{
+    aabb::Aabb,
+    hit::{HitRecord, Hittable},
+    quad::Quad,
+    ray::Ray,
+    vec3::Vec3,
+};
+
+use Vec3 as Point3;
 
 pub struct YzRect {
-    mp: Arc<dyn Material + Sync + Send>,
-    y0: f64,
-    y1: f64,
-    z0: f64,
-    z1: f64,
-    k: f64,
+    quad: Quad,
 }
 
 impl YzRect {
@@ -23,51 +26,30 @@ impl YzRect {
         mat: Arc<dyn Material + Sync + Send>,
     ) -> Self {
         Self {
-            y0,
-            y1,
-            z0,
-            z1,
-            k,
-            mp: mat,
+            quad: Quad::new(
+                &Point3::new(k, y0, z0),
+                &Vec3::new(0.0, y1 - y0, 0.0),
+                &Vec3::new(0.0, 0.0, z1 - z0),
+                mat,
+            ),
         }
     }
 }
 
 impl Hittable for YzRect {
-    fn hit(
-        &self,
-        r: &super::ray::Ray,
-        t_min: f64,
-        t_max: f64,
-        rec: &mut super::hit::HitRecord,
-    ) -> bool {
-        let t = (self.k - r.origin().x()) / r.dir().x();
-        if t < t_min || t > t_max {
-            return false;
-        }
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.quad.hit(r, t_min, t_max, rec)
+    }
 
-        let y = r.origin().y() + t * r.dir().y();
-        let z = r.origin().z() + t * r.dir().z();
-        if z < self.z0 || z > self.z1 || y < self.y0 || y > self.y1 {
-            return false;
-        }
-        rec.u = (y - self.y0) / (self.y1 - self.y0);
-        rec.v = (z - self.z0) / (self.z1 - self.z0);
-        rec.t = t;
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut Aabb) -> bool {
+        self.quad.bounding_box(time0, time1, output_box)
+    }
 
-        let outward_normal = Vec3::new(1.0, 0.0, 0.0);
-        rec.set_face_normal(r, &outward_normal);
-        rec.material = self.mp.clone();
-        rec.p = r.at(t);
-        return true;
+    fn pdf_value(&self, origin: &Point3, dir: &Vec3) -> f64 {
+        self.quad.pdf_value(origin, dir)
     }
 
-    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut super::aabb::Aabb) -> bool {
-        // The bounding box must have non-zero width in each dimension, so pad the X dimension a small amount
-        *output_box = Aabb::new(
-            Vec3::new(self.k - 0.0001, self.y0, self.z0),
-            Vec3::new(self.k + 0.0001, self.y1, self.z1),
-        );
-        return true;
+    fn random(&self, origin: &Point3) -> Vec3 {
+        self.quad.random(origin)
     }
 }
@@ -0,0 +1,117 @@
+use std::sync::OnceLock;
+
+use crate::{model::vec3::Vec3, util::rtweekend::random_double};
+
+// The visible range that wavelengths are sampled from, in nanometers.
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 720.0;
+
+// A Gaussian with a different width on either side of its peak.
+fn lobe(lambda: f64, mu: f64, sigma_below: f64, sigma_above: f64) -> f64 {
+    let sigma = if lambda < mu {
+        sigma_below
+    } else {
+        sigma_above
+    };
+    let t = (lambda - mu) / sigma;
+
+    (-0.5 * t * t).exp()
+}
+
+// The CIE 1931 standard observer color matching functions, using the
+// multi-lobe fit of Wyman, Sloan and Shirley (2013).
+pub fn color_matching(lambda: f64) -> Vec3 {
+    Vec3::new(
+        1.056 * lobe(lambda, 599.8, 37.9, 31.0) + 0.362 * lobe(lambda, 442.0, 16.0, 26.7)
+            - 0.065 * lobe(lambda, 501.1, 20.4, 26.2),
+        0.821 * lobe(lambda, 568.8, 46.9, 40.5) + 0.286 * lobe(lambda, 530.9, 16.3, 31.1),
+        1.217 * lobe(lambda, 437.0, 11.8, 36.0) + 0.681 * lobe(lambda, 459.0, 26.0, 13.8),
+    )
+}
+
+// CIE XYZ to linear sRGB.
+pub fn xyz_to_rgb(xyz: &Vec3) -> Vec3 {
+    Vec3::new(
+        3.2404542 * xyz.x() - 1.5371385 * xyz.y() - 0.4985314 * xyz.z(),
+        -0.9692660 * xyz.x() + 1.8760108 * xyz.y() + 0.0415560 * xyz.z(),
+        0.0556434 * xyz.x() - 0.2040259 * xyz.y() + 1.0572252 * xyz.z(),
+    )
+}
+
+// The RGB of a spectrum of constant 1 across the sampled range, by which
+// results are divided so that a flat spectrum comes out white.
+fn white() -> Vec3 {
+    static WHITE: OnceLock<Vec3> = OnceLock::new();
+    *WHITE.get_or_init(|| {
+        let n = 1000;
+        let step = (LAMBDA_MAX - LAMBDA_MIN) / n as f64;
+        let mut xyz = Vec3::default();
+        for i in 0..n {
+            xyz += color_matching(LAMBDA_MIN + (i as f64 + 0.5) * step) * step;
+        }
+
+        xyz_to_rgb(&xyz)
+    })
+}
+
+// A uniform wavelength from one of `strata` equal slices of the range, so
+// that the samples of a pixel cover the spectrum evenly.
+pub fn sample_wavelength(stratum: usize, strata: usize) -> f64 {
+    let width = (LAMBDA_MAX - LAMBDA_MIN) / strata as f64;
+    LAMBDA_MIN + width * (stratum as f64 + random_double())
+}
+
+// The RGB contribution of a radiance sample at a uniformly sampled wavelength,
+// so that averaging over many wavelengths integrates the spectrum.
+pub fn wavelength_to_rgb(lambda: f64, value: f64) -> Vec3 {
+    let rgb = xyz_to_rgb(&color_matching(lambda)) * (value * (LAMBDA_MAX - LAMBDA_MIN));
+    rgb / white()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The midpoints of n equal slices of the sampled range
+    fn wavelengths(n: usize) -> impl Iterator<Item = f64> {
+        (0..n).map(move |i| LAMBDA_MIN + (i as f64 + 0.5) / n as f64 * (LAMBDA_MAX - LAMBDA_MIN))
+    }
+
+    #[test]
+    fn test_flat_spectrum_is_white() {
+        let n = 1000;
+        let rgb = wavelengths(n).fold(Vec3::default(), |rgb, lambda| {
+            rgb + wavelength_to_rgb(lambda, 1.0) / n as f64
+        });
+        assert!((rgb - Vec3::new(1.0, 1.0, 1.0)).length() < 1e-9, "{}", rgb);
+    }
+
+    #[test]
+    fn test_color_matching() {
+        // Luminance peaks near 555 nm at about 1
+        assert!((color_matching(555.0).y() - 1.0).abs() < 0.01);
+        let peak = wavelengths(340)
+            .max_by(|a, b| color_matching(*a).y().total_cmp(&color_matching(*b).y()))
+            .unwrap();
+        assert!((peak - 555.0).abs() < 5.0, "{}", peak);
+
+        // Pure wavelengths at either end come out red and blue
+        let red = wavelength_to_rgb(650.0, 1.0);
+        assert!(red.x() > 0.0 && red.x() > 10.0 * red.z());
+        let blue = wavelength_to_rgb(450.0, 1.0);
+        assert!(blue.z() > 0.0 && blue.z() > 10.0 * blue.x());
+    }
+
+    #[test]
+    fn test_sample_wavelength() {
+        let strata = 8;
+        let width = (LAMBDA_MAX - LAMBDA_MIN) / strata as f64;
+        for stratum in 0..strata {
+            for _ in 0..100 {
+                let lambda = sample_wavelength(stratum, strata);
+                let low = LAMBDA_MIN + width * stratum as f64;
+                assert!(lambda >= low && lambda < low + width);
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+// An index of refraction that may vary with wavelength. Wavelengths are in
+// nanometers. Without one, as when rendering in RGB, the index at the
+// helium d line is used, which is what glass catalogs quote.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Ior {
+    Constant(f64),
+    // n = a + b / λ², with λ in micrometers
+    Cauchy { a: f64, b: f64 },
+    // n² = 1 + Σ b λ² / (λ² - c), with λ in micrometers and c in µm²
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+pub const D_LINE: f64 = 587.56;
+
+impl Ior {
+    // Borosilicate crown glass
+    pub const BK7: Ior = Ior::Sellmeier {
+        b: [1.03961212, 0.231792344, 1.01046945],
+        c: [0.00600069867, 0.0200179144, 103.560653],
+    };
+    // Dense flint glass, for strong dispersion
+    pub const SF11: Ior = Ior::Sellmeier {
+        b: [1.73759695, 0.313747346, 1.89878101],
+        c: [0.013188707, 0.0623068142, 155.23629],
+    };
+    pub const DIAMOND: Ior = Ior::Sellmeier {
+        b: [4.3356, 0.3306, 0.0],
+        c: [0.011236, 0.030625, 0.0],
+    };
+
+    pub fn at(&self, wavelength: Option<f64>) -> f64 {
+        let micrometers = wavelength.unwrap_or(D_LINE) / 1000.0;
+        let l2 = micrometers * micrometers;
+
+        match self {
+            Ior::Constant(n) => *n,
+            Ior::Cauchy { a, b } => a + b / l2,
+            Ior::Sellmeier { b, c } => {
+                let sum: f64 = (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_values() {
+        // Indices at the d line as quoted by the glass catalogs
+        assert!((Ior::BK7.at(Some(D_LINE)) - 1.5168).abs() < 1e-4);
+        assert!((Ior::SF11.at(Some(D_LINE)) - 1.7847).abs() < 1e-4);
+        assert!((Ior::DIAMOND.at(None) - 2.4168).abs() < 1e-3);
+
+        // Normal dispersion: blue bends more than red
+        for ior in [Ior::BK7, Ior::SF11, Ior::DIAMOND] {
+            assert!(ior.at(Some(450.0)) > ior.at(Some(650.0)));
+        }
+        assert_eq!(Ior::Constant(1.5).at(Some(450.0)), 1.5);
+    }
+}
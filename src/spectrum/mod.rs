@@ -0,0 +1,3 @@
+pub mod cie;
+pub mod ior;
+pub mod smits;
@@ -0,0 +1,97 @@
+use crate::model::vec3::Vec3;
+
+use super::cie::{LAMBDA_MAX, LAMBDA_MIN};
+
+// Smits' (1999) basis spectra, each over ten equal bins of the visible range.
+const WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+// The value at `lambda` of a smooth spectrum with the given RGB color. The
+// shared part of the channels comes from the white spectrum, then what is
+// left from the secondary and primary spectra between the remaining ones.
+pub fn rgb_to_spectrum(rgb: &Vec3, lambda: f64) -> f64 {
+    let bin = ((lambda - LAMBDA_MIN) / (LAMBDA_MAX - LAMBDA_MIN) * 10.0).clamp(0.0, 9.0) as usize;
+    let (r, g, b) = (rgb.x(), rgb.y(), rgb.z());
+
+    if r <= g && r <= b {
+        let rest = if g <= b {
+            (g - r) * CYAN[bin] + (b - g) * BLUE[bin]
+        } else {
+            (b - r) * CYAN[bin] + (g - b) * GREEN[bin]
+        };
+        r * WHITE[bin] + rest
+    } else if g <= r && g <= b {
+        let rest = if r <= b {
+            (r - g) * MAGENTA[bin] + (b - r) * BLUE[bin]
+        } else {
+            (b - g) * MAGENTA[bin] + (r - b) * RED[bin]
+        };
+        g * WHITE[bin] + rest
+    } else {
+        let rest = if r <= g {
+            (r - b) * YELLOW[bin] + (g - r) * GREEN[bin]
+        } else {
+            (g - b) * YELLOW[bin] + (r - g) * RED[bin]
+        };
+        b * WHITE[bin] + rest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spectrum::cie::wavelength_to_rgb;
+
+    use super::*;
+
+    #[test]
+    fn test_grey_is_flat() {
+        for i in 0..100 {
+            let lambda = LAMBDA_MIN + i as f64 / 99.0 * (LAMBDA_MAX - LAMBDA_MIN);
+            let value = rgb_to_spectrum(&Vec3::new(0.5, 0.5, 0.5), lambda);
+            assert!((value - 0.5).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let colors = [
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.8, 0.5, 0.3),
+            Vec3::new(0.2, 0.3, 0.9),
+            Vec3::new(0.3, 0.9, 0.6),
+        ];
+
+        // Integrating the spectrum back into RGB gives the color again
+        let n = 1000;
+        for rgb in colors {
+            let mut back = Vec3::default();
+            for i in 0..n {
+                let lambda = LAMBDA_MIN + (i as f64 + 0.5) / n as f64 * (LAMBDA_MAX - LAMBDA_MIN);
+                back += wavelength_to_rgb(lambda, rgb_to_spectrum(&rgb, lambda)) / n as f64;
+            }
+            assert!((back - rgb).length() < 0.02, "{} != {}", back, rgb);
+        }
+    }
+}
@@ -15,12 +15,18 @@ const BYTES_PER_PIXEL: i32 = 3;
 
 impl ImageTexture {
     pub fn new(filename: String) -> Self {
-        let mut components_per_pixel = BYTES_PER_PIXEL;
-
         let mut f = File::open(filename).expect("file not found");
         let mut contents = vec![];
         f.read_to_end(&mut contents);
 
+        ImageTexture::new_from_memory(&contents)
+    }
+
+    // Decodes an encoded image (PNG, JPEG, ...) that is already in memory.
+    pub fn new_from_memory(encoded: &[u8]) -> Self {
+        let components_per_pixel = BYTES_PER_PIXEL;
+        let mut contents = encoded.to_vec();
+
         let mut x: i32 = 0;
         let mut y: i32 = 0;
         let mut comp: i32 = 0;
@@ -37,11 +43,15 @@ impl ImageTexture {
             );
         }
 
+        if img.is_null() {
+            return ImageTexture::default();
+        }
+
         let width = x;
         let height = y;
         let bytes_per_scanline = BYTES_PER_PIXEL * width;
         let data = unsafe {
-            std::slice::from_raw_parts(img, (x * y * comp * components_per_pixel) as usize).to_vec()
+            std::slice::from_raw_parts(img, (x * y * components_per_pixel) as usize).to_vec()
         };
 
         unsafe {
@@ -55,6 +65,26 @@ impl ImageTexture {
             data,
         }
     }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    // Color of pixel (i, j), counting rows from the top of the image.
+    pub fn pixel(&self, i: i32, j: i32) -> Vec3 {
+        let color_scale = 1.0 / 255.0;
+        let pos = (j * self.bytes_per_scanline + i * BYTES_PER_PIXEL) as usize;
+
+        Vec3::new(
+            color_scale * self.data[pos] as f64,
+            color_scale * self.data[pos + 1] as f64,
+            color_scale * self.data[pos + 2] as f64,
+        )
+    }
 }
 
 impl Default for ImageTexture {
@@ -91,13 +121,6 @@ impl Texture for ImageTexture {
             j = self.height - 1;
         }
 
-        let color_scale = 1.0 / 255.0;
-        let pos = (j * self.bytes_per_scanline + i * BYTES_PER_PIXEL) as usize;
-
-        return Vec3::new(
-            color_scale * self.data[pos] as f64,
-            color_scale * self.data[pos + 1] as f64,
-            color_scale * self.data[pos + 2] as f64,
-        );
+        return self.pixel(i, j);
     }
 }
@@ -4,3 +4,4 @@ pub mod noise;
 pub mod perlin;
 pub mod solid_color;
 pub mod texture;
+pub mod vertex_color;
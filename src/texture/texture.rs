@@ -1,7 +1,12 @@
-use crate::model::vec3::Vec3;
+use crate::model::{hit::HitRecord, vec3::Vec3};
 
 use Vec3 as Point3;
 
 pub trait Texture {
     fn value(&self, u: f64, v: f64, p: &Point3) -> Vec3;
+
+    // Textures that need more of the hit than its uv and point override this
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        self.value(rec.u, rec.v, &rec.p)
+    }
 }
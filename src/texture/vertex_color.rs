@@ -0,0 +1,29 @@
+use crate::model::{hit::HitRecord, vec3::Vec3};
+
+use super::texture::Texture;
+
+use Vec3 as Point3;
+
+// The vertex colors a `TriangleMesh` interpolates into the hit record. Hits
+// without one, such as on a mesh without colors, fall back to `fallback`.
+pub struct VertexColorTexture {
+    fallback: Vec3,
+}
+
+impl VertexColorTexture {
+    pub fn new(fallback: &Vec3) -> Self {
+        Self {
+            fallback: *fallback,
+        }
+    }
+}
+
+impl Texture for VertexColorTexture {
+    fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Vec3 {
+        self.fallback
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        rec.color.unwrap_or(self.fallback)
+    }
+}
@@ -1 +1,2 @@
+pub mod polynomial;
 pub mod rtweekend;
@@ -0,0 +1,220 @@
+use super::rtweekend::PI;
+
+// Real roots of a*t^2 + b*t + c = 0 in ascending order. Avoids the
+// cancellation in the textbook formula when b*b is much larger than 4*a*c.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    if a == 0.0 {
+        if b == 0.0 {
+            return None;
+        }
+        let t = -c / b;
+        return Some((t, t));
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    if q == 0.0 {
+        // b and c are both zero
+        return Some((0.0, 0.0));
+    }
+
+    let t0 = q / a;
+    let t1 = c / q;
+    if t0 <= t1 {
+        Some((t0, t1))
+    } else {
+        Some((t1, t0))
+    }
+}
+
+// Real roots of a*t^3 + b*t^2 + c*t + d = 0 in ascending order.
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return match solve_quadratic(b, c, d) {
+            Some((t0, t1)) => finite_sorted(vec![t0, t1]),
+            None => Vec::new(),
+        };
+    }
+
+    // Depress to y^3 + p*y + q = 0 with t = y - b / 3a
+    let (b, c, d) = (b / a, c / a, d / a);
+    let shift = b / 3.0;
+    let p = c - b * shift;
+    let q = 2.0 * shift * shift * shift - shift * c + d;
+
+    let discriminant = 0.25 * q * q + p * p * p / 27.0;
+    let mut roots = if discriminant > 0.0 {
+        let sqrtd = discriminant.sqrt();
+        vec![(-0.5 * q + sqrtd).cbrt() + (-0.5 * q - sqrtd).cbrt()]
+    } else if p == 0.0 {
+        vec![0.0]
+    } else {
+        // Three real roots from the trigonometric form
+        let m = 2.0 * (-p / 3.0).sqrt();
+        let theta = ((3.0 * q / (p * m)).clamp(-1.0, 1.0)).acos() / 3.0;
+        (0..3)
+            .map(|k| m * (theta - 2.0 * PI * k as f64 / 3.0).cos())
+            .collect()
+    };
+
+    for root in roots.iter_mut() {
+        *root = polish(&[1.0, b, c, d], *root - shift);
+    }
+    finite_sorted(roots)
+}
+
+// Real roots of a*t^4 + b*t^3 + c*t^2 + d*t + e = 0 in ascending order, by
+// Ferrari's method. Each root is polished with Newton's method against the
+// original polynomial, which recovers most of the precision lost to the
+// resolvent cubic.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return solve_cubic(b, c, d, e);
+    }
+
+    // Depress to y^4 + p*y^2 + q*y + r = 0 with t = y - b / 4a
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+    let shift = 0.25 * b;
+    let shift2 = shift * shift;
+    let p = c - 6.0 * shift2;
+    let q = d - 2.0 * c * shift + 8.0 * shift * shift2;
+    let r = e - d * shift + c * shift2 - 3.0 * shift2 * shift2;
+
+    // The largest root of the resolvent cubic splits the quartic into two
+    // quadratics. It is positive unless q is zero.
+    let m = solve_cubic(8.0, 8.0 * p, 2.0 * p * p - 8.0 * r, -q * q)
+        .last()
+        .copied()
+        .unwrap_or(0.0);
+
+    let mut roots = Vec::with_capacity(4);
+    if m <= 1e-12 {
+        // Biquadratic: y^4 + p*y^2 + r = 0
+        if let Some((z0, z1)) = solve_quadratic(1.0, p, r) {
+            for z in [z0, z1] {
+                if z >= 0.0 {
+                    roots.push(z.sqrt());
+                    roots.push(-z.sqrt());
+                }
+            }
+        }
+    } else {
+        let s = (2.0 * m).sqrt();
+        for (sign, offset) in [(-1.0, 1.0), (1.0, -1.0)] {
+            let constant = 0.5 * p + m + offset * q / (2.0 * s);
+            if let Some((y0, y1)) = solve_quadratic(1.0, sign * s, constant) {
+                roots.push(y0);
+                roots.push(y1);
+            }
+        }
+    }
+
+    for root in roots.iter_mut() {
+        *root = polish(&[1.0, b, c, d, e], *root - shift);
+    }
+    finite_sorted(roots)
+}
+
+// Degenerate coefficients, such as a leading one so small that dividing by it
+// overflows, turn the roots into NaN or infinity. Those are no roots at all.
+fn finite_sorted(mut roots: Vec<f64>) -> Vec<f64> {
+    roots.retain(|t| t.is_finite());
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+// A few Newton steps on the polynomial with the given coefficients, highest
+// degree first. Keeps the starting point if a step makes things worse.
+fn polish(coefficients: &[f64], mut t: f64) -> f64 {
+    for _ in 0..4 {
+        let (mut f, mut df) = (0.0, 0.0);
+        for coefficient in coefficients {
+            df = df * t + f;
+            f = f * t + coefficient;
+        }
+        if df == 0.0 {
+            break;
+        }
+
+        let next = t - f / df;
+        let mut f_next = 0.0;
+        for coefficient in coefficients {
+            f_next = f_next * next + coefficient;
+        }
+        if f_next.abs() >= f.abs() {
+            break;
+        }
+        t = next;
+    }
+
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(actual: &[f64], expected: &[f64]) {
+        assert_eq!(
+            actual.len(),
+            expected.len(),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_quadratic() {
+        assert_eq!(solve_quadratic(1.0, -3.0, 2.0), Some((1.0, 2.0)));
+        assert_eq!(solve_quadratic(1.0, 0.0, 1.0), None);
+        assert_eq!(solve_quadratic(0.0, 2.0, -4.0), Some((2.0, 2.0)));
+
+        // The textbook formula loses the small root to cancellation here
+        let (t0, t1) = solve_quadratic(1.0, 1e8, 1.0).unwrap();
+        assert!((t0 + 1e8).abs() < 1e-6);
+        assert!((t1 + 1e-8).abs() < 1e-20);
+    }
+
+    #[test]
+    fn test_cubic() {
+        // (t - 1)(t - 2)(t - 3)
+        assert_roots(&solve_cubic(1.0, -6.0, 11.0, -6.0), &[1.0, 2.0, 3.0]);
+        // (t + 2)(t^2 + 1)
+        assert_roots(&solve_cubic(2.0, 4.0, 2.0, 4.0), &[-2.0]);
+        assert_roots(&solve_cubic(1.0, 0.0, 0.0, -8.0), &[2.0]);
+    }
+
+    #[test]
+    fn test_quartic() {
+        // (t - 1)(t - 2)(t - 3)(t - 4)
+        assert_roots(
+            &solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+        // (t^2 - 4)(t^2 - 9), which has no cubic or linear term
+        assert_roots(
+            &solve_quartic(1.0, 0.0, -13.0, 0.0, 36.0),
+            &[-3.0, -2.0, 2.0, 3.0],
+        );
+        // (t - 0.5)(t + 1.5)(t^2 + 1)
+        assert_roots(&solve_quartic(2.0, 2.0, 0.5, 2.0, -1.5), &[-1.5, 0.5]);
+        assert_roots(&solve_quartic(1.0, 0.0, 2.0, 0.0, 1.0), &[]);
+    }
+
+    #[test]
+    fn test_degenerate_coefficients() {
+        // Nothing sensible comes out of these, but nothing should panic
+        assert_roots(&solve_cubic(f64::NAN, 1.0, 1.0, 1.0), &[]);
+        assert_roots(&solve_cubic(0.0, f64::NAN, 1.0, 1.0), &[]);
+        assert_roots(&solve_quartic(1.0, f64::INFINITY, 1.0, 1.0, 1.0), &[]);
+        assert_roots(&solve_quartic(1e-320, 1.0, -1.0, 1.0, -1.0), &[]);
+    }
+}